@@ -8,8 +8,9 @@ use failure::Error;
 use gnuplot::{AutoOption, AxesCommon, Figure, PlotOption, Tick};
 
 use histogram::*;
-use malice::default_track_filter;
+use malice::analysis::Analysis;
 use malice::Event;
+use malice::{default_event_filter, default_track_filter};
 
 pub struct SimpleAnalysis {
     pub single_particles: Histogram,
@@ -17,8 +18,8 @@ pub struct SimpleAnalysis {
     pub multiplicity: Histogram,
 }
 
-impl SimpleAnalysis {
-    pub fn new() -> SimpleAnalysis {
+impl Analysis for SimpleAnalysis {
+    fn new() -> SimpleAnalysis {
         // eta, phi, z
         let nphi = 120 / 2;
         let neta = 120 / 2;
@@ -41,10 +42,11 @@ impl SimpleAnalysis {
                 .expect("Error building histogram"),
         }
     }
-}
 
-impl SimpleAnalysis {
-    pub fn process_event(&mut self, event: &Event) {
+    fn process_event(&mut self, event: &Event) {
+        if !default_event_filter(event) {
+            return;
+        }
         // Fill only if we have a valid primary vertex
         if let Some(prime_vtx) = event.primary_vertex() {
             for track in event
@@ -65,7 +67,18 @@ impl SimpleAnalysis {
                 .count() as f64]);
         };
     }
+}
 
+/// Needed to combine the results obtained on different threads
+impl Mergeable for SimpleAnalysis {
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        self.single_particles.merge(&other.single_particles)?;
+        self.z_vertex.merge(&other.z_vertex)?;
+        self.multiplicity.merge(&other.multiplicity)
+    }
+}
+
+impl SimpleAnalysis {
     /// Example of how one may write the results to disc
     pub fn write_to_disc(&self) -> Result<(), Error> {
         self.single_particles.dump_to_file("hybrid")?;
@@ -1,5 +1,4 @@
-use malice::default_event_filter;
-use malice::event_iterator_from_files;
+use malice::analysis;
 
 mod distribution;
 use distribution::SimpleAnalysis;
@@ -8,13 +7,8 @@ fn main() {
     let files = alice_open_data::all_files_10h()
         .expect("No data files found. Did you download with alice-open-data?");
 
-    // Create an iterator over all the events in all the given files
-    let events = event_iterator_from_files(files.into_iter());
-
-    let mut analysis = SimpleAnalysis::new();
-    for event in events.filter(default_event_filter) {
-        analysis.process_event(&event);
-    }
+    // Process the events of all the given files in parallel
+    let analysis: SimpleAnalysis = analysis::run(files).expect("Failed to read files");
     // Optionally write results to disc
     analysis.write_to_disc().unwrap();
     // Optionally compute the centrality bin edges and print them in the terminal
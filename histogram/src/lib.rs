@@ -1,10 +1,13 @@
 use std::cmp::Ordering;
 use std::fs::File;
 use std::io::prelude::*;
+use std::iter::once;
+use std::ops::Range;
 
 use bincode::serialize;
-use failure::Error;
+use failure::{format_err, Error};
 use ndarray as nd;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 // Re-export some ndarray things
@@ -13,31 +16,112 @@ pub use nd::Dimension;
 pub use nd::IxDyn;
 
 #[wasm_bindgen]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Histogram {
     edges: Vec<Vec<BinEdges>>,
+    /// Does each axis have an under- and overflow bin? If so, they
+    /// are the first and last bin of that axis in `bins` and `sumw2`.
+    flow: Vec<bool>,
     bins: nd::Array<f64, IxDyn>,
+    /// Sum of the squared weights in each bin
+    sumw2: nd::Array<f64, IxDyn>,
+    stats: Stats,
 }
 
-#[wasm_bindgen]
-impl Histogram {
-    /// Find the bin index containing `value` on `axis`
-    /// Return None if the the value is not in range
-    fn find_bin_index_axis(&self, axis: usize, value: f64) -> Option<usize> {
-        let (edges1d, value) = (&self.edges[axis], value);
-        edges1d.binary_search_by(|bin| bin.cmp_with(value)).ok()
+/// Running statistics of the filled values similar to those shown in
+/// ROOT's statistics box. Only values inside the range of all axes
+/// contribute, except for the number of entries.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Stats {
+    entries: u64,
+    sumw: f64,
+    sumw2: f64,
+    /// Sum of weight times value for each axis
+    sumwx: Vec<f64>,
+    /// Sum of weight times value squared for each axis
+    sumwx2: Vec<f64>,
+}
+
+impl Stats {
+    fn new(ndim: usize) -> Self {
+        Stats {
+            sumwx: vec![0.0; ndim],
+            sumwx2: vec![0.0; ndim],
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, other: &Stats) {
+        self.entries += other.entries;
+        self.sumw += other.sumw;
+        self.sumw2 += other.sumw2;
+        for (a, b) in self.sumwx.iter_mut().zip(&other.sumwx) {
+            *a += b;
+        }
+        for (a, b) in self.sumwx2.iter_mut().zip(&other.sumwx2) {
+            *a += b;
+        }
     }
+}
 
-    /// Find indices of bins along each axis
-    fn find_bin_indices(&self, values: &[f64]) -> Option<Vec<usize>> {
+#[wasm_bindgen]
+impl Histogram {
+    /// Find the position of the bin containing `values` in `bins`.
+    /// Values out of range end up in the under- or overflow bin of
+    /// axes which have them.
+    fn find_storage_indices(&self, values: &[f64]) -> Option<Vec<usize>> {
         (0..values.len())
-            .map(|dim| self.find_bin_index_axis(dim, values[dim]))
+            .map(|dim| {
+                let edges1d = &self.edges[dim];
+                let flow = self.flow[dim];
+                match edges1d.binary_search_by(|bin| bin.cmp_with(values[dim])) {
+                    Ok(idx) if flow => Some(idx + 1),
+                    Ok(idx) => Some(idx),
+                    Err(0) if flow => Some(0),
+                    Err(_) if flow => Some(edges1d.len() + 1),
+                    Err(_) => None,
+                }
+            })
             .collect()
     }
 
+    /// Is the position `idx` in `bins` along `axis` a bin in range
+    /// rather than an under- or overflow bin?
+    fn is_in_range(&self, axis: usize, idx: usize) -> bool {
+        !self.flow[axis] || (1..=self.edges[axis].len()).contains(&idx)
+    }
+
+    /// Fill the bin containing `values` with a weight of one
     pub fn fill(&mut self, values: &[f64]) {
-        if let Some(bin) = self.bin_mut(values) {
-            *bin += 1.0;
+        self.fill_weighted(values, 1.0);
+    }
+
+    /// Fill the bin containing `values` with the given `weight`.
+    /// Panics if `values` dimensionality is incompatible with that of
+    /// the histogram.
+    pub fn fill_weighted(&mut self, values: &[f64], weight: f64) {
+        if values.len() != self.edges.len() {
+            panic!("Expected values slice of len {}", self.edges.len());
+        }
+        self.stats.entries += 1;
+        let idx = match self.find_storage_indices(values) {
+            Some(idx) => idx,
+            None => return,
+        };
+        self.bins[idx.as_slice()] += weight;
+        self.sumw2[idx.as_slice()] += weight * weight;
+        if idx
+            .iter()
+            .enumerate()
+            .all(|(axis, &i)| self.is_in_range(axis, i))
+        {
+            let stats = &mut self.stats;
+            stats.sumw += weight;
+            stats.sumw2 += weight * weight;
+            for (axis, x) in values.iter().enumerate() {
+                stats.sumwx[axis] += weight * x;
+                stats.sumwx2[axis] += weight * x * x;
+            }
         }
     }
 
@@ -51,17 +135,71 @@ impl Histogram {
         self.edges[axis].iter().map(|bin| bin.width()).collect()
     }
 
+    /// The content of each bin without under- and overflow bins
     pub fn values(&self) -> Box<[f64]> {
-        self.bins.clone().into_raw_vec().into_boxed_slice()
+        self.in_range(&self.bins).iter().copied().collect()
+    }
+
+    /// The content of each bin including the under- and overflow bins
+    /// of the axes which have them
+    pub fn values_with_flow(&self) -> Box<[f64]> {
+        self.bins.iter().copied().collect()
+    }
+
+    /// The sum of the squared weights in each bin
+    pub fn sumw2(&self) -> Box<[f64]> {
+        self.in_range(&self.sumw2).iter().copied().collect()
+    }
+
+    /// The statistical uncertainty of each bin; i.e. the square root
+    /// of the sum of the squared weights
+    pub fn errors(&self) -> Box<[f64]> {
+        self.in_range(&self.sumw2)
+            .iter()
+            .map(|w2| w2.sqrt())
+            .collect()
+    }
+
+    /// Sum of the content of all bins in range
+    pub fn integral(&self) -> f64 {
+        self.in_range(&self.bins).sum()
+    }
+
+    /// Number of times this histogram was filled, including values
+    /// out of range
+    pub fn entries(&self) -> u64 {
+        self.stats.entries
+    }
+
+    /// Weighted mean of the values filled in range along `axis`
+    pub fn mean(&self, axis: usize) -> f64 {
+        if self.stats.sumw == 0.0 {
+            return 0.0;
+        }
+        self.stats.sumwx[axis] / self.stats.sumw
+    }
+
+    /// Weighted standard deviation of the values filled in range
+    /// along `axis`
+    pub fn std_dev(&self, axis: usize) -> f64 {
+        if self.stats.sumw == 0.0 {
+            return 0.0;
+        }
+        let mean = self.mean(axis);
+        (self.stats.sumwx2[axis] / self.stats.sumw - mean * mean)
+            .max(0.0)
+            .sqrt()
     }
 
     /// Sum all bins along `axis` returning a new histogram with
-    /// reduced dimensionality.
+    /// reduced dimensionality. The under- and overflow bins of `axis`
+    /// are included in the sum.
     ///
     /// Panics if `axis` is out of bounds.
     pub fn sum_axis(&self, axis: u32) -> Histogram {
         let axis = axis as usize;
         let bins = self.bins.sum_axis(Axis(axis));
+        let sumw2 = self.sumw2.sum_axis(Axis(axis));
         let edges = self
             .edges
             .iter()
@@ -74,15 +212,32 @@ impl Histogram {
                 }
             })
             .collect();
-        Histogram { bins, edges }
+        let mut flow = self.flow.clone();
+        flow.remove(axis);
+        let mut h = Histogram {
+            stats: Stats::new(flow.len()),
+            bins,
+            sumw2,
+            edges,
+            flow,
+        };
+        h.reset_stats(self.stats.entries);
+        h
     }
 
     /// Multiply the values inside this this histogram by a scalar
-    /// value.
+    /// value. The uncertainties are scaled accordingly.
     #[allow(clippy::should_implement_trait)]
     pub fn mul(self, factor: f64) -> Histogram {
+        let mut stats = self.stats;
+        stats.sumw *= factor;
+        stats.sumw2 *= factor * factor;
+        stats.sumwx.iter_mut().for_each(|x| *x *= factor);
+        stats.sumwx2.iter_mut().for_each(|x| *x *= factor);
         Histogram {
             bins: self.bins * factor,
+            sumw2: self.sumw2 * (factor * factor),
+            stats,
             ..self
         }
     }
@@ -92,37 +247,327 @@ impl Histogram {
 impl Histogram {
     /// Get a mutable reference to the bin including `values`. Panics
     /// if `values` dimensionality is incompatible with that of the
-    /// histogram.
+    /// histogram. Note that the sum of squared weights and the
+    /// statistics are not updated; use `fill_weighted` for that.
     pub fn bin_mut(&mut self, values: &[f64]) -> Option<&mut f64> {
         if values.len() != self.edges.len() {
             panic!("Expected values slice of len {}", self.edges.len());
         }
-        self.find_bin_indices(values)
+        self.find_storage_indices(values)
             .and_then(move |idx| self.bins.get_mut(idx.as_slice()))
     }
 
-    /// Dump histogram (without edges) to a file of `name`.
+    /// View of `a` without the under- and overflow bins
+    fn in_range<'a>(&self, a: &'a nd::ArrayD<f64>) -> nd::ArrayViewD<'a, f64> {
+        a.slice_each_axis(|ax| in_range_slice(&self.flow, ax))
+    }
+
+    /// Recompute the statistics from the bin contents using the bin
+    /// centers; e.g. after the bins were modified by an operation
+    fn reset_stats(&mut self, entries: u64) {
+        let mut stats = Stats::new(self.edges.len());
+        stats.entries = entries;
+        let centers: Vec<_> = (0..self.edges.len()).map(|ax| self.centers(ax)).collect();
+        let bins = self.in_range(&self.bins);
+        let sumw2 = self.in_range(&self.sumw2);
+        for ((idx, &w), &w2) in bins.indexed_iter().zip(sumw2.iter()) {
+            stats.sumw += w;
+            stats.sumw2 += w2;
+            for (axis, &i) in idx.slice().iter().enumerate() {
+                let x = centers[axis][i];
+                stats.sumwx[axis] += w * x;
+                stats.sumwx2[axis] += w * x * x;
+            }
+        }
+        self.stats = stats;
+    }
+
+    /// Dump histogram (without edges and under- and overflow bins) to
+    /// a file of `name`.
     /// The binary layout is:
     /// `(array_version: u8, ndim: u64, shape: [ndim; u64], a_size: u64, a: [a_size; A])`
     pub fn dump_to_file(&self, name: &str) -> Result<(), Error> {
-        let buf = serialize(&self.bins)?;
+        let buf = serialize(&self.in_range(&self.bins))?;
         let mut f = File::create(name)?;
         f.write_all(buf.as_slice())?;
         Ok(())
     }
+
+    /// Fail if `other` has different bin edges than this histogram
+    fn check_compatible(&self, other: &Histogram) -> Result<(), Error> {
+        if self.edges != other.edges || self.flow != other.flow {
+            return Err(format_err!("Histograms have different bin edges"));
+        }
+        Ok(())
+    }
+
+    /// Combine this histogram bin by bin with `other`. `op` receives
+    /// the content and sum of squared weights of both bins and
+    /// returns those of the resulting bin.
+    fn zip_with<F>(&self, other: &Histogram, op: F) -> Result<Histogram, Error>
+    where
+        F: Fn(f64, f64, f64, f64) -> (f64, f64),
+    {
+        self.check_compatible(other)?;
+        let mut bins = self.bins.clone();
+        let mut sumw2 = self.sumw2.clone();
+        nd::Zip::from(&mut bins)
+            .and(&mut sumw2)
+            .and(&other.bins)
+            .and(&other.sumw2)
+            .for_each(|a, a_w2, &b, &b_w2| {
+                let (c, c_w2) = op(*a, *a_w2, b, b_w2);
+                *a = c;
+                *a_w2 = c_w2;
+            });
+        let mut h = Histogram {
+            edges: self.edges.clone(),
+            flow: self.flow.clone(),
+            bins,
+            sumw2,
+            stats: Stats::default(),
+        };
+        h.reset_stats(self.stats.entries);
+        Ok(h)
+    }
+
+    /// Add `other` to this histogram bin by bin. The uncertainties
+    /// are added in quadrature. Fails if the bin edges differ.
+    pub fn add(&self, other: &Histogram) -> Result<Histogram, Error> {
+        let mut sum = self.clone();
+        sum.merge(other)?;
+        Ok(sum)
+    }
+
+    /// Subtract `other` from this histogram bin by bin. The
+    /// uncertainties are added in quadrature. Fails if the bin edges
+    /// differ.
+    pub fn subtract(&self, other: &Histogram) -> Result<Histogram, Error> {
+        self.zip_with(other, |a, a_w2, b, b_w2| (a - b, a_w2 + b_w2))
+    }
+
+    /// Multiply this histogram with `other` bin by bin, assuming that
+    /// both are uncorrelated. Fails if the bin edges differ.
+    pub fn multiply(&self, other: &Histogram) -> Result<Histogram, Error> {
+        self.zip_with(other, |a, a_w2, b, b_w2| {
+            (a * b, a_w2 * b * b + b_w2 * a * a)
+        })
+    }
+
+    /// Divide this histogram by `other` bin by bin, assuming that
+    /// both are uncorrelated. Bins where `other` is zero are set to
+    /// zero. Fails if the bin edges differ.
+    pub fn divide(&self, other: &Histogram) -> Result<Histogram, Error> {
+        self.zip_with(other, |a, a_w2, b, b_w2| {
+            if b == 0.0 {
+                (0.0, 0.0)
+            } else {
+                // (σ_c / c)² = (σ_a / a)² + (σ_b / b)² for c = a / b
+                (a / b, (a_w2 * b * b + b_w2 * a * a) / b.powi(4))
+            }
+        })
+    }
+
+    /// Keep only the bins whose centers lie in the given ranges. The
+    /// ranges are given for each axis; `None` keeps the entire axis.
+    /// Axes which are restricted to a range lose their under- and
+    /// overflow bins.
+    pub fn slice(&self, ranges: &[Option<Range<f64>>]) -> Result<Histogram, Error> {
+        if ranges.len() != self.edges.len() {
+            return Err(format_err!("Expected {} ranges", self.edges.len()));
+        }
+        let mut h = self.clone();
+        for (axis, range) in ranges.iter().enumerate() {
+            if let Some(range) = range {
+                h = h.slice_axis(axis, range)?;
+            }
+        }
+        h.reset_stats(self.stats.entries);
+        Ok(h)
+    }
+
+    /// Keep only the bins of `axis` whose centers lie in `range`
+    fn slice_axis(&self, axis: usize, range: &Range<f64>) -> Result<Histogram, Error> {
+        let centers = self.centers(axis);
+        let first = centers.iter().position(|c| range.contains(c));
+        let last = centers.iter().rposition(|c| range.contains(c));
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(format_err!("No bins of axis {} in {:?}", axis, range)),
+        };
+        let offset = self.flow[axis] as usize;
+        let slice = nd::Slice::from(first + offset..=last + offset);
+        let mut h = self.clone();
+        h.bins = self.bins.slice_axis(Axis(axis), slice).to_owned();
+        h.sumw2 = self.sumw2.slice_axis(Axis(axis), slice).to_owned();
+        h.edges[axis] = self.edges[axis][first..=last].to_vec();
+        h.flow[axis] = false;
+        Ok(h)
+    }
+
+    /// Project this histogram onto `axis` by summing over all other
+    /// axes. Only the bins whose centers lie in `ranges` are included;
+    /// see `slice`. The under- and overflow bins of axes without a
+    /// range are included in the sum.
+    pub fn project(&self, axis: usize, ranges: &[Option<Range<f64>>]) -> Result<Histogram, Error> {
+        if axis >= self.edges.len() {
+            return Err(format_err!("Histogram has no axis {}", axis));
+        }
+        let mut h = self.slice(ranges)?;
+        for other in (0..self.edges.len()).rev().filter(|other| *other != axis) {
+            h = h.sum_axis(other as u32);
+        }
+        Ok(h)
+    }
+
+    /// Merge every `factor` consecutive bins of `axis`. Fails if the
+    /// number of bins is not divisible by `factor`.
+    pub fn rebin(&self, axis: usize, factor: usize) -> Result<Histogram, Error> {
+        let nbins = self.edges[axis].len();
+        if factor == 0 || !nbins.is_multiple_of(factor) {
+            return Err(format_err!(
+                "Cannot merge {} bins in groups of {}",
+                nbins,
+                factor
+            ));
+        }
+        let edges: Vec<_> = self.edges_of(axis).into_iter().step_by(factor).collect();
+        self.rebin_to(axis, &edges)
+    }
+
+    /// Rebin `axis` to the given edges. The new edges must be a
+    /// subset of the current edges including the first and last one.
+    pub fn rebin_to(&self, axis: usize, edges: &[f64]) -> Result<Histogram, Error> {
+        let old = self.edges_of(axis);
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+        // Position of each new edge among the old ones
+        let positions = edges
+            .iter()
+            .map(|e| old.iter().position(|o| close(*o, *e)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format_err!("New edges must be a subset of the current edges"))?;
+        let is_valid = positions.len() >= 2
+            && positions[0] == 0
+            && positions[positions.len() - 1] == old.len() - 1
+            && positions.windows(2).all(|w| w[0] < w[1]);
+        if !is_valid {
+            return Err(format_err!(
+                "New edges must be increasing and span the range of the current edges"
+            ));
+        }
+        // Position of each old bin (including flow bins) in the new bins
+        let flow = self.flow[axis];
+        let n_new = positions.len() - 1;
+        let offset = flow as usize;
+        let mut targets = vec![];
+        if flow {
+            targets.push(0);
+        }
+        for (new, w) in positions.windows(2).enumerate() {
+            targets.extend((w[0]..w[1]).map(|_| new + offset));
+        }
+        if flow {
+            targets.push(n_new + 1);
+        }
+        let rebin = |a: &nd::ArrayD<f64>| {
+            let mut shape = a.shape().to_vec();
+            shape[axis] = n_new + 2 * offset;
+            let mut out = nd::ArrayD::zeros(IxDyn(&shape));
+            for (old, &new) in targets.iter().enumerate() {
+                let mut lane = out.index_axis_mut(Axis(axis), new);
+                lane += &a.index_axis(Axis(axis), old);
+            }
+            out
+        };
+        let mut h = self.clone();
+        h.bins = rebin(&self.bins);
+        h.sumw2 = rebin(&self.sumw2);
+        let new_edges: Vec<_> = positions.iter().map(|&p| old[p]).collect();
+        h.edges[axis] = edges_to_bins(&new_edges);
+        Ok(h)
+    }
+
+    /// All edges of `axis` from the lower edge of the first bin to
+    /// the upper edge of the last bin
+    fn edges_of(&self, axis: usize) -> Vec<f64> {
+        let bins = &self.edges[axis];
+        once(bins[0].lower)
+            .chain(bins.iter().map(|bin| bin.upper))
+            .collect()
+    }
+
+    /// Scale this histogram such that the content of all bins in
+    /// range sums up to one. Fails if the histogram is empty.
+    pub fn normalize(&self) -> Result<Histogram, Error> {
+        let integral = self.integral();
+        if integral == 0.0 {
+            return Err(format_err!("Cannot normalize a histogram without content"));
+        }
+        Ok(self.clone().mul(1.0 / integral))
+    }
+
+    /// Divide the content of each bin by its width (or area, volume,
+    /// etc. for several axes) turning counts into densities. The
+    /// under- and overflow bins are not changed.
+    pub fn divide_by_bin_width(&self) -> Histogram {
+        let widths: Vec<_> = (0..self.edges.len()).map(|ax| self.widths(ax)).collect();
+        let mut h = self.clone();
+        for (a, power) in [(&mut h.bins, 1), (&mut h.sumw2, 2)] {
+            let mut a = a.slice_each_axis_mut(|ax| in_range_slice(&self.flow, ax));
+            for (idx, v) in a.indexed_iter_mut() {
+                let width: f64 = idx
+                    .slice()
+                    .iter()
+                    .enumerate()
+                    .map(|(ax, &i)| widths[ax][i])
+                    .product();
+                *v /= width.powi(power);
+            }
+        }
+        h
+    }
+}
+
+/// Slice of `ax` without the under- and overflow bins if it has them
+fn in_range_slice(flow: &[bool], ax: nd::AxisDescription) -> nd::Slice {
+    if flow[ax.axis.index()] {
+        nd::Slice::new(1, Some(-1), 1)
+    } else {
+        nd::Slice::from(..)
+    }
+}
+
+/// Partial results of an analysis which can be combined into the
+/// result of the entire analysis; e.g. histograms filled from
+/// different files or on different threads.
+pub trait Mergeable {
+    /// Add the content of `other` to `self`. Fails if the two are not
+    /// compatible, e.g. histograms with different binning.
+    fn merge(&mut self, other: &Self) -> Result<(), Error>;
+}
+
+impl Mergeable for Histogram {
+    fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        self.check_compatible(other)?;
+        self.bins += &other.bins;
+        self.sumw2 += &other.sumw2;
+        self.stats.add(&other.stats);
+        Ok(())
+    }
 }
 
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct HistogramBuilder {
     edges: Vec<Vec<f64>>,
+    flow: Vec<bool>,
 }
 
 #[wasm_bindgen]
 impl HistogramBuilder {
     #[wasm_bindgen(constructor)]
     pub fn new() -> HistogramBuilder {
-        HistogramBuilder { edges: Vec::new() }
+        HistogramBuilder::default()
     }
 
     /// Create a new n-dimensional histogram
@@ -135,10 +580,21 @@ impl HistogramBuilder {
         if edges.is_empty() {
             return None;
         }
-        let shape: Vec<_> = edges.iter().map(|edges| edges.len()).collect();
+        let shape: Vec<_> = edges
+            .iter()
+            .zip(&self.flow)
+            .map(|(edges, flow)| edges.len() + if *flow { 2 } else { 0 })
+            .collect();
 
         let bins = nd::ArrayD::zeros(IxDyn(shape.as_ref()));
-        Some(Histogram { bins, edges })
+        let sumw2 = bins.clone();
+        Some(Histogram {
+            bins,
+            sumw2,
+            stats: Stats::new(edges.len()),
+            edges,
+            flow: self.flow.clone(),
+        })
     }
 
     pub fn add_equal_width_axis(mut self, nbins: usize, min: f64, max: f64) -> HistogramBuilder {
@@ -148,16 +604,31 @@ impl HistogramBuilder {
                 .map(|i| min + width * i as f64)
                 .collect::<Vec<f64>>(),
         );
+        self.flow.push(false);
         self
     }
 
     pub fn add_variable_width_axis(mut self, edges1d: &[f64]) -> HistogramBuilder {
         self.edges.push(edges1d.to_vec());
+        self.flow.push(false);
+        self
+    }
+
+    /// Add an under- and overflow bin to the axis added last. Values
+    /// out of the range of that axis are then counted in these bins
+    /// instead of being dropped.
+    ///
+    /// Panics if no axis was added yet.
+    pub fn with_flow_bins(mut self) -> HistogramBuilder {
+        *self
+            .flow
+            .last_mut()
+            .expect("Add an axis before adding under- and overflow bins") = true;
         self
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 struct BinEdges {
     lower: f64,
     upper: f64,
@@ -205,13 +676,25 @@ mod tests {
             .add_equal_width_axis(1, 0., 1.)
             .build()
             .unwrap();
-        assert_eq!(h.find_bin_indices(&[-1.0, -1.0]), None, "Wrong indices");
-        assert_eq!(h.find_bin_indices(&[2.0, 2.0]), None, "Wrong indices");
+        assert_eq!(h.find_storage_indices(&[-1.0, -1.0]), None, "Wrong indices");
+        assert_eq!(h.find_storage_indices(&[2.0, 2.0]), None, "Wrong indices");
         assert_eq!(
-            h.find_bin_indices(&[0.5, 0.5]),
+            h.find_storage_indices(&[0.5, 0.5]),
             Some(vec![0, 0]),
             "Wrong indices"
         );
+
+        // Indices along axes with flow bins are shifted by the underflow bin
+        let h = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 2.)
+            .with_flow_bins()
+            .add_equal_width_axis(2, 0., 2.)
+            .build()
+            .unwrap();
+        assert_eq!(h.find_storage_indices(&[1.5, 1.5]), Some(vec![2, 1]));
+        assert_eq!(h.find_storage_indices(&[-1.0, 0.5]), Some(vec![0, 0]));
+        assert_eq!(h.find_storage_indices(&[3.0, 0.5]), Some(vec![3, 0]));
+        assert_eq!(h.find_storage_indices(&[0.5, 3.0]), None);
     }
 
     #[test]
@@ -277,6 +760,242 @@ mod tests {
         assert_eq!(h.centers(0), &[-0.5, 0.5]);
     }
 
+    #[test]
+    fn merge() {
+        let builder = HistogramBuilder::new().add_equal_width_axis(2, 0., 2.);
+        let mut a = builder.build().unwrap();
+        let mut b = builder.build().unwrap();
+        a.fill(&[0.5]);
+        b.fill(&[0.5]);
+        b.fill(&[1.5]);
+        a.merge(&b).unwrap();
+        assert_eq!(a.bins, nd::arr1(&[2., 1.]).into_dyn());
+
+        let c = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 4.)
+            .build()
+            .unwrap();
+        assert!(a.merge(&c).is_err());
+        // Serialized partial results can be merged later on
+        let a: Histogram = bincode::deserialize(&serialize(&a).unwrap()).unwrap();
+        assert_eq!(a.values().as_ref(), &[2., 1.]);
+    }
+
+    #[test]
+    fn weights() {
+        let builder = HistogramBuilder::new().add_equal_width_axis(2, 0., 2.);
+        let mut a = builder.build().unwrap();
+        a.fill_weighted(&[0.5], 2.0);
+        a.fill_weighted(&[0.5], 1.0);
+        a.fill(&[1.5]);
+        // Out of range
+        a.fill_weighted(&[5.], 3.0);
+        assert_eq!(a.values().as_ref(), &[3., 1.]);
+        assert_eq!(a.sumw2().as_ref(), &[5., 1.]);
+        assert_eq!(a.errors().as_ref(), &[5f64.sqrt(), 1.]);
+
+        let scaled = a.clone().mul(2.0);
+        assert_eq!(scaled.values().as_ref(), &[6., 2.]);
+        assert_eq!(scaled.errors().as_ref(), &[2. * 5f64.sqrt(), 2.]);
+
+        let mut b = builder.build().unwrap();
+        b.fill_weighted(&[0.5], 3.0);
+        let sum = a.add(&b).unwrap();
+        assert_eq!(sum.values().as_ref(), &[6., 1.]);
+        assert_eq!(sum.sumw2().as_ref(), &[14., 1.]);
+
+        let ratio = a.divide(&b).unwrap();
+        assert_eq!(ratio.values().as_ref(), &[1., 0.]);
+        // (5 * 9 + 9 * 9) / 3^4
+        assert_eq!(ratio.sumw2().as_ref(), &[126. / 81., 0.]);
+
+        let other = HistogramBuilder::new()
+            .add_equal_width_axis(3, 0., 2.)
+            .build()
+            .unwrap();
+        assert!(a.add(&other).is_err());
+        assert!(a.divide(&other).is_err());
+    }
+
+    #[test]
+    fn sum_axis_sumw2() {
+        let mut h = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 2.)
+            .add_equal_width_axis(2, 0., 2.)
+            .build()
+            .unwrap();
+        h.fill_weighted(&[0.5, 0.5], 2.0);
+        h.fill_weighted(&[0.5, 1.5], 3.0);
+        let projected = h.sum_axis(1);
+        assert_eq!(projected.values().as_ref(), &[5., 0.]);
+        assert_eq!(projected.sumw2().as_ref(), &[13., 0.]);
+    }
+
+    #[test]
+    fn flow_bins() {
+        let mut h = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 2.)
+            .with_flow_bins()
+            .add_equal_width_axis(2, 0., 2.)
+            .build()
+            .unwrap();
+        h.fill(&[-5., 0.5]);
+        h.fill(&[5., 1.5]);
+        h.fill(&[0.5, 0.5]);
+        // Dropped since the second axis has no flow bins
+        h.fill(&[0.5, 5.]);
+        assert_eq!(h.entries(), 4);
+        assert_eq!(
+            h.bins,
+            nd::arr2(&[[1., 0.], [1., 0.], [0., 0.], [0., 1.]]).into_dyn()
+        );
+        assert_eq!(h.values().as_ref(), &[1., 0., 0., 0.]);
+        assert_eq!(h.values_with_flow().len(), 8);
+        // Values in the flow bins do not contribute to the statistics
+        assert_eq!((h.mean(0), h.mean(1)), (0.5, 0.5));
+        // The flow bins of the first axis are included in the sum
+        assert_eq!(h.sum_axis(0).values().as_ref(), &[2., 1.]);
+        assert_eq!(h.sum_axis(1).values().as_ref(), &[1., 0.]);
+        assert_eq!(h.sum_axis(1).values_with_flow().as_ref(), &[1., 1., 0., 1.]);
+    }
+
+    #[test]
+    fn statistics() {
+        let mut h = HistogramBuilder::new()
+            .add_equal_width_axis(10, 0., 10.)
+            .build()
+            .unwrap();
+        assert_eq!(h.mean(0), 0.0);
+        h.fill(&[1.0]);
+        h.fill(&[3.0]);
+        h.fill_weighted(&[2.0], 2.0);
+        // Out of range values do not contribute
+        h.fill(&[20.0]);
+        assert_eq!(h.entries(), 4);
+        assert_eq!(h.mean(0), 2.0);
+        assert_eq!(h.std_dev(0), 0.5f64.sqrt());
+
+        let mut other = h.clone();
+        other.merge(&h).unwrap();
+        assert_eq!(other.entries(), 8);
+        assert_eq!(other.mean(0), 2.0);
+        assert_eq!(h.clone().mul(3.0).mean(0), 2.0);
+
+        // Computed from the bin centers after projections
+        let mut h2 = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 2.)
+            .add_equal_width_axis(2, 0., 2.)
+            .build()
+            .unwrap();
+        h2.fill(&[0.2, 0.2]);
+        h2.fill(&[1.2, 0.2]);
+        assert_eq!(h2.mean(0), 0.7);
+        assert_eq!(h2.sum_axis(1).mean(0), 1.0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let builder = HistogramBuilder::new().add_equal_width_axis(2, 0., 2.);
+        let mut a = builder.build().unwrap();
+        let mut b = builder.build().unwrap();
+        a.fill_weighted(&[0.5], 4.0);
+        a.fill_weighted(&[1.5], 2.0);
+        b.fill_weighted(&[0.5], 1.0);
+        b.fill_weighted(&[1.5], 3.0);
+
+        let diff = a.subtract(&b).unwrap();
+        assert_eq!(diff.values().as_ref(), &[3., -1.]);
+        assert_eq!(diff.sumw2().as_ref(), &[17., 13.]);
+
+        let prod = a.multiply(&b).unwrap();
+        assert_eq!(prod.values().as_ref(), &[4., 6.]);
+        // σa² b² + σb² a²
+        assert_eq!(prod.sumw2().as_ref(), &[16. + 16., 4. * 9. + 9. * 4.]);
+
+        let other = HistogramBuilder::new()
+            .add_equal_width_axis(2, 0., 1.)
+            .build()
+            .unwrap();
+        assert!(a.subtract(&other).is_err());
+        assert!(a.multiply(&other).is_err());
+    }
+
+    fn filled_2d() -> Histogram {
+        let mut h = HistogramBuilder::new()
+            .add_equal_width_axis(4, 0., 4.)
+            .add_equal_width_axis(2, 0., 2.)
+            .with_flow_bins()
+            .build()
+            .unwrap();
+        for x in 0..4 {
+            h.fill_weighted(&[x as f64 + 0.5, 0.5], 1.0);
+            h.fill_weighted(&[x as f64 + 0.5, 1.5], 10.0);
+        }
+        h.fill_weighted(&[0.5, 5.0], 100.0);
+        h
+    }
+
+    #[test]
+    fn slice_and_project() {
+        let h = filled_2d();
+        let sliced = h.slice(&[Some(1.0..3.0), Some(1.0..2.0)]).unwrap();
+        assert_eq!(sliced.centers(0), &[1.5, 2.5]);
+        assert_eq!(sliced.values().as_ref(), &[10., 10.]);
+        // The flow bins of a sliced axis are dropped
+        assert_eq!(sliced.values_with_flow().len(), 2);
+        assert!(h.slice(&[Some(10.0..20.0), None]).is_err());
+        assert!(h.slice(&[None]).is_err());
+
+        // Including the overflow bin of the second axis
+        let px = h.project(0, &[None, None]).unwrap();
+        assert_eq!(px.values().as_ref(), &[111., 11., 11., 11.]);
+        let px = h.project(0, &[None, Some(0.0..1.0)]).unwrap();
+        assert_eq!(px.values().as_ref(), &[1., 1., 1., 1.]);
+        let py = h.project(1, &[Some(0.0..2.0), None]).unwrap();
+        assert_eq!(py.values_with_flow().as_ref(), &[0., 2., 20., 100.]);
+        assert!(h.project(2, &[None, None]).is_err());
+    }
+
+    #[test]
+    fn rebinning() {
+        let h = filled_2d();
+        let r = h.rebin(0, 2).unwrap();
+        assert_eq!(r.centers(0), &[1.0, 3.0]);
+        assert_eq!(
+            r.values_with_flow().as_ref(),
+            &[0., 2., 20., 100., 0., 2., 20., 0.]
+        );
+        assert_eq!(r.sumw2().as_ref(), &[2., 200., 2., 200.]);
+        assert!(h.rebin(0, 3).is_err());
+
+        let r = h.rebin_to(0, &[0., 1., 4.]).unwrap();
+        assert_eq!(r.widths(0), &[1., 3.]);
+        assert_eq!(r.values().as_ref(), &[1., 10., 3., 30.]);
+        assert_eq!(r.entries(), h.entries());
+        // Not a subset of the current edges or not spanning the range
+        assert!(h.rebin_to(0, &[0., 1.5, 4.]).is_err());
+        assert!(h.rebin_to(0, &[0., 2.]).is_err());
+        assert!(h.rebin_to(0, &[0., 3., 2., 4.]).is_err());
+    }
+
+    #[test]
+    fn normalization() {
+        let mut h = HistogramBuilder::new()
+            .add_variable_width_axis(&[0., 1., 3.])
+            .build()
+            .unwrap();
+        assert!(h.normalize().is_err());
+        h.fill_weighted(&[0.5], 2.0);
+        h.fill_weighted(&[2.0], 2.0);
+        assert_eq!(h.integral(), 4.0);
+        let n = h.normalize().unwrap();
+        assert_eq!(n.values().as_ref(), &[0.5, 0.5]);
+        assert_eq!(n.integral(), 1.0);
+        let d = h.divide_by_bin_width();
+        assert_eq!(d.values().as_ref(), &[2., 1.]);
+        assert_eq!(d.errors().as_ref(), &[2., 1.]);
+    }
+
     #[test]
     fn bin_edges() {
         let be = BinEdges {
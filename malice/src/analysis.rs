@@ -0,0 +1,177 @@
+//! Run analyses over many files and combine their partial results.
+//!
+//! An analysis is split into independent parts by running it over
+//! each file separately (the "map" step). The partial results can be
+//! written to disc with `process_file`, e.g. by separate jobs on
+//! different machines, and later be combined with `merge_partials`
+//! (the "reduce" step). `map_reduce` does both steps in one go and
+//! `run` processes all files without writing partial results.
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use failure::{format_err, Error};
+use histogram::Mergeable;
+use root_io::Source;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{par_process, Event};
+
+/// An analysis which processes one event at a time and whose
+/// (partial) results can be merged
+pub trait Analysis: Mergeable + Send {
+    /// A new analysis which has not seen any events yet
+    fn new() -> Self;
+
+    /// Add the given event to this analysis
+    fn process_event(&mut self, event: &Event);
+}
+
+/// Merge function for `par_process`. Partial results of the same
+/// analysis are always compatible.
+fn merge<A: Analysis>(mut a: A, b: A) -> A {
+    a.merge(&b)
+        .expect("Failed to merge partial results of the same analysis");
+    a
+}
+
+/// Run the analysis `A` over all events of the given files. The
+/// events are processed in parallel; see `par_process`.
+pub fn run<A, I, S>(sources: I) -> Result<A, Error>
+where
+    A: Analysis,
+    I: IntoIterator<Item = S> + Send + 'static,
+    S: Into<Source> + Send,
+{
+    par_process(sources, A::new, A::process_event, merge)
+}
+
+/// Run the analysis `A` over all events of the given file and write
+/// the partial result to `out`
+pub fn process_file<A, S>(source: S, out: &Path) -> Result<(), Error>
+where
+    A: Analysis + Serialize,
+    S: Into<Source> + Send + 'static,
+{
+    let partial: A = run(vec![source])?;
+    write_partial(&partial, out)
+}
+
+/// Write a partial result to the file at `path`
+pub fn write_partial<A: Serialize>(partial: &A, path: &Path) -> Result<(), Error> {
+    let f = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(f, partial)?;
+    Ok(())
+}
+
+/// Read a partial result written with `write_partial`
+pub fn read_partial<A: DeserializeOwned>(path: &Path) -> Result<A, Error> {
+    let f = BufReader::new(File::open(path)?);
+    Ok(bincode::deserialize_from(f)?)
+}
+
+/// Merge the partial results stored in the given files
+pub fn merge_partials<A, I, P>(paths: I) -> Result<A, Error>
+where
+    A: Analysis + DeserializeOwned,
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut result = A::new();
+    for path in paths {
+        let partial: A = read_partial(path.as_ref())?;
+        result.merge(&partial).map_err(|e| {
+            format_err!(
+                "Failed to merge partial result {}: {}",
+                path.as_ref().display(),
+                e
+            )
+        })?;
+    }
+    Ok(result)
+}
+
+/// Run the analysis `A` over each file separately and write the
+/// partial results to `work_dir` as `partial-<n>.bin` where `n` is the
+/// position of the file in `sources`. Then merge all partial results.
+/// The partial results remain in `work_dir` so that they can be
+/// merged with those of other jobs.
+pub fn map_reduce<A, I, S>(sources: I, work_dir: &Path) -> Result<A, Error>
+where
+    A: Analysis + Serialize + DeserializeOwned,
+    I: IntoIterator<Item = S>,
+    S: Into<Source> + Send + 'static,
+{
+    fs::create_dir_all(work_dir)?;
+    let mut partials: Vec<PathBuf> = vec![];
+    for (n, source) in sources.into_iter().enumerate() {
+        let path = work_dir.join(format!("partial-{}.bin", n));
+        process_file::<A, _>(source, &path)?;
+        partials.push(path);
+    }
+    merge_partials(&partials)
+}
+
+#[cfg(test)]
+mod tests {
+    use histogram::{Histogram, HistogramBuilder};
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Multiplicity(Histogram);
+
+    impl Mergeable for Multiplicity {
+        fn merge(&mut self, other: &Self) -> Result<(), Error> {
+            self.0.merge(&other.0)
+        }
+    }
+
+    impl Analysis for Multiplicity {
+        fn new() -> Self {
+            Multiplicity(
+                HistogramBuilder::new()
+                    .add_equal_width_axis(10, 0.0, 10.0)
+                    .build()
+                    .unwrap(),
+            )
+        }
+
+        fn process_event(&mut self, event: &Event) {
+            self.0.fill(&[event.tracks().count() as f64]);
+        }
+    }
+
+    #[test]
+    fn merge_partial_results() {
+        let dir = std::env::temp_dir().join(format!("malice-partials-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<_> = (0..3)
+            .map(|n| {
+                let mut partial = Multiplicity::new();
+                partial.0.fill(&[n as f64]);
+                partial.0.fill(&[1.0]);
+                let path = dir.join(format!("partial-{}.bin", n));
+                write_partial(&partial, &path).unwrap();
+                path
+            })
+            .collect();
+        let merged: Multiplicity = merge_partials(&paths).unwrap();
+        assert_eq!(
+            &merged.0.values()[..4],
+            &[1.0, 4.0, 1.0, 0.0],
+            "Wrong merged values"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn map_reduce_fails_for_missing_file() {
+        let dir = std::env::temp_dir().join(format!("malice-missing-{}", std::process::id()));
+        let res: Result<Multiplicity, _> =
+            map_reduce(vec![PathBuf::from("does-not-exist.root")], &dir);
+        assert!(res.is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -35,6 +35,8 @@
 #[macro_use]
 extern crate bitflags;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod analysis;
 mod event;
 mod primary_vertex;
 mod track;
@@ -68,7 +70,7 @@ where
     let tmp = {
         move || async {
             let rf = RootFile::new(p).await?;
-            let tree = rf.items()[0].as_tree().await?;
+            let tree = rf.get("esdTree")?.as_tree().await?;
             event_stream_from_tree(&tree).await
         }
     }();
@@ -121,6 +123,78 @@ where
     receiver.into_iter()
 }
 
+/// Run an analysis over all events of the given files in parallel.
+///
+/// Several files are read concurrently on a separate IO thread. The
+/// events are distributed over the threads of `rayon`'s thread pool;
+/// each thread creates its own accumulator with `init` and adds
+/// events to it with `process`. Finally all accumulators are
+/// combined with `merge`. The events are processed in no particular
+/// order.
+///
+/// Returns the first IO error encountered; the remaining files are
+/// not read in that case.
+///
+/// This function is not available on the wasm32 target and must not
+/// be called from an asynchronous context itself.
+///
+/// # Example
+/// ``` rust,no_run
+/// use malice::{default_event_filter, par_process};
+///
+/// let files = alice_open_data::all_files_10h().unwrap();
+/// // Count the events passing the default filter
+/// let n_events = par_process(
+///     files,
+///     || 0,
+///     |n, event| *n += default_event_filter(event) as usize,
+///     |a, b| a + b,
+/// )
+/// .unwrap();
+/// println!("{} events", n_events);
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub fn par_process<I, S, A, F, P, M>(sources: I, init: F, process: P, merge: M) -> Result<A, Error>
+where
+    I: IntoIterator<Item = S> + Send + 'static,
+    S: Into<Source> + Send,
+    A: Send,
+    F: Fn() -> A + Send + Sync,
+    P: Fn(&mut A, &Event) + Send + Sync,
+    M: Fn(A, A) -> A + Send + Sync,
+{
+    use rayon::iter::{ParallelBridge, ParallelIterator};
+    use std::sync::mpsc::sync_channel;
+    use std::thread::spawn;
+
+    const CONCURRENT_FILES: usize = 4;
+    let (sender, receiver) = sync_channel(16 * rayon::current_num_threads());
+    let io = spawn(move || {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async move {
+            stream::iter(sources)
+                .map(event_stream_from_esd_file)
+                .buffer_unordered(CONCURRENT_FILES)
+                .flatten_unordered(CONCURRENT_FILES)
+                .try_for_each(|event| async {
+                    // Errors if the receiving end has hung up
+                    sender.send(event).map_err(Into::into)
+                })
+                .await
+        })
+    });
+    let acc = receiver
+        .into_iter()
+        .par_bridge()
+        .fold(&init, |mut acc, event| {
+            process(&mut acc, &event);
+            acc
+        })
+        .reduce(&init, &merge);
+    io.join().expect("IO thread panicked")?;
+    Ok(acc)
+}
+
 /// Create a stream of events found in the given files (local or
 /// remote). You probably want to use `event_iterator_from_files`
 /// instead unless you are a on the `wasm32` target.
@@ -138,16 +212,47 @@ where
 #[cfg(test)]
 mod tests {
 
+    use std::path::PathBuf;
+
     use futures::{future, StreamExt};
     use root_io::RootFile;
 
-    use super::{default_event_filter, default_track_filter, event_stream_from_tree};
+    use super::{default_event_filter, default_track_filter, event_stream_from_tree, par_process};
+
+    #[test]
+    fn par_process_same_as_sequential() {
+        let f = alice_open_data::test_file().unwrap();
+        let counts = par_process(
+            vec![f.clone(), f],
+            || (0, 0),
+            |(n_events, n_tracks), ev| {
+                if default_event_filter(ev) {
+                    *n_events += 1;
+                    *n_tracks += ev.tracks().count();
+                }
+            },
+            |a, b| (a.0 + b.0, a.1 + b.1),
+        )
+        .unwrap();
+        assert_eq!(counts, (4, 2 * 11958));
+    }
+
+    #[test]
+    fn par_process_io_error() {
+        let res = par_process(
+            vec![PathBuf::from("does-not-exist.root")],
+            || 0,
+            |n, _| *n += 1,
+            |a, b| a + b,
+        );
+        assert!(res.is_err());
+    }
 
     #[tokio::test]
     async fn test_filters() {
         let f = alice_open_data::test_file().unwrap();
         let rf = RootFile::new(f).await.unwrap();
-        let t = rf.items()[0].as_tree().await.unwrap();
+        let t = rf.get("esdTree").unwrap().as_tree().await.unwrap();
         let events = event_stream_from_tree(&t).await.unwrap();
         let mut cnt_evts = 0;
         let mut cnt_tracks = 0;
@@ -181,7 +286,7 @@ mod tests {
             .into_iter()
             .take(n_files)
             .map(|path| RootFile::new(path).expect("Failed to open file"))
-            .map(|rf| rf.items()[0].as_tree().unwrap())
+            .map(|rf| rf.get("esdTree").unwrap().as_tree().unwrap())
             .flat_map(|tree| match DsIntoIter_rust::new(&tree) {
                 Ok(s) => s,
                 Err(err) => panic!("An error occured! Message: {}", err),
@@ -217,7 +322,10 @@ mod tests {
             })
             .expect("Funky file not found");
         let rust_iter = {
-            let tree = RootFile::new(file).expect("Failed to open file").items()[0]
+            let tree = RootFile::new(file)
+                .expect("Failed to open file")
+                .get("esdTree")
+                .unwrap()
                 .as_tree()
                 .unwrap();
             match DsIntoIter_rust::new(&tree) {
@@ -251,7 +359,10 @@ mod tests {
                 .find(|p| p.to_str().unwrap().contains(funky))
                 .expect("Funky file not found");
             let mut rust_iter = {
-                let tree = RootFile::new(file).expect("Failed to open file").items()[0]
+                let tree = RootFile::new(file)
+                    .expect("Failed to open file")
+                    .get("esdTree")
+                    .unwrap()
                     .as_tree()
                     .unwrap();
                 match DsIntoIter_rust::new(&tree) {
@@ -272,7 +383,10 @@ mod tests {
                 .find(|p| p.to_str().unwrap().contains(funky))
                 .expect("Funky file not found");
             let mut rust_iter = {
-                let tree = RootFile::new(file).expect("Failed to open file").items()[0]
+                let tree = RootFile::new(file)
+                    .expect("Failed to open file")
+                    .get("esdTree")
+                    .unwrap()
                     .as_tree()
                     .unwrap();
                 match DsIntoIter_rust::new(&tree) {
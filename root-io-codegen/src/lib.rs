@@ -0,0 +1,104 @@
+//! Generate Rust structs and parsers for the classes stored in a
+//! `.root` file from a build script. This is the build-time
+//! equivalent of `root-ls <file> to-rust`.
+//!
+//! ``` no_run
+//! // build.rs
+//! root_io_codegen::Builder::new("data/AliESDs.root")
+//!     .class("AliESDRun")
+//!     .write_to_out_dir("esd.rs")
+//!     .unwrap();
+//! ```
+//!
+//! The generated file is then included with
+//! `include!(concat!(env!("OUT_DIR"), "/esd.rs"));`.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{format_err, Error};
+use futures::executor::block_on;
+use root_io::RootFile;
+
+/// Configures which code is generated from which file
+#[derive(Debug, Clone)]
+pub struct Builder {
+    path: PathBuf,
+    classes: Vec<String>,
+}
+
+impl Builder {
+    /// Generate code from the streamer info of the file at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Builder {
+            path: path.as_ref().to_path_buf(),
+            classes: vec![],
+        }
+    }
+
+    /// Generate code for the class `name` and all the classes it
+    /// depends on. If no class is given, code is generated for all
+    /// classes of the file.
+    pub fn class(mut self, name: &str) -> Self {
+        self.classes.push(name.to_string());
+        self
+    }
+
+    /// Generate the formatted code
+    pub fn generate(&self) -> Result<String, Error> {
+        let f = block_on(RootFile::new(self.path.as_path()))?;
+        let mut s = String::new();
+        if self.classes.is_empty() {
+            block_on(f.streamer_info_as_rust(&mut s))?;
+        } else {
+            let classes: Vec<_> = self.classes.iter().map(String::as_str).collect();
+            block_on(f.classes_as_rust(&classes, &mut s))?;
+        }
+        let code = syn::parse_file(&s)?;
+        Ok(prettyplease::unparse(&code))
+    }
+
+    /// Write the generated code to the file `name` in the `OUT_DIR`
+    /// of the running build script and return its path. The build
+    /// script is rerun if the `.root` file changes.
+    pub fn write_to_out_dir(&self, name: &str) -> Result<PathBuf, Error> {
+        let out_dir = env::var_os("OUT_DIR")
+            .ok_or_else(|| format_err!("`OUT_DIR` is not set; not running in a build script?"))?;
+        let out = Path::new(&out_dir).join(name);
+        fs::write(&out, self.generate()?)?;
+        println!("cargo:rerun-if-changed={}", self.path.display());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str = "../root-io/src/test_data/simple.root";
+
+    #[test]
+    fn generate_with_dependencies() {
+        let code = Builder::new(SIMPLE).class("TTree").generate().unwrap();
+        // Base classes of `TTree`
+        assert!(code.contains("pub struct TTree {"));
+        assert!(code.contains("pub struct TAttLine {"));
+        assert!(code.contains("pub fn tattline<'s>"));
+        // Unrelated classes are not generated
+        assert!(!code.contains("pub struct TLeafI {"));
+        assert!(!code.contains("pub struct TBranch {"));
+
+        let all = Builder::new(SIMPLE).generate().unwrap();
+        assert!(all.contains("pub struct TLeafI {"));
+    }
+
+    #[test]
+    fn unknown_class() {
+        assert!(Builder::new(SIMPLE)
+            .class("NoSuchClass")
+            .generate()
+            .is_err());
+        // Core types are not generated
+        assert!(Builder::new(SIMPLE).class("TNamed").generate().is_err());
+    }
+}
@@ -18,7 +18,7 @@ fn fixed_size_branch() {
         let f = RootFile::new(path.as_path())
             .await
             .expect("Failed to open file");
-        let t = f.items()[0].as_tree().await.unwrap();
+        let t = f.get("esdTree").unwrap().as_tree().await.unwrap();
         let iter = t
             .branch_by_name("PrimaryVertex.AliVertex.fNContributors")
             .unwrap()
@@ -38,7 +38,7 @@ fn var_size_branch() {
         let f = RootFile::new(path.as_path())
             .await
             .expect("Failed to open file");
-        let t = f.items()[0].as_tree().await.unwrap();
+        let t = f.get("esdTree").unwrap().as_tree().await.unwrap();
 
         let track_counter: Vec<_> = t
             .branch_by_name("Tracks")
@@ -59,9 +59,39 @@ fn var_size_branch() {
     rt.block_on(fut);
 }
 
+/// Open the ESD tree and access a single one of its many
+/// branches. Either parse the entire tree up front or only the
+/// accessed branch.
+fn single_branch_of_wide_tree(lazy: bool) {
+    let path = alice_open_data::test_file().unwrap();
+
+    let fut = async {
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let t = if lazy {
+            f.get("esdTree").unwrap().as_lazy_tree().await.unwrap()
+        } else {
+            f.get("esdTree").unwrap().as_tree().await.unwrap()
+        };
+        black_box(
+            t.branch_by_name("PrimaryVertex.AliVertex.fNContributors")
+                .unwrap(),
+        );
+    };
+    let rt = Runtime::new().unwrap();
+    rt.block_on(fut);
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("fixed_size_branch", |b| b.iter(|| fixed_size_branch));
     c.bench_function("var_size_branch", |b| b.iter(|| var_size_branch));
+    c.bench_function("single_branch_eager_tree", |b| {
+        b.iter(|| single_branch_of_wide_tree(false))
+    });
+    c.bench_function("single_branch_lazy_tree", |b| {
+        b.iter(|| single_branch_of_wide_tree(true))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,435 @@
+//! A blocking API for reading ROOT files without an async runtime.
+//!
+//! The types in this module mirror `RootFile`, `FileItem`, `Tree` and
+//! `TBranch` but return plain values and `Iterator`s instead of
+//! futures and streams. Each `RootFile` drives the I/O on its own
+//! single threaded runtime, so callers do not need to set one up. All
+//! methods which do not perform any I/O are available through `Deref`
+//! to the respective async type.
+//!
+//! The methods in this module must not be called from within an
+//! async context; they panic in that case. This module is not
+//! available on the `wasm32` target.
+//!
+//! # Example
+//! ```
+//! use std::path::Path;
+//!
+//! use root_io::blocking::RootFile;
+//! use root_io::tree_reader::Value;
+//!
+//! let path = Path::new("./src/test_data/simple.root");
+//! let f = RootFile::new(path).expect("Failed to open file");
+//! let tree = f.get("tree").unwrap().as_tree().unwrap();
+//! for row in tree.select(&["one", "three"]).unwrap() {
+//!     let row = row.unwrap();
+//!     println!("{} {}", row[0], row[1]);
+//! }
+//! ```
+use std::fmt;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+use failure::Error;
+use futures::stream::{LocalBoxStream, StreamExt};
+use nom::IResult;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::core::{Context, Object, ProcessId, Ref, Source, TStreamerInfo, VerifyReport};
+use crate::schema::FileSchema;
+use crate::tree_reader::{EntryList, Formula, TBranch, Value};
+
+/// Blocking iterator over the items of an asynchronous stream
+pub struct Iter<'a, T> {
+    stream: LocalBoxStream<'a, T>,
+    rt: &'a Runtime,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn new(stream: LocalBoxStream<'a, T>, rt: &'a Runtime) -> Self {
+        Iter { stream, rt }
+    }
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rt.block_on(self.stream.next())
+    }
+}
+
+impl<T> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
+
+/// Blocking version of `root_io::RootFile`
+#[derive(Debug)]
+pub struct RootFile {
+    file: crate::RootFile,
+    rt: Arc<Runtime>,
+}
+
+impl Deref for RootFile {
+    type Target = crate::RootFile;
+
+    fn deref(&self) -> &crate::RootFile {
+        &self.file
+    }
+}
+
+impl RootFile {
+    /// Open a new ROOT file either from a `Url`, or from a `Path`
+    pub fn new<S: Into<Source>>(source: S) -> Result<Self, Error> {
+        let rt = Builder::new_current_thread().enable_all().build()?;
+        let file = rt.block_on(crate::RootFile::new(source))?;
+        Ok(RootFile {
+            file,
+            rt: Arc::new(rt),
+        })
+    }
+
+    /// Open a file and recover it if its list of keys is missing or
+    /// corrupt; see `root_io::RootFile::recover`
+    pub fn recover<S: Into<Source>>(source: S) -> Result<Self, Error> {
+        let rt = Builder::new_current_thread().enable_all().build()?;
+        let file = rt.block_on(crate::RootFile::recover(source))?;
+        Ok(RootFile {
+            file,
+            rt: Arc::new(rt),
+        })
+    }
+
+    /// The underlying asynchronous file
+    pub fn into_inner(self) -> crate::RootFile {
+        self.file
+    }
+
+    /// The items contained in this file
+    pub fn items(&self) -> Vec<FileItem<'_>> {
+        self.file
+            .items()
+            .iter()
+            .map(|item| self.item(item))
+            .collect()
+    }
+
+    /// Get the item of the given name; see `root_io::RootFile::get`
+    pub fn get(&self, name: &str) -> Result<FileItem<'_>, Error> {
+        self.file.get(name).map(|item| self.item(item))
+    }
+
+    /// Get the given cycle of the item of the given name
+    pub fn get_cycle(&self, name: &str, cycle: i16) -> Result<FileItem<'_>, Error> {
+        self.file.get_cycle(name, cycle).map(|item| self.item(item))
+    }
+
+    fn item<'a>(&'a self, item: &'a crate::FileItem) -> FileItem<'a> {
+        FileItem { item, rt: &self.rt }
+    }
+
+    pub fn get_streamer_context(&self) -> Result<Context, Error> {
+        self.rt.block_on(self.file.get_streamer_context())
+    }
+
+    /// The `TProcessID` of the given number (see `Ref::pid`)
+    pub fn process_id(&self, pid: u16) -> Result<ProcessId, Error> {
+        self.rt.block_on(self.file.process_id(pid))
+    }
+
+    /// The streamer info of this file
+    pub fn streamer_infos(&self) -> Result<Vec<TStreamerInfo>, Error> {
+        self.rt.block_on(self.file.streamer_infos())
+    }
+
+    /// Translate the streamer info of this file to a YAML file
+    pub fn streamer_info_as_yaml<W: fmt::Write>(&self, s: &mut W) -> Result<(), Error> {
+        self.rt.block_on(self.file.streamer_info_as_yaml(s))
+    }
+
+    /// Machine readable description of the classes in the streamer
+    /// info and of the layout of all trees in this file
+    pub fn schema(&self) -> Result<FileSchema, Error> {
+        self.rt.block_on(self.file.schema())
+    }
+
+    /// Generate Rust code from the streamer info of this file
+    pub fn streamer_info_as_rust<W: fmt::Write>(&self, s: &mut W) -> Result<(), Error> {
+        self.rt.block_on(self.file.streamer_info_as_rust(s))
+    }
+
+    /// Generate Rust code for the given classes and all the classes
+    /// they depend on
+    pub fn classes_as_rust<W: fmt::Write>(&self, classes: &[&str], s: &mut W) -> Result<(), Error> {
+        self.rt.block_on(self.file.classes_as_rust(classes, s))
+    }
+
+    /// The unused segments of this file as (first, last) byte
+    pub fn free_segments(&self) -> Result<Vec<(u64, u64)>, Error> {
+        self.rt.block_on(self.file.free_segments())
+    }
+
+    /// Check the integrity of this file; see `root_io::RootFile::verify`
+    pub fn verify(&self) -> Result<VerifyReport, Error> {
+        self.rt.block_on(self.file.verify())
+    }
+}
+
+/// Blocking version of `root_io::FileItem`
+#[derive(Debug, Clone, Copy)]
+pub struct FileItem<'a> {
+    item: &'a crate::FileItem,
+    rt: &'a Arc<Runtime>,
+}
+
+impl Deref for FileItem<'_> {
+    type Target = crate::FileItem;
+
+    fn deref(&self) -> &crate::FileItem {
+        self.item
+    }
+}
+
+impl FileItem<'_> {
+    /// Parse this `FileItem` as a `Tree`
+    pub fn as_tree(&self) -> Result<Tree, Error> {
+        let tree = self.rt.block_on(self.item.as_tree())?;
+        Ok(self.tree(tree))
+    }
+
+    /// Parse this `FileItem` as a `Tree` but defer the parsing of its
+    /// branches and leaves until they are accessed
+    pub fn as_lazy_tree(&self) -> Result<Tree, Error> {
+        let tree = self.rt.block_on(self.item.as_lazy_tree())?;
+        Ok(self.tree(tree))
+    }
+
+    fn tree(&self, tree: crate::tree_reader::Tree) -> Tree {
+        Tree {
+            tree,
+            rt: self.rt.clone(),
+        }
+    }
+
+    /// Parse this `FileItem` as an `EntryList`
+    pub fn as_entry_list(&self) -> Result<EntryList, Error> {
+        self.rt.block_on(self.item.as_entry_list())
+    }
+
+    /// Parse this `FileItem` as a generic `Object`
+    pub fn as_object(&self) -> Result<Object, Error> {
+        self.rt.block_on(self.item.as_object())
+    }
+
+    /// Parse this `FileItem` with the given parser
+    pub fn parse_with<F, O>(&self, parser: F) -> Result<O, Error>
+    where
+        F: for<'s> Fn(&'s [u8], &'s Context) -> IResult<&'s [u8], O>,
+    {
+        self.rt.block_on(self.item.parse_with(parser))
+    }
+}
+
+/// Blocking version of `root_io::tree_reader::Tree`
+#[derive(Debug)]
+pub struct Tree {
+    tree: crate::tree_reader::Tree,
+    rt: Arc<Runtime>,
+}
+
+impl Deref for Tree {
+    type Target = crate::tree_reader::Tree;
+
+    fn deref(&self) -> &crate::tree_reader::Tree {
+        &self.tree
+    }
+}
+
+impl Tree {
+    /// The underlying asynchronous tree
+    pub fn into_inner(self) -> crate::tree_reader::Tree {
+        self.tree
+    }
+
+    /// Get a branch by name, including nested ones
+    pub fn branch_by_name(&self, name: &str) -> Result<Branch<'_>, Error> {
+        let branch = self.tree.branch_by_name(name)?;
+        Ok(Branch {
+            branch,
+            rt: &self.rt,
+        })
+    }
+
+    /// Iterate over the rows of the given branches; see
+    /// `root_io::tree_reader::Tree::select`
+    pub fn select<'a>(
+        &'a self,
+        names: &'a [&'a str],
+    ) -> Result<Iter<'a, Result<Vec<Value>, Error>>, Error> {
+        let rows = self.rt.block_on(self.tree.select(names))?;
+        Ok(Iter::new(rows.boxed_local(), &self.rt))
+    }
+
+    /// Like `select` but only the entries selected by `entry_list`
+    /// are returned
+    pub fn select_entries<'a>(
+        &'a self,
+        names: &'a [&'a str],
+        entry_list: &'a EntryList,
+    ) -> Result<Iter<'a, Result<Vec<Value>, Error>>, Error> {
+        let rows = self
+            .rt
+            .block_on(self.tree.select_entries(names, entry_list))?;
+        Ok(Iter::new(rows.boxed_local(), &self.rt))
+    }
+
+    /// Like `select` but only the entries in the given range are
+    /// returned
+    pub fn select_range<'a>(
+        &'a self,
+        names: &'a [&'a str],
+        entries: Range<i64>,
+    ) -> Result<Iter<'a, Result<Vec<Value>, Error>>, Error> {
+        let rows = self.rt.block_on(self.tree.select_range(names, entries))?;
+        Ok(Iter::new(rows.boxed_local(), &self.rt))
+    }
+
+    /// Evaluate `formula` for each entry of this tree
+    pub fn evaluate<'a>(
+        &'a self,
+        formula: &'a Formula,
+    ) -> Result<Iter<'a, Result<Value, Error>>, Error> {
+        let values = self.rt.block_on(formula.evaluate(&self.tree))?;
+        Ok(Iter::new(values.boxed_local(), &self.rt))
+    }
+
+    /// Evaluate `formula` as a selection for each entry of this tree
+    pub fn cut<'a>(&'a self, formula: &'a Formula) -> Result<Iter<'a, Result<bool, Error>>, Error> {
+        let passed = self.rt.block_on(formula.cut(&self.tree))?;
+        Ok(Iter::new(passed.boxed_local(), &self.rt))
+    }
+
+    /// Build an index of this tree; see
+    /// `root_io::tree_reader::Tree::build_index`
+    pub fn build_index(&mut self, major: &str, minor: &str) -> Result<(), Error> {
+        self.rt.block_on(self.tree.build_index(major, minor))
+    }
+
+    /// Find the branch holding the object referenced by `reference`
+    /// in the given entry
+    pub fn resolve_ref(
+        &self,
+        entry: i64,
+        reference: &Ref,
+        process: &ProcessId,
+    ) -> Result<Option<&str>, Error> {
+        self.rt
+            .block_on(self.tree.resolve_ref(entry, reference, process))
+    }
+}
+
+/// Blocking version of `root_io::tree_reader::TBranch`
+#[derive(Debug, Clone, Copy)]
+pub struct Branch<'a> {
+    branch: &'a TBranch,
+    rt: &'a Runtime,
+}
+
+impl Deref for Branch<'_> {
+    type Target = TBranch;
+
+    fn deref(&self) -> &TBranch {
+        self.branch
+    }
+}
+
+impl<'a> Branch<'a> {
+    /// Iterator over the data of a column with a fixed number of
+    /// elements per entry
+    pub fn as_fixed_size_iterator<T, P>(&self, p: P) -> Iter<'a, T>
+    where
+        T: 'a,
+        P: Fn(&[u8]) -> IResult<&[u8], T> + 'a,
+    {
+        let s = self.branch.as_fixed_size_iterator(p);
+        Iter::new(s.boxed_local(), self.rt)
+    }
+
+    /// Iterator over the data of a column with a variable number of
+    /// elements per entry
+    pub fn as_var_size_iterator<T, P>(&self, p: P, el_counter: Vec<u32>) -> Iter<'a, Vec<T>>
+    where
+        T: 'a,
+        P: Fn(&[u8]) -> IResult<&[u8], T> + 'a,
+    {
+        let s = self.branch.as_var_size_iterator(p, el_counter);
+        Iter::new(s.boxed_local(), self.rt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use nom::number::complete::{be_f32, be_i32};
+
+    use super::*;
+
+    fn open(name: &str) -> RootFile {
+        RootFile::new(Path::new("./src/test_data").join(name)).unwrap()
+    }
+
+    #[test]
+    fn simple_tree() {
+        let f = open("simple.root");
+        assert_eq!(f.items().len(), 1);
+        let tree = f.get("tree").unwrap().as_tree().unwrap();
+        assert_eq!(tree.entries(), 4);
+
+        let ones: Vec<_> = tree
+            .branch_by_name("one")
+            .unwrap()
+            .as_fixed_size_iterator(|i| be_i32(i))
+            .collect();
+        assert_eq!(ones, [1, 2, 3, 4]);
+
+        let rows = tree.select_range(&["one", "three"], 1..3).unwrap();
+        let rows: Vec<_> = rows.collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            rows,
+            [
+                vec![Value::I32(2), Value::String("dos".to_string())],
+                vec![Value::I32(3), Value::String("tres".to_string())],
+            ]
+        );
+
+        let formula: Formula = "one > 2".parse().unwrap();
+        let passed: Vec<_> = tree.cut(&formula).unwrap().map(Result::unwrap).collect();
+        assert_eq!(passed, [false, false, true, true]);
+    }
+
+    #[test]
+    fn jagged_branch() {
+        let f = open("HZZ.root");
+        let tree = f.get("events").unwrap().as_lazy_tree().unwrap();
+        let n_jets: Vec<u32> = tree
+            .branch_by_name("NJet")
+            .unwrap()
+            .as_fixed_size_iterator(|i| be_i32(i))
+            .map(|n| n as u32)
+            .collect();
+        let jets: Vec<_> = tree
+            .branch_by_name("Jet_Px")
+            .unwrap()
+            .as_var_size_iterator(|i| be_f32(i), n_jets.clone())
+            .collect();
+        assert_eq!(jets.len(), n_jets.len());
+        assert!(jets
+            .iter()
+            .zip(&n_jets)
+            .all(|(j, n)| j.len() == *n as usize));
+        assert!(f.verify().unwrap().is_ok());
+    }
+}
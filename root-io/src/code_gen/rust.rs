@@ -1,6 +1,10 @@
 /// Types to map out the inter-dependences of the streamed objects
+use failure::Error;
 use quote::Tokens;
 
+use crate::code_gen::utils::{field_name, type_is_core};
+use crate::core::{is_decodable, TStreamerInfo};
+
 pub(crate) trait ToRustType {
     fn type_doc(&self) -> Tokens {
         quote!()
@@ -9,9 +13,72 @@ pub(crate) trait ToRustType {
 }
 
 pub(crate) trait ToRustParser: ToRustType {
-    /// The definition of the parser parsing this thing such that it can be used in-line
-    fn to_inline_parser(&self) -> Tokens {
-        quote! {#(self.parser_name())}
+    /// The definition of the parser parsing this thing such that it
+    /// can be used in-line. The parser may refer to the `context` of
+    /// the surrounding parser.
+    fn to_inline_parser(&self, classes: &Classes) -> InlineParser;
+}
+
+/// A parser as used in-line in generated code
+pub(crate) struct InlineParser {
+    tokens: Tokens,
+    /// Are the tokens a call of a parser on the input `i` rather than
+    /// the parser itself?
+    is_call: bool,
+    /// Does the parser refer to the `context` of the surrounding parser?
+    uses_context: bool,
+}
+
+impl InlineParser {
+    /// A parser such as `be_u16` or `count(be_i32, 3)`
+    pub(crate) fn new(tokens: Tokens) -> Self {
+        InlineParser {
+            tokens,
+            is_call: false,
+            uses_context: false,
+        }
+    }
+
+    /// A call of a parser on the input `i` such as `tarray(be_i32, i)`
+    pub(crate) fn call(tokens: Tokens) -> Self {
+        InlineParser {
+            is_call: true,
+            ..Self::new(tokens)
+        }
+    }
+
+    /// Mark this parser as referring to the `context` of the
+    /// surrounding parser
+    pub(crate) fn with_context(self) -> Self {
+        InlineParser {
+            uses_context: true,
+            ..self
+        }
+    }
+
+    /// Does this parser refer to the `context` of the surrounding parser?
+    pub(crate) fn uses_context(&self) -> bool {
+        self.uses_context
+    }
+
+    /// This parser as an argument of other parsers
+    pub(crate) fn to_parser(&self) -> Tokens {
+        let tokens = &self.tokens;
+        if self.is_call {
+            quote! {|i| #tokens}
+        } else {
+            tokens.clone()
+        }
+    }
+
+    /// This parser applied to the input `i`
+    pub(crate) fn to_call(&self) -> Tokens {
+        let tokens = &self.tokens;
+        if self.is_call {
+            tokens.clone()
+        } else {
+            quote! {#tokens(i)}
+        }
     }
 }
 
@@ -20,9 +87,93 @@ pub(crate) trait ToNamedRustParser: ToRustParser {
     fn parser_name(&self) -> Tokens;
 
     /// The definition of the parser parsing this thing; May be blank if it is build-in
-    fn to_named_parser(&self) -> Tokens;
+    fn to_named_parser(&self, classes: &Classes) -> Tokens;
 }
 
 pub(crate) trait ToRustStruct: ToRustType {
-    fn to_struct(&self) -> Tokens;
+    fn to_struct(&self, classes: &Classes) -> Tokens;
+}
+
+/// The classes for which code is generated. Members of other classes
+/// are parsed into one of the core types or into an `Object`.
+pub(crate) struct Classes<'a> {
+    infos: &'a [TStreamerInfo],
+    /// The class whose code is currently generated
+    current: &'a str,
+}
+
+impl<'a> Classes<'a> {
+    pub(crate) fn new(infos: &'a [TStreamerInfo]) -> Self {
+        Classes { infos, current: "" }
+    }
+
+    /// The same set of classes while generating code for `class`
+    pub(crate) fn within(&self, class: &'a str) -> Self {
+        Classes {
+            infos: self.infos,
+            current: class,
+        }
+    }
+
+    /// The class whose code is currently generated
+    pub(crate) fn current(&self) -> &str {
+        self.current
+    }
+
+    fn get(&self, class: &str) -> Option<&'a TStreamerInfo> {
+        self.infos.iter().find(|info| info.name() == class)
+    }
+
+    /// Is a struct and parser generated for the given class?
+    pub(crate) fn is_generated(&self, class: &str) -> bool {
+        !type_is_core(class) && !is_decodable(class) && self.get(class).is_some()
+    }
+
+    /// Path to the member `member` of `class` as seen from the class
+    /// `from`. This is the member itself if `class` is `from` and
+    /// otherwise goes through the embedded base classes.
+    pub(crate) fn member_path(&self, from: &str, class: &str, member: &str) -> Option<String> {
+        if from == class {
+            return Some(field_name(member));
+        }
+        self.get(from)?
+            .base_classes()
+            .filter(|base| self.is_generated(base))
+            .find_map(|base| {
+                let path = self.member_path(base, class, member)?;
+                Some(format!("{}.{}", field_name(base), path))
+            })
+    }
+
+    /// The given classes and all generated classes they depend on
+    /// through their base classes and data members; in the order of
+    /// the streamer info.
+    pub(crate) fn with_dependencies(
+        &self,
+        classes: &[&str],
+    ) -> Result<Vec<&'a TStreamerInfo>, Error> {
+        let mut needed: Vec<&str> = vec![];
+        let mut todo: Vec<&str> = classes.to_vec();
+        while let Some(class) = todo.pop() {
+            if needed.contains(&class) {
+                continue;
+            }
+            let info = self
+                .get(class)
+                .ok_or_else(|| format_err!("Class `{}` not found in the streamer info", class))?;
+            if !self.is_generated(class) {
+                return Err(format_err!(
+                    "No code is generated for the class `{}`",
+                    class
+                ));
+            }
+            needed.push(info.name());
+            todo.extend(info.dependencies().filter(|dep| self.is_generated(dep)));
+        }
+        Ok(self
+            .infos
+            .iter()
+            .filter(|info| needed.contains(&info.name()))
+            .collect())
+    }
 }
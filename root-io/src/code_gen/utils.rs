@@ -8,16 +8,32 @@ pub(crate) fn type_is_core(name: &str) -> bool {
     }
 }
 
-/// If necessary, annotate the given type name with a life time or replace with an alias
-pub(crate) fn alias_or_lifetime(t: &str) -> String {
-    // Most core types do not need a life time specifier
-    if type_is_core(t) && t != "TObjArray" {
-        return t.to_string();
-    }
-    // All non-core types get a life time
-    // This is over zealous, but currently, I don't have a proper way
-    // to check if a type has a member with a lifetime
-    format!("{}<'s>", t)
+/// Turn a ROOT class name such as `TParameter<double>` or
+/// `ns::TFoo` into a valid Rust type name (`TParameterDouble`,
+/// `NsTFoo`)
+pub(crate) fn rust_ident(n: &str) -> String {
+    n.split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// The name of the field of a generated struct holding the given
+/// data member or base class
+pub(crate) fn field_name(n: &str) -> String {
+    let name: Vec<_> = n
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect();
+    sanitize(&name.join("_"))
 }
 
 pub(crate) fn sanitize(n: &str) -> String {
@@ -27,7 +43,8 @@ pub(crate) fn sanitize(n: &str) -> String {
         "return", "Self", "self", "static", "struct", "super", "trait", "true", "type", "unsafe",
         "use", "where", "while", "abstract", "alignof", "become", "box", "do", "final", "macro",
         "offsetof", "override", "priv", "proc", "pure", "sizeof", "typeof", "unsized", "virtual",
-        "yield",
+        "yield", // Names used by the generated parsers
+        "i", "context", "ver",
     ];
     if keywords.into_iter().any(|w| w == n) {
         format!("{}_", n)
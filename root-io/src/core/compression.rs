@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// Compression algorithms supported by ROOT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// The global default of ROOT; effectively zlib
+    Global,
+    Zlib,
+    Lzma,
+    /// The old ROOT specific algorithm
+    Old,
+    Lz4,
+    Zstd,
+    Unknown(i32),
+}
+
+/// Compression setting of a file or branch. ROOT stores the setting
+/// as `100 * algorithm + level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression(i32);
+
+impl Compression {
+    pub(crate) fn new(setting: i32) -> Self {
+        Compression(setting)
+    }
+
+    /// The raw setting as stored by ROOT
+    pub fn setting(&self) -> i32 {
+        self.0
+    }
+
+    /// Is the setting inherited from the file? This is the case for
+    /// negative settings of branches.
+    pub fn is_inherited(&self) -> bool {
+        self.0 < 0
+    }
+
+    /// The compression algorithm
+    pub fn algorithm(&self) -> CompressionAlgorithm {
+        use CompressionAlgorithm::*;
+        match self.0 / 100 {
+            0 => Global,
+            1 => Zlib,
+            2 => Lzma,
+            3 => Old,
+            4 => Lz4,
+            5 => Zstd,
+            id => Unknown(id),
+        }
+    }
+
+    /// The compression level; 0 means uncompressed
+    pub fn level(&self) -> i32 {
+        self.0 % 100
+    }
+}
+
+/// E.g. `zlib-1`, `lz4-4` or `uncompressed`
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use CompressionAlgorithm::*;
+        if self.is_inherited() {
+            return write!(f, "inherited");
+        }
+        if self.level() == 0 {
+            return write!(f, "uncompressed");
+        }
+        match self.algorithm() {
+            Global => write!(f, "default-{}", self.level()),
+            Zlib => write!(f, "zlib-{}", self.level()),
+            Lzma => write!(f, "lzma-{}", self.level()),
+            Old => write!(f, "old-{}", self.level()),
+            Lz4 => write!(f, "lz4-{}", self.level()),
+            Zstd => write!(f, "zstd-{}", self.level()),
+            Unknown(id) => write!(f, "unknown({})-{}", id, self.level()),
+        }
+    }
+}
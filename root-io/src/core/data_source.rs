@@ -1,18 +1,36 @@
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
 use std::io::{Read, Seek, SeekFrom};
 #[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use failure::Error;
+use futures::future::BoxFuture;
+#[cfg(feature = "http")]
 use reqwest::{
-    header::{RANGE, USER_AGENT},
+    header::{CONTENT_LENGTH, RANGE, USER_AGENT},
     Client, Url,
 };
 
+/// A custom way of reading the bytes of a file. This allows reading
+/// remote files with any HTTP client (e.g. the one already used by
+/// an async service) or from other storage systems. The returned
+/// futures must not depend on a particular async runtime.
+pub trait Fetch: fmt::Debug + Send + Sync {
+    /// Read `len` bytes starting at byte `start`
+    fn fetch(&self, start: u64, len: u64) -> BoxFuture<'_, Result<Vec<u8>, Error>>;
+
+    /// Size of the file in bytes
+    fn size(&self) -> BoxFuture<'_, Result<u64, Error>>;
+}
+
 /// The source from where the Root file is read. Construct it using
-/// `.into()` on a `Url` or `Path`. The latter is not availible for
-/// the `wasm32` target.
+/// `.into()` on a `Url` or `Path` or with `Source::from_fetch`. Paths
+/// are not availible for the `wasm32` target and `Url`s need the
+/// `http` feature.
 #[derive(Debug, Clone)]
 pub struct Source(SourceInner);
 
@@ -21,11 +39,14 @@ pub struct Source(SourceInner);
 #[derive(Debug, Clone)]
 enum SourceInner {
     /// A local source, i.e. a file on disc.
+    #[cfg(not(target_arch = "wasm32"))]
     Local(PathBuf),
+    #[cfg(feature = "http")]
     Remote {
         client: Client,
         url: Url,
     },
+    Custom(Arc<dyn Fetch>),
 }
 
 impl Source {
@@ -33,15 +54,28 @@ impl Source {
         thing.into()
     }
 
+    /// A source reading its bytes with the given `Fetch` implementation
+    pub fn from_fetch<F: Fetch + 'static>(fetch: F) -> Self {
+        Self(SourceInner::Custom(Arc::new(fetch)))
+    }
+
     pub async fn fetch(&self, start: u64, len: u64) -> Result<Vec<u8>, Error> {
         match &self.0 {
+            // Reading from disc blocks; do it on a thread pool which
+            // works with any executor
+            #[cfg(not(target_arch = "wasm32"))]
             SourceInner::Local(path) => {
-                let mut f = File::open(path)?;
-                f.seek(SeekFrom::Start(start))?;
-                let mut buf = vec![0; len as usize];
-                f.read_exact(&mut buf)?;
-                Ok(buf)
+                let path = path.to_owned();
+                ::blocking::unblock(move || {
+                    let mut f = File::open(path)?;
+                    f.seek(SeekFrom::Start(start))?;
+                    let mut buf = vec![0; len as usize];
+                    f.read_exact(&mut buf)?;
+                    Ok::<_, Error>(buf)
+                })
+                .await
             }
+            #[cfg(feature = "http")]
             SourceInner::Remote { client, url } => {
                 let rsp = client
                     .get(url.clone())
@@ -53,10 +87,39 @@ impl Source {
                 let bytes = rsp.bytes().await?;
                 Ok(bytes.as_ref().to_vec())
             }
+            SourceInner::Custom(fetch) => fetch.fetch(start, len).await,
+        }
+    }
+
+    /// Size of the file in bytes
+    pub async fn size(&self) -> Result<u64, Error> {
+        match &self.0 {
+            #[cfg(not(target_arch = "wasm32"))]
+            SourceInner::Local(path) => {
+                let path = path.to_owned();
+                ::blocking::unblock(move || Ok(std::fs::metadata(path)?.len())).await
+            }
+            #[cfg(feature = "http")]
+            SourceInner::Remote { client, url } => {
+                let rsp = client
+                    .head(url.clone())
+                    .header(USER_AGENT, "alice-rs")
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                rsp.headers()
+                    .get(CONTENT_LENGTH)
+                    .and_then(|len| len.to_str().ok())
+                    .and_then(|len| len.parse().ok())
+                    .ok_or_else(|| format_err!("Server did not report the size of {}", url))
+            }
+            SourceInner::Custom(fetch) => fetch.size().await,
         }
     }
 }
 
+/// Read the file with `reqwest`; this requires a `tokio` runtime
+#[cfg(feature = "http")]
 impl From<Url> for Source {
     fn from(url: Url) -> Self {
         Self(SourceInner::Remote {
@@ -81,3 +144,52 @@ impl From<PathBuf> for Source {
         Self(SourceInner::Local(path_buf))
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use futures::executor::block_on;
+    use futures::FutureExt;
+
+    use super::*;
+    use crate::RootFile;
+
+    /// A file held in memory
+    #[derive(Debug)]
+    struct InMemory(Vec<u8>);
+
+    impl Fetch for InMemory {
+        fn fetch(&self, start: u64, len: u64) -> BoxFuture<'_, Result<Vec<u8>, Error>> {
+            let range = start as usize..(start + len) as usize;
+            let bytes = self
+                .0
+                .get(range)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| format_err!("Read beyond the end of the file"));
+            async move { bytes }.boxed()
+        }
+
+        fn size(&self) -> BoxFuture<'_, Result<u64, Error>> {
+            let len = self.0.len() as u64;
+            async move { Ok(len) }.boxed()
+        }
+    }
+
+    #[test]
+    fn custom_source_without_tokio() {
+        let bytes = std::fs::read("./src/test_data/simple.root").unwrap();
+        let f = block_on(RootFile::new(Source::from_fetch(InMemory(bytes)))).unwrap();
+        let tree = block_on(f.get("tree").unwrap().as_tree()).unwrap();
+        assert_eq!(tree.entries(), 4);
+    }
+
+    #[test]
+    fn local_source_without_tokio() {
+        let source = Source::new(Path::new("./src/test_data/simple.root"));
+        let magic = block_on(source.fetch(0, 4)).unwrap();
+        assert_eq!(magic, b"root");
+        let len = std::fs::metadata("./src/test_data/simple.root")
+            .unwrap()
+            .len();
+        assert_eq!(block_on(source.size()).unwrap(), len);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fmt;
 
 use failure::Error;
@@ -12,9 +13,10 @@ use nom::{
 use uuid::Uuid;
 
 use crate::{
-    code_gen::rust::{ToNamedRustParser, ToRustStruct},
+    code_gen::rust::{Classes, ToNamedRustParser, ToRustStruct},
     core::tstreamer::streamers,
     core::*,
+    schema::FileSchema,
     MAP_OFFSET,
 };
 
@@ -28,24 +30,25 @@ const TDIRECTORY_MAX_SIZE: u64 = 42;
 /// `RootFile` wraps the most basic information of a ROOT file.
 #[derive(Debug)]
 pub struct RootFile {
-    source: Source,
-    hdr: FileHeader,
-    items: Vec<FileItem>,
+    pub(crate) source: Source,
+    pub(crate) hdr: FileHeader,
+    pub(crate) items: Vec<FileItem>,
+    pub(crate) warnings: Vec<String>,
 }
 
 #[derive(Debug, PartialEq)]
-struct FileHeader {
+pub(crate) struct FileHeader {
     version: i32,
-    begin: i32,
-    end: u64,
-    seek_free: u64,
-    nbytes_free: i32,
-    n_entries_free: i32,
+    pub(crate) begin: i32,
+    pub(crate) end: u64,
+    pub(crate) seek_free: u64,
+    pub(crate) nbytes_free: i32,
+    pub(crate) n_entries_free: i32,
     n_bytes_name: i32,
     pointer_size: u8,
     compression: i32,
-    seek_info: SeekPointer,
-    nbytes_info: i32,
+    pub(crate) seek_info: SeekPointer,
+    pub(crate) nbytes_info: i32,
     uuid: Uuid,
     seek_dir: SeekPointer,
 }
@@ -144,43 +147,82 @@ fn directory(input: &[u8]) -> nom::IResult<&[u8], Directory> {
     ))
 }
 
+/// Read and parse the file header
+pub(crate) async fn read_header(source: &Source) -> Result<FileHeader, Error> {
+    source.fetch(0, FILE_HEADER_SIZE).await.and_then(|buf| {
+        file_header(&buf)
+            .map_err(|_| format_err!("Failed to parse file header"))
+            .map(|(_i, o)| o)
+    })
+}
+
+/// Read the headers of all keys of the top directory from the list
+/// of keys. The outer `Err` is returned for I/O errors, the inner one
+/// if the list of keys is corrupt or, given the `size` of the file,
+/// missing.
+pub(crate) async fn read_keys(
+    source: &Source,
+    hdr: &FileHeader,
+    size: Option<u64>,
+) -> Result<Result<Vec<TKeyHeader>, Error>, Error> {
+    // Jump to the TDirectory and parse it
+    let buf = source.fetch(hdr.seek_dir, TDIRECTORY_MAX_SIZE).await?;
+    let dir = match directory(&buf) {
+        Ok((_, dir)) => dir,
+        Err(_) => return Ok(Err(format_err!("Failed to parse TDirectory"))),
+    };
+    let is_missing = dir.seek_keys == 0
+        || size.is_some_and(|size| dir.seek_keys.saturating_add(dir.n_bytes_keys as u64) > size);
+    if is_missing {
+        return Ok(Err(format_err!("The list of keys is missing")));
+    }
+    let buf = source.fetch(dir.seek_keys, dir.n_bytes_keys as u64).await?;
+    let tkey_of_keys = match tkey(&buf) {
+        Ok((_, key)) => key,
+        Err(_) => return Ok(Err(format_err!("Failed to parse TKeys"))),
+    };
+    match tkey_headers(&tkey_of_keys.obj) {
+        Ok((_, hdrs)) => Ok(Ok(hdrs)),
+        _ => Ok(Err(format_err!("Expected TKeyHeaders"))),
+    }
+}
+
 impl RootFile {
     /// Open a new ROOT file either from a `Url`, or from a `Path`
-    /// (not available on `wasm32`).
+    /// (not available on `wasm32`). Fails if the list of keys of the
+    /// file is missing or corrupt; see `RootFile::recover` for such
+    /// files.
     pub async fn new<S: Into<Source>>(source: S) -> Result<Self, Error> {
         let source = source.into();
-        let hdr = source.fetch(0, FILE_HEADER_SIZE).await.and_then(|buf| {
-            file_header(&buf)
-                .map_err(|_| format_err!("Failed to parse file header"))
-                .map(|(_i, o)| o)
-        })?;
-        // Jump to the TDirectory and parse it
-        let dir = source
-            .fetch(hdr.seek_dir, TDIRECTORY_MAX_SIZE)
-            .await
-            .and_then(|buf| {
-                directory(&buf)
-                    .map_err(|_| format_err!("Failed to parse TDirectory"))
-                    .map(|(_i, o)| o)
-            })?;
-        let tkey_of_keys = source
-            .fetch(dir.seek_keys, dir.n_bytes_keys as u64)
-            .await
-            .and_then(|buf| {
-                tkey(&buf)
-                    .map_err(|_| format_err!("Failed to parse TKeys"))
-                    .map(|(_i, o)| o)
-            })?;
-        let keys = match tkey_headers(&tkey_of_keys.obj) {
-            Ok((_, hdrs)) => Ok(hdrs),
-            _ => Err(format_err!("Expected TKeyHeaders")),
-        }?;
+        let hdr = read_header(&source).await?;
+        let keys = read_keys(&source, &hdr, None).await??;
+        Ok(RootFile::from_keys(source, hdr, &keys))
+    }
+
+    /// A file with the given keys in its top directory
+    pub(crate) fn from_keys(source: Source, hdr: FileHeader, keys: &[TKeyHeader]) -> Self {
         let items = keys
             .iter()
             .map(|k_hdr| FileItem::new(k_hdr, source.clone()))
             .collect();
+        RootFile {
+            source,
+            hdr,
+            items,
+            warnings: vec![],
+        }
+    }
 
-        Ok(RootFile { source, hdr, items })
+    /// Warnings about problems encountered when opening this file
+    /// with `RootFile::recover`; e.g. data lost when recovering a file
+    /// without a valid list of keys. Empty for intact files.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// The default compression setting of this file
+    pub fn compression(&self) -> Compression {
+        Compression::new(self.hdr.compression)
     }
 
     pub async fn get_streamer_context(&self) -> Result<Context, Error> {
@@ -189,13 +231,17 @@ impl RootFile {
             .source
             .fetch(self.hdr.seek_info, seek_info_len)
             .await
-            .map(|buf| tkey(&buf).unwrap().1)?;
+            .and_then(|buf| {
+                tkey(&buf)
+                    .map_err(|_| format_err!("Failed to parse streamer info"))
+                    .map(|(_i, o)| o)
+            })?;
 
         let key_len = info_key.hdr.key_len;
         Ok(Context {
             source: self.source.clone(),
             offset: key_len as u64 + MAP_OFFSET,
-            s: info_key.obj,
+            s: info_key.obj.into(),
         })
     }
 
@@ -204,6 +250,53 @@ impl RootFile {
         &self.items
     }
 
+    /// Get the item of the given name. If several cycles of this item
+    /// exist, the highest one is returned. Like in ROOT, a specific
+    /// cycle may be requested with the `name;cycle` syntax.
+    pub fn get(&self, name: &str) -> Result<&FileItem, Error> {
+        if let Some((name, cycle)) = name.rsplit_once(';') {
+            if let Ok(cycle) = cycle.parse() {
+                return self.get_cycle(name, cycle);
+            }
+        }
+        self.items
+            .iter()
+            .filter(|item| item.object_name() == name)
+            .max_by_key(|item| item.cycle())
+            .ok_or_else(|| self.not_found(name))
+    }
+
+    /// Get the given cycle of the item of the given name
+    pub fn get_cycle(&self, name: &str, cycle: i16) -> Result<&FileItem, Error> {
+        self.items
+            .iter()
+            .find(|item| item.object_name() == name && item.cycle() == cycle)
+            .ok_or_else(|| self.not_found(&format!("{};{}", name, cycle)))
+    }
+
+    /// The `TProcessID` of the given number (see `Ref::pid`)
+    pub async fn process_id(&self, pid: u16) -> Result<ProcessId, Error> {
+        let item = self.get(&format!("ProcessID{}", pid))?;
+        match item.as_object().await? {
+            Object::ProcessId(process_id) => Ok(process_id),
+            _ => Err(format_err!(
+                "Expected `TProcessID` but found `{}`",
+                item.class_name()
+            )),
+        }
+    }
+
+    fn not_found(&self, name: &str) -> Error {
+        format_err!(
+            "Item `{}` not found in file. Available items: {:?}",
+            name,
+            self.items
+                .iter()
+                .map(|item| format!("{};{}", item.object_name(), item.cycle()))
+                .collect::<Vec<_>>()
+        )
+    }
+
     /// Translate the streamer info of this file to a YAML file
     pub async fn streamer_infos(&self) -> Result<Vec<TStreamerInfo>, Error> {
         let ctx = self.get_streamer_context().await?;
@@ -221,39 +314,81 @@ impl RootFile {
         Ok(())
     }
 
+    /// Machine readable description of the classes in the streamer
+    /// info and of the layout of all trees in this file
+    pub async fn schema(&self) -> Result<FileSchema, Error> {
+        let classes = self
+            .streamer_infos()
+            .await?
+            .iter()
+            .map(|info| info.schema())
+            .collect();
+        let mut trees = vec![];
+        for item in self
+            .items
+            .iter()
+            .filter(|item| item.class_name() == "TTree")
+        {
+            let tree = item.as_lazy_tree().await?;
+            trees.push(tree.schema(item.cycle())?);
+        }
+        Ok(FileSchema { classes, trees })
+    }
+
     /// Generate Rust code from the streamer info of this file
     pub async fn streamer_info_as_rust<W: fmt::Write>(&self, s: &mut W) -> Result<(), Error> {
+        self.write_rust(s, None).await
+    }
+
+    /// Generate Rust code for the given classes and all the classes
+    /// they depend on through their base classes and data members
+    pub async fn classes_as_rust<W: fmt::Write>(
+        &self,
+        classes: &[&str],
+        s: &mut W,
+    ) -> Result<(), Error> {
+        self.write_rust(s, Some(classes)).await
+    }
+
+    /// Generate Rust code for the given classes or for all classes
+    async fn write_rust<W: fmt::Write>(
+        &self,
+        s: &mut W,
+        selected: Option<&[&str]>,
+    ) -> Result<(), Error> {
+        let mut streamer_infos = self.streamer_infos().await?;
+        // Only the first of several versions of a class is used
+        let mut seen = HashSet::new();
+        streamer_infos.retain(|el| seen.insert(el.name().to_string()));
+        let classes = Classes::new(&streamer_infos);
+        let streamer_infos = match selected {
+            Some(selected) => classes.with_dependencies(selected)?,
+            None => streamer_infos.iter().collect(),
+        };
+
         // Add necessary imports at the top of the file
         writeln!(
             s,
             "{}",
             quote! {
-                use std::marker::PhantomData;
-                use nom::*;
-                use parsers::*;
-                use parsers::utils::*;
-                use core_types::*;
+                #[allow(unused_imports)]
+                use nom::{
+                    bytes::complete::*, combinator::*, multi::*, number::complete::*,
+                    sequence::*, IResult,
+                };
+                #[allow(unused_imports)]
+                use root_io::core::{class_object, object_pointer, parsers::*, types::*, Object};
             }
         )?;
-        let streamer_infos = self.streamer_infos().await?;
         // generate structs
         for el in &streamer_infos {
             // The structs contain comments which introduce line breaks; i.e. readable
-            writeln!(s, "{}", el.to_struct())?;
+            writeln!(s, "{}", el.to_struct(&classes))?;
         }
 
         // generate parsers
         for el in &streamer_infos {
-            // The parsers have no comments, but are ugly; We introduce some
-            // Linebreaks here to not have rustfmt choke later (doing it later
-            // is inconvinient since the comments in the structs might contain
-            // the patterns
-            let parsers = el.to_named_parser().to_string();
-            let parsers = parsers.replace(',', ",\n");
-            let parsers = parsers.replace(">>", ">>\n");
-            // macro names are generated as my_macro ! (...) by `quote`
-            let parsers = parsers.replace(" ! (", "!(");
-            writeln!(s, "{}", parsers)?;
+            writeln!(s, "{}", el.to_named_parser(&classes))?;
         }
         Ok(())
     }
@@ -265,12 +400,15 @@ mod test {
     use std::path::Path;
 
     use nom::multi::length_value;
+    #[cfg(feature = "http")]
     use reqwest::Url;
     use tokio;
 
+    #[cfg(feature = "http")]
     const SIMPLE_FILE_REMOTE: &str =
 	"https://github.com/cbourjau/alice-rs/blob/master/root-io/src/test_data/simple.root?raw=true";
 
+    #[cfg(feature = "http")]
     #[tokio::test]
     async fn read_cms_file_remote() {
         let url = "http://opendata.web.cern.ch/eos/opendata/cms/hidata/HIRun2010/HIAllPhysics/RECO/ZS-v2/0000/001DA267-7243-E011-B38F-001617C3B6CE.root";
@@ -318,6 +456,7 @@ mod test {
         file_header_test(local).await;
     }
 
+    #[cfg(feature = "http")]
     #[tokio::test]
     async fn file_header_test_remote() {
         let remote = Source::new(Url::parse(SIMPLE_FILE_REMOTE).unwrap());
@@ -366,6 +505,7 @@ mod test {
         directory_test(local).await;
     }
 
+    #[cfg(feature = "http")]
     #[tokio::test]
     async fn directory_test_remote() {
         let remote = Source::new(Url::parse(SIMPLE_FILE_REMOTE).unwrap());
@@ -389,10 +529,10 @@ mod test {
         let context = Context {
             source: source.clone(),
             offset: (key_len + k_map_offset) as u64,
-            s: key.obj,
+            s: key.obj.into(),
         };
 
-        match length_value(checked_byte_count, |i| tlist(i, &context))(&context.s) {
+        match length_value(checked_byte_count, |i| tlist(i, &context))(context.s.as_slice()) {
             Ok((_, l)) => {
                 assert_eq!(l.len(), 19);
             }
@@ -406,6 +546,7 @@ mod test {
         streamerinfo_test(local).await;
     }
 
+    #[cfg(feature = "http")]
     #[tokio::test]
     async fn streamerinfo_test_remote() {
         let remote = Url::parse(
@@ -414,4 +555,46 @@ mod test {
 	    .into();
         streamerinfo_test(remote).await;
     }
+
+    #[tokio::test]
+    async fn schema_local() {
+        let f = RootFile::new(Path::new("./src/test_data/simple.root"))
+            .await
+            .unwrap();
+        let schema = f.schema().await.unwrap();
+        assert_eq!(schema.classes.len(), 18);
+        let tattline = schema
+            .classes
+            .iter()
+            .find(|c| c.name == "TAttLine")
+            .unwrap();
+        let members: Vec<_> = tattline.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(members, ["fLineColor", "fLineStyle", "fLineWidth"]);
+        assert_eq!(tattline.members[0].type_id, 2);
+
+        assert_eq!(schema.trees.len(), 1);
+        let tree = &schema.trees[0];
+        assert_eq!(
+            (tree.name.as_str(), tree.cycle, tree.entries),
+            ("tree", 1, 4)
+        );
+        let leaves: Vec<_> = tree
+            .branches
+            .iter()
+            .map(|b| {
+                assert!(b.compressed_bytes > 0);
+                assert!(b.uncompressed_bytes > 0);
+                let l = &b.leaves[0];
+                (l.name.as_str(), l.class.as_str(), l.type_name.as_deref())
+            })
+            .collect();
+        assert_eq!(
+            leaves,
+            [
+                ("one", "TLeafI", Some("i32")),
+                ("two", "TLeafF", Some("f32")),
+                ("three", "TLeafC", Some("String"))
+            ]
+        );
+    }
 }
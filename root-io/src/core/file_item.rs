@@ -1,8 +1,11 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use failure::Error;
-use nom::multi::length_value;
+use nom::{multi::length_value, IResult};
 
-use crate::core::{checked_byte_count, decompress, Context, Source, TKeyHeader};
-use crate::tree_reader::{ttree, Tree};
+use crate::core::{
+    checked_byte_count, decompress, object, Context, Object, Raw, Source, TKeyHeader,
+};
+use crate::tree_reader::{entry_list, ttree, EntryList, Tree};
 
 /// Describes a single item within this file (e.g. a `Tree`)
 #[derive(Debug)]
@@ -24,6 +27,9 @@ impl FileItem {
     pub fn verbose_info(&self) -> String {
         format!("{:#?}", self.tkey_hdr)
     }
+
+    /// Name and class of this item in human readable form
+    #[deprecated(note = "Use `object_name` and `class_name` instead")]
     pub fn name(&self) -> String {
         format!(
             "`{}` of type `{}`",
@@ -31,6 +37,53 @@ impl FileItem {
         )
     }
 
+    /// Name of the class of the stored object (e.g. `TTree`)
+    pub fn class_name(&self) -> &str {
+        &self.tkey_hdr.class_name
+    }
+
+    /// Name of the stored object
+    pub fn object_name(&self) -> &str {
+        &self.tkey_hdr.obj_name
+    }
+
+    /// Title of the stored object
+    pub fn title(&self) -> &str {
+        &self.tkey_hdr.obj_title
+    }
+
+    /// Cycle of this item. Several versions (cycles) of an object with
+    /// the same name may be stored in a file; the highest cycle is the
+    /// most recent one.
+    pub fn cycle(&self) -> i16 {
+        self.tkey_hdr.cycle
+    }
+
+    /// Time at which this item was written. ROOT stores the local
+    /// time without time zone. `None` if the stored date is invalid.
+    pub fn datetime(&self) -> Option<NaiveDateTime> {
+        // Decode ROOT's `TDatime`
+        let d = self.tkey_hdr.datime;
+        NaiveDate::from_ymd_opt((d >> 26) as i32 + 1995, (d >> 22) & 0xF, (d >> 17) & 0x1F)?
+            .and_hms_opt((d >> 12) & 0x1F, (d >> 6) & 0x3F, d & 0x3F)
+    }
+
+    /// Size of the (possibly compressed) object in the file in bytes;
+    /// not including the key header
+    pub fn compressed_size(&self) -> u32 {
+        self.tkey_hdr.total_size - self.tkey_hdr.key_len as u32
+    }
+
+    /// Size of the uncompressed object in bytes
+    pub fn uncompressed_size(&self) -> u32 {
+        self.tkey_hdr.uncomp_len
+    }
+
+    /// The header of the key of this item
+    pub(crate) fn key_header(&self) -> &TKeyHeader {
+        &self.tkey_hdr
+    }
+
     async fn get_buffer(&self) -> Result<Vec<u8>, Error> {
         let start = self.tkey_hdr.seek_key + self.tkey_hdr.key_len as u64;
         let len = self.tkey_hdr.total_size - self.tkey_hdr.key_len as u32;
@@ -38,7 +91,13 @@ impl FileItem {
 
         let buf = if self.tkey_hdr.total_size < self.tkey_hdr.uncomp_len {
             // Decompress the read buffer; buf is Vec<u8>
-            let (_, buf) = decompress(comp_buf.as_slice()).unwrap();
+            let (_, buf) = decompress(comp_buf.as_slice()).map_err(|e| {
+                format_err!(
+                    "Failed to decompress `{}`: {:?}",
+                    self.tkey_hdr.obj_name,
+                    e.map(|e| e.code)
+                )
+            })?;
             buf
         } else {
             comp_buf
@@ -52,12 +111,24 @@ impl FileItem {
         Ok(Context {
             source: self.source.clone(),
             offset: (self.tkey_hdr.key_len + k_map_offset) as u64,
-            s: buffer,
+            s: buffer.into(),
         })
     }
 
     /// Parse this `FileItem` as a `Tree`
     pub async fn as_tree(&self) -> Result<Tree, Error> {
+        let tree = self.as_lazy_tree().await?;
+        // Surface errors in any of the branches right away
+        tree.parse_all()?;
+        Ok(tree)
+    }
+
+    /// Parse this `FileItem` as a `Tree` but defer the parsing of its
+    /// branches and leaves until they are accessed. This is much
+    /// cheaper than `as_tree` for wide trees of which only a few
+    /// branches are used. Errors in a branch surface when it is
+    /// accessed for the first time.
+    pub async fn as_lazy_tree(&self) -> Result<Tree, Error> {
         let ctx = self.get_context().await?;
         let buf = ctx.s.as_slice();
 
@@ -70,6 +141,71 @@ impl FileItem {
             _ => panic!(),
         }
     }
+
+    /// Parse this `FileItem` as an `EntryList`. Both, `TEntryList`
+    /// and the older `TEventList` are supported.
+    pub async fn as_entry_list(&self) -> Result<EntryList, Error> {
+        let ctx = self.get_context().await?;
+        let buf = ctx.s.as_slice();
+        let res = length_value(checked_byte_count, |i| {
+            let raw = Raw {
+                classinfo: &self.tkey_hdr.class_name,
+                obj: i,
+            };
+            entry_list(&raw, &ctx)
+        })(buf);
+        match res {
+            Ok((_, obj)) => Ok(obj),
+            Err(e) => Err(format_err!(
+                "Failed to parse `{}` as entry list: {:?}",
+                self.tkey_hdr.class_name,
+                e.map(|e| e.code)
+            )),
+        }
+    }
+
+    /// Parse this `FileItem` as a generic `Object`. Containers such as
+    /// `TList`, `TObjArray` or `TMap` are decoded recursively; objects
+    /// of unsupported classes are returned as `Object::Unknown`.
+    pub async fn as_object(&self) -> Result<Object, Error> {
+        let ctx = self.get_context().await?;
+        let buf = ctx.s.as_slice();
+        let res = length_value(checked_byte_count, |i| {
+            let raw = Raw {
+                classinfo: &self.tkey_hdr.class_name,
+                obj: i,
+            };
+            object(&raw, &ctx)
+        })(buf);
+        match res {
+            Ok((_, obj)) => Ok(obj),
+            Err(e) => Err(format_err!(
+                "Failed to parse `{}` as object: {:?}",
+                self.tkey_hdr.class_name,
+                e.map(|e| e.code)
+            )),
+        }
+    }
+
+    /// Parse this `FileItem` with the given parser, e.g. one
+    /// generated by `RootFile::streamer_info_as_rust`. The parser
+    /// receives the object without its leading byte count.
+    pub async fn parse_with<F, O>(&self, parser: F) -> Result<O, Error>
+    where
+        F: for<'s> Fn(&'s [u8], &'s Context) -> IResult<&'s [u8], O>,
+    {
+        let ctx = self.get_context().await?;
+        let buf = ctx.s.as_slice();
+        let res = length_value(checked_byte_count, |i| parser(i, &ctx))(buf);
+        match res {
+            Ok((_, obj)) => Ok(obj),
+            Err(e) => Err(format_err!(
+                "Failed to parse `{}`: {:?}",
+                self.tkey_hdr.class_name,
+                e.map(|e| e.code)
+            )),
+        }
+    }
 }
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
@@ -82,7 +218,16 @@ mod tests {
         let path = Path::new("./src/test_data/simple.root");
         let f = RootFile::new(path).await.expect("Failed to open file");
         assert_eq!(f.items().len(), 1);
-        assert_eq!(f.items()[0].tkey_hdr.obj_name, "tree");
+        let item = f.get("tree").unwrap();
+        assert_eq!(item.object_name(), "tree");
+        assert_eq!(item.class_name(), "TTree");
+        assert_eq!(item.cycle(), 1);
+        assert_eq!(item.datetime().unwrap().to_string(), "2016-02-08 11:00:28");
+        assert!(item.compressed_size() < item.uncompressed_size());
+        assert!(f.get("tree;1").is_ok());
+        assert!(f.get("tree;2").is_err());
+        assert!(f.get_cycle("tree", 1).is_ok());
+        assert!(f.get("no_such_tree").is_err());
         // Only streamers; not rules
         assert_eq!(f.streamer_infos().await.unwrap().len(), 18);
     }
@@ -98,8 +243,8 @@ mod tests {
             .expect("Failed to open file");
 
         assert_eq!(f.items().len(), 2);
-        assert_eq!(f.items()[0].tkey_hdr.obj_name, "esdTree");
-        assert_eq!(f.items()[1].tkey_hdr.obj_name, "HLTesdTree");
+        assert_eq!(f.items()[0].object_name(), "esdTree");
+        assert_eq!(f.items()[1].object_name(), "HLTesdTree");
         assert_eq!(f.streamer_infos().await.unwrap().len(), 87);
     }
 }
@@ -2,16 +2,24 @@
 //! the self-description of a root file. These parsers can be used to
 //! build new parsers using the [root-ls](https://github.com/cbourjau/alice-rs) cli.
 
+mod compression;
 mod data_source;
 mod file;
 mod file_item;
+mod objects;
 pub mod parsers;
+mod recover;
+mod refs;
+#[cfg(test)]
+pub(crate) mod test_utils;
 mod tkey;
 mod tstreamer;
 mod tstreamerinfo;
 mod typeid;
 pub mod types;
+mod verify;
 
+pub(crate) use self::objects::{is_decodable, object};
 pub(crate) use self::parsers::*;
 pub(crate) use self::tkey::*;
 pub(crate) use self::tstreamer::{tstreamer, TStreamer};
@@ -19,6 +27,10 @@ pub(crate) use self::tstreamerinfo::{tstreamerinfo, TStreamerInfo};
 pub(crate) use self::typeid::*;
 pub(crate) use self::types::*;
 
-pub use self::data_source::Source;
+pub use self::compression::{Compression, CompressionAlgorithm};
+pub use self::data_source::{Fetch, Source};
 pub use self::file::RootFile;
 pub use self::file_item::FileItem;
+pub use self::objects::{class_object, object_pointer, Object};
+pub use self::refs::{ProcessId, Ref, RefArray};
+pub use self::verify::{Issue, VerifyReport};
@@ -0,0 +1,482 @@
+use nom::{
+    combinator::{cond, map},
+    error::{make_error, ErrorKind},
+    multi::{count, length_data},
+    number::complete::*,
+    IResult,
+};
+
+use crate::core::parsers::*;
+use crate::core::refs::*;
+use crate::core::types::*;
+use crate::tree_reader::Value;
+
+/// Parser decoding the `Raw` buffer of a class into an `Object`
+type ObjectReader = for<'s> fn(&Raw<'s>, &'s Context) -> IResult<&'s [u8], Object>;
+
+/// Classes which can be decoded into an `Object`. `TParameter<T>` is
+/// handled separately since its name depends on the template
+/// parameter.
+const READERS: &[(&str, ObjectReader)] = &[
+    ("TList", tlist_object),
+    ("THashList", tlist_object),
+    ("TObjArray", tobjarray_object),
+    ("TMap", tmap_object),
+    ("TObjString", tobjstring_object),
+    ("TNamed", tnamed_object),
+    ("TClonesArray", tclonesarray_object),
+    ("TRef", |raw, _| map(tref, Object::Ref)(raw.obj)),
+    ("TRefArray", |raw, _| {
+        map(trefarray, Object::RefArray)(raw.obj)
+    }),
+    ("TProcessID", |raw, _| {
+        map(tprocessid, Object::ProcessId)(raw.obj)
+    }),
+];
+
+/// A generic ROOT object decoded at runtime. Containers hold their
+/// elements as `Object`s themselves; elements of classes which are
+/// not known to this crate are kept as `Object::Unknown`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Object {
+    /// A `TList` or `THashList`
+    List { name: String, items: Vec<Object> },
+    /// A `TObjArray`
+    ObjArray { name: String, items: Vec<Object> },
+    /// A `TMap` of (key, value) pairs
+    Map {
+        name: String,
+        entries: Vec<(Object, Object)>,
+    },
+    /// A `TClonesArray` of objects of the class `class`
+    ClonesArray {
+        name: String,
+        class: String,
+        items: Vec<Object>,
+    },
+    /// A `TClonesArray` of `len` objects of the class `class` which
+    /// was streamed member-wise (i.e. the first member of all
+    /// objects, then the second one, etc.). This is ROOT's default
+    /// but decoding it requires the streamer info of the class and is
+    /// not supported (yet); `data` is the raw buffer of the objects
+    MemberWiseClonesArray {
+        name: String,
+        class: String,
+        len: usize,
+        data: Vec<u8>,
+    },
+    /// A `TObjString`
+    ObjString(String),
+    /// A `TNamed`
+    Named { name: String, title: String },
+    /// A `TParameter<T>`
+    Parameter { name: String, value: Value },
+    /// A `TRef`
+    Ref(Ref),
+    /// A `TRefArray`
+    RefArray(RefArray),
+    /// A `TProcessID`
+    ProcessId(ProcessId),
+    /// An object of a class which cannot be decoded (yet); `data` is
+    /// the raw (uncompressed) buffer of the object
+    Unknown { class: String, data: Vec<u8> },
+    /// A null pointer (e.g. an empty slot in a `TObjArray`)
+    Null,
+}
+
+impl Object {
+    /// The name of this object as returned by ROOT's `GetName`. This
+    /// is the string itself for `ObjString`s.
+    pub fn name(&self) -> Option<&str> {
+        use Object::*;
+        match self {
+            List { name, .. }
+            | ObjArray { name, .. }
+            | Map { name, .. }
+            | ClonesArray { name, .. }
+            | MemberWiseClonesArray { name, .. }
+            | Named { name, .. }
+            | Parameter { name, .. }
+            | ObjString(name) => Some(name),
+            RefArray(arr) => Some(arr.name()),
+            ProcessId(pid) => Some(pid.name()),
+            Ref(_) | Unknown { .. } | Null => None,
+        }
+    }
+
+    /// The elements of a list or array; `None` for other objects
+    pub fn items(&self) -> Option<&[Object]> {
+        match self {
+            Object::List { items, .. }
+            | Object::ObjArray { items, .. }
+            | Object::ClonesArray { items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Find an element of this container by name. For `Map`s, the
+    /// value of the entry whose key has the given name is returned.
+    pub fn get(&self, name: &str) -> Option<&Object> {
+        match self {
+            Object::Map { entries, .. } => entries
+                .iter()
+                .find(|(k, _)| k.name() == Some(name))
+                .map(|(_, v)| v),
+            _ => self.items()?.iter().find(|obj| obj.name() == Some(name)),
+        }
+    }
+}
+
+/// Find the reader for the given class name
+fn reader(class: &str) -> Option<ObjectReader> {
+    if class.starts_with("TParameter<") {
+        return Some(tparameter_object);
+    }
+    READERS
+        .iter()
+        .find(|(name, _)| *name == class)
+        .map(|(_, reader)| *reader)
+}
+
+/// Decode the given `Raw` object. Objects of unknown classes are
+/// returned as `Object::Unknown` rather than as an error.
+pub(crate) fn object<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], Object> {
+    match reader(raw.classinfo) {
+        Some(reader) => reader(raw, context),
+        // Null pointer
+        None if raw.classinfo.is_empty() => Ok((raw.obj, Object::Null)),
+        None => Ok((
+            &raw.obj[raw.obj.len()..],
+            Object::Unknown {
+                class: raw.classinfo.to_string(),
+                data: raw.obj.to_vec(),
+            },
+        )),
+    }
+}
+
+/// Can objects of the given class be decoded into something other
+/// than `Object::Unknown`?
+pub(crate) fn is_decodable(class: &str) -> bool {
+    reader(class).is_some()
+}
+
+/// Parse an object pointer (class tag and object) and decode it. Null
+/// pointers are returned as `Object::Null`.
+pub fn object_pointer<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], Object> {
+    let (i, raw) = raw(i, context)?;
+    let (_, obj) = object(&raw, context)?;
+    Ok((i, obj))
+}
+
+/// Decode an object of the class `class` which is not preceded by
+/// its class info, e.g. a data member of another object. The input
+/// starts after the byte count of the object.
+pub fn class_object<'s>(
+    class: &'s str,
+    i: &'s [u8],
+    context: &'s Context,
+) -> IResult<&'s [u8], Object> {
+    object(
+        &Raw {
+            classinfo: class,
+            obj: i,
+        },
+        context,
+    )
+}
+
+fn tlist_object<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], Object> {
+    let i = raw.obj;
+    let (i, _ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, items) = count(
+        |i| {
+            let (i, obj) = object_pointer(i, context)?;
+            // Option string of the element
+            let (i, _) = length_data(be_u8)(i)?;
+            Ok((i, obj))
+        },
+        n as usize,
+    )(i)?;
+    Ok((i, Object::List { name, items }))
+}
+
+fn tobjarray_object<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], Object> {
+    let i = raw.obj;
+    let (i, _ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, _low) = be_i32(i)?;
+    let (i, items) = count(|i| object_pointer(i, context), n as usize)(i)?;
+    Ok((i, Object::ObjArray { name, items }))
+}
+
+fn tmap_object<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], Object> {
+    let i = raw.obj;
+    let (i, _ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, entries) = count(
+        |i| {
+            let (i, key) = object_pointer(i, context)?;
+            let (i, value) = object_pointer(i, context)?;
+            Ok((i, (key, value)))
+        },
+        n as usize,
+    )(i)?;
+    Ok((i, Object::Map { name, entries }))
+}
+
+fn tobjstring_object<'s>(raw: &Raw<'s>, _context: &'s Context) -> IResult<&'s [u8], Object> {
+    map(tobjstring, Object::ObjString)(raw.obj)
+}
+
+fn tnamed_object<'s>(raw: &Raw<'s>, _context: &'s Context) -> IResult<&'s [u8], Object> {
+    map(tnamed, |TNamed { name, title }| Object::Named {
+        name,
+        title,
+    })(raw.obj)
+}
+
+fn tparameter_object<'s>(raw: &Raw<'s>, _context: &'s Context) -> IResult<&'s [u8], Object> {
+    let type_name = raw
+        .classinfo
+        .trim_start_matches("TParameter<")
+        .trim_end_matches('>')
+        .trim();
+    let parser: fn(&[u8]) -> IResult<&[u8], Value> = match type_name {
+        "bool" | "Bool_t" => |i| map(be_u8, |v| Value::Bool(v != 0))(i),
+        "int" | "Int_t" => |i| map(be_i32, Value::I32)(i),
+        "unsigned int" | "UInt_t" => |i| map(be_u32, Value::U32)(i),
+        "long" | "Long_t" | "long long" | "Long64_t" => |i| map(be_i64, Value::I64)(i),
+        "unsigned long" | "ULong_t" | "unsigned long long" | "ULong64_t" => {
+            |i| map(be_u64, Value::U64)(i)
+        }
+        "float" | "Float_t" => |i| map(be_f32, Value::F32)(i),
+        "double" | "Double_t" => |i| map(be_f64, Value::F64)(i),
+        _ => return Err(nom::Err::Failure(make_error(raw.obj, ErrorKind::Tag))),
+    };
+    let i = raw.obj;
+    let (i, ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    let (i, value) = parser(i)?;
+    let (i, _merge_mode) = cond(ver >= 2, be_u8)(i)?;
+    Ok((i, Object::Parameter { name, value }))
+}
+
+/// Parse a `TClonesArray`. Arrays streamed object-wise are decoded
+/// element by element. ROOT writes most `TClonesArray`s member-wise,
+/// though, which requires the streamer info of the element class.
+/// Such arrays are returned as `Object::MemberWiseClonesArray`.
+fn tclonesarray_object<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], Object> {
+    let i = raw.obj;
+    let (i, _ver) = be_u16(i)?;
+    let (i, tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    // Element class and its version as "name;version"
+    let (i, class_and_version) = string(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, _low) = be_i32(i)?;
+    let class = class_and_version
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if tobj.bits.contains(TObjectFlags::BYPASS_STREAMER) {
+        return Ok((
+            &i[i.len()..],
+            Object::MemberWiseClonesArray {
+                name,
+                class,
+                len: n as usize,
+                data: i.to_vec(),
+            },
+        ));
+    }
+    let element = |i: &'s [u8]| -> IResult<&'s [u8], Object> {
+        let (i, is_present) = be_u8(i)?;
+        if is_present == 0 {
+            return Ok((i, Object::Null));
+        }
+        let (i, data) = length_data(checked_byte_count)(i)?;
+        let element_raw = Raw {
+            classinfo: &class,
+            obj: data,
+        };
+        let (_, obj) =
+            object(&element_raw, context).map_err(|e| e.map(|e| make_error(data, e.code)))?;
+        Ok((i, obj))
+    };
+    let (i, items) = count(element, n as usize)(i)?;
+    Ok((i, Object::ClonesArray { name, class, items }))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use nom::multi::length_value;
+    use std::path::Path;
+
+    use crate::core::test_utils::Writer;
+    use crate::core::RootFile;
+
+    fn context(s: Vec<u8>) -> Context {
+        Context {
+            source: Path::new("./src/test_data/simple.root").into(),
+            offset: 0,
+            s: s.into(),
+        }
+    }
+
+    #[test]
+    fn list_of_objects() {
+        let mut w = Writer::default();
+        w.bytes([0, 5]);
+        w.tobject();
+        w.string("output");
+        w.bytes(3i32.to_be_bytes());
+        w.object("TObjString", |w| {
+            w.bytes([0, 1]);
+            w.tobject();
+            w.string("hello");
+        });
+        w.string("");
+        w.object("TParameter<double>", |w| {
+            w.bytes([0, 2]);
+            w.tobject();
+            w.string("lumi");
+            w.bytes(2.5f64.to_be_bytes());
+            w.bytes([0]);
+        });
+        w.string("");
+        w.object("TMap", |w| {
+            w.bytes([0, 3]);
+            w.tobject();
+            w.string("");
+            w.bytes(1i32.to_be_bytes());
+            w.object("TNamed", |w| w.tnamed_members("key", ""));
+            // Null value
+            w.null();
+        });
+        w.string("");
+
+        let ctx = context(w.buf);
+        let raw = Raw {
+            classinfo: "TList",
+            obj: ctx.s.as_slice(),
+        };
+        let (_, obj) = object(&raw, &ctx).unwrap();
+        assert_eq!(obj.name(), Some("output"));
+        assert_eq!(obj.items().unwrap().len(), 3);
+        assert_eq!(obj.get("hello"), Some(&Object::ObjString("hello".into())));
+        assert_eq!(
+            obj.get("lumi"),
+            Some(&Object::Parameter {
+                name: "lumi".into(),
+                value: Value::F64(2.5)
+            })
+        );
+        let map = &obj.items().unwrap()[2];
+        assert_eq!(map.get("key"), Some(&Object::Null));
+    }
+
+    /// A `TClonesArray` of `TNamed`s with the given bits of its
+    /// `TObject`; the second slot is empty
+    fn clones_array_bytes(bits: u32) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.bytes([0, 4]);
+        w.tobject_with(0, bits);
+        w.string("tracks");
+        w.string("TNamed;1");
+        w.bytes(3i32.to_be_bytes());
+        w.bytes(0i32.to_be_bytes());
+        for name in ["first", "", "third"] {
+            if name.is_empty() {
+                w.bytes([0]);
+            } else {
+                w.bytes([1]);
+                w.tnamed(name, "");
+            }
+        }
+        w.buf
+    }
+
+    #[test]
+    fn clones_array() {
+        let ctx = context(clones_array_bytes(0x0300_0000));
+        let raw = Raw {
+            classinfo: "TClonesArray",
+            obj: ctx.s.as_slice(),
+        };
+        let (rest, obj) = object(&raw, &ctx).unwrap();
+        assert!(rest.is_empty());
+        let named = |name: &str| Object::Named {
+            name: name.into(),
+            title: "".into(),
+        };
+        assert_eq!(
+            obj,
+            Object::ClonesArray {
+                name: "tracks".into(),
+                class: "TNamed".into(),
+                items: vec![named("first"), Object::Null, named("third")],
+            }
+        );
+        assert_eq!(obj.get("third"), Some(&named("third")));
+
+        // Broken elements are reported rather than hidden
+        let mut truncated = clones_array_bytes(0x0300_0000);
+        truncated.truncate(truncated.len() - 4);
+        let ctx = context(truncated);
+        let raw = Raw {
+            classinfo: "TClonesArray",
+            obj: ctx.s.as_slice(),
+        };
+        assert!(object(&raw, &ctx).is_err());
+
+        let ctx = context(clones_array_bytes(0x0300_1000));
+        let raw = Raw {
+            classinfo: "TClonesArray",
+            obj: ctx.s.as_slice(),
+        };
+        let (_, obj) = object(&raw, &ctx).unwrap();
+        assert!(matches!(
+            obj,
+            Object::MemberWiseClonesArray { ref class, len: 3, .. } if class == "TNamed"
+        ));
+        assert_eq!(obj.name(), Some("tracks"));
+    }
+
+    #[tokio::test]
+    async fn streamer_info_list() {
+        let path = Path::new("./src/test_data/simple.root");
+        let f = RootFile::new(path).await.unwrap();
+        let ctx = f.get_streamer_context().await.unwrap();
+        let obj = length_value(checked_byte_count, |i| {
+            object(
+                &Raw {
+                    classinfo: "TList",
+                    obj: i,
+                },
+                &ctx,
+            )
+        })(ctx.s.as_slice())
+        .unwrap()
+        .1;
+        let items = obj.items().unwrap();
+        assert_eq!(items.len(), 19);
+        let n_infos = items
+            .iter()
+            .filter(|obj| matches!(obj, Object::Unknown { class, .. } if class == "TStreamerInfo"))
+            .count();
+        assert_eq!(n_infos, 18);
+        // Schema evolution rules
+        assert!(matches!(items[18], Object::List { .. }));
+    }
+}
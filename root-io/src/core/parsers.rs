@@ -17,9 +17,9 @@ use nom::{
     self,
     bytes::complete::{take, take_until},
     combinator::{all_consuming, cond, eof, map, map_res, rest, verify},
-    error::ParseError,
+    error::{make_error, ErrorKind, ParseError},
     multi::{count, length_data, length_value},
-    number::complete::{be_i32, be_u16, be_u32, be_u64, be_u8},
+    number::complete::{be_i32, be_u16, be_u32, be_u64, be_u8, le_u24},
     sequence::{pair, tuple},
     IResult,
 };
@@ -166,22 +166,34 @@ fn decode_reader<'s>(bytes: &'s [u8], magic: &[u8]) -> nom::IResult<&'s [u8], Ve
         b"XZ" => map_res(rest, |bytes| {
             let mut ret = vec![];
             let mut reader = std::io::BufReader::new(bytes);
-            xz_decompress(&mut reader, &mut ret).unwrap();
+            xz_decompress(&mut reader, &mut ret).map_err(|e| format_err!("{:?}", e))?;
             Ok::<_, Error>(ret)
         })(bytes),
         b"L4" => {
             let (bytes, _checksum) = be_u64(bytes)?;
             map_res(rest, lz4_decompress)(bytes)
         }
-        _ => panic!(), // m => return Err(format_err!("Unsupported compression format `{}`", m)),
+        _ => Err(nom::Err::Failure(make_error(bytes, ErrorKind::Tag))),
     }
 }
 
-/// Decompress the given buffer. Figures out the compression algorithm from the preceeding \"magic\" bytes
+/// Decompress the given buffer. Figures out the compression algorithm
+/// from the preceeding \"magic\" bytes. Large objects are compressed
+/// in several consecutive chunks, each with its own header.
 pub fn decompress(input: &[u8]) -> nom::IResult<&[u8], Vec<u8>> {
-    let (input, magic) = take(2usize)(input)?;
-    let (input, _header) = take(7usize)(input)?;
-    decode_reader(input, magic)
+    let mut input = input;
+    let mut ret = vec![];
+    while !input.is_empty() {
+        let (i, magic) = take(2usize)(input)?;
+        let (i, _method) = be_u8(i)?;
+        let (i, compressed_len) = le_u24(i)?;
+        let (i, _uncompressed_len) = le_u24(i)?;
+        let (i, chunk) = take(compressed_len)(i)?;
+        let (_, buf) = decode_reader(chunk, magic)?;
+        ret.extend(buf);
+        input = i;
+    }
+    Ok((input, ret))
 }
 
 /// Parse a null terminated string
@@ -324,6 +336,23 @@ pub fn parse_custom_mantissa(input: &[u8], nbits: usize) -> nom::IResult<&[u8],
     })
 }
 
+/// Parse an array data member given as a pointer (e.g. `Double_t*
+/// fArr; //[fN]`). Such arrays are preceded by a byte which is zero
+/// if the pointer is null; otherwise, `n` elements follow where `n`
+/// is the value of the counter member.
+pub fn basic_pointer<'s, F, O>(
+    parser: F,
+    n: usize,
+) -> impl FnMut(&'s [u8]) -> nom::IResult<&'s [u8], Vec<O>>
+where
+    F: Fn(&'s [u8]) -> nom::IResult<&'s [u8], O>,
+{
+    move |i| {
+        let (i, is_set) = be_u8(i)?;
+        count(&parser, if is_set == 0 { 0 } else { n })(i)
+    }
+}
+
 /// Parse a sized object and check that it used all its bytes.
 pub fn parse_sized_object<'s, F, O>(parser: F) -> impl Fn(&'s [u8]) -> nom::IResult<&'s [u8], O>
 where
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use failure::Error;
+use nom::number::complete::{be_i16, be_i32};
+
+use crate::core::file::{read_header, read_keys};
+use crate::core::*;
+use crate::tree_reader::Container;
+
+/// Size of the beginning of a key header up to and including the
+/// length of the key
+const KEY_PREFIX_SIZE: u64 = 16;
+
+/// Number of bytes read at the position of a presumed key. This is
+/// enough for the header of most keys such that each key needs only
+/// one read.
+const KEY_READ_SIZE: u64 = 512;
+
+/// Result of scanning a file for keys
+#[derive(Default)]
+struct Scan {
+    /// Keys of the top directory
+    keys: Vec<TKeyHeader>,
+    /// Positions of all baskets
+    baskets: HashSet<u64>,
+    /// The `StreamerInfo` key, if found
+    streamer_info: Option<TKeyHeader>,
+    /// Position after the last intact key
+    end: u64,
+    warnings: Vec<String>,
+}
+
+/// What was found at a position in the file
+enum Record {
+    Key(TKeyHeader),
+    /// A free segment of the given size
+    Free(u64),
+    /// The end of the written data
+    End,
+    /// Neither a key nor a free segment
+    Invalid,
+}
+
+/// Read the record at `pos` of a file of the given `size`. I/O
+/// errors are returned as `Err`.
+async fn record_at(source: &Source, pos: u64, size: u64) -> Result<Record, Error> {
+    if pos + KEY_PREFIX_SIZE > size {
+        return Ok(Record::End);
+    }
+    let mut buf = source.fetch(pos, KEY_READ_SIZE.min(size - pos)).await?;
+    let (_, total_size) = be_i32::<_, ()>(&buf[..4]).expect("Buffer is large enough");
+    let (_, key_len) = be_i16::<_, ()>(&buf[14..16]).expect("Buffer is large enough");
+    match total_size {
+        0 => return Ok(Record::End),
+        // Free segments start with their negative size
+        n if n < 0 => return Ok(Record::Free(n.unsigned_abs() as u64)),
+        _ => {}
+    }
+    if key_len <= 0 || pos + key_len as u64 > size {
+        return Ok(Record::Invalid);
+    }
+    if key_len as u64 > buf.len() as u64 {
+        buf = source.fetch(pos, key_len as u64).await?;
+    }
+    match tkey_header(&buf) {
+        Ok((_, hdr)) if hdr.seek_key == pos && hdr.total_size >= hdr.key_len as u32 => {
+            Ok(Record::Key(hdr))
+        }
+        _ => Ok(Record::Invalid),
+    }
+}
+
+/// Scan the file of the given `size` for keys starting at `begin`;
+/// this is modeled after ROOT's `TFile::Recover`
+async fn scan(source: &Source, begin: u64, size: u64) -> Result<Scan, Error> {
+    let mut scan = Scan::default();
+    let mut pos = begin;
+    loop {
+        let hdr = match record_at(source, pos, size).await? {
+            Record::Key(hdr) => hdr,
+            Record::Free(gap) => {
+                pos += gap;
+                continue;
+            }
+            Record::End => break,
+            Record::Invalid => {
+                scan.warnings.push(format!(
+                    "No valid key found at byte {}; stopped scanning",
+                    pos
+                ));
+                break;
+            }
+        };
+        let end = pos + hdr.total_size as u64;
+        if end > size {
+            scan.warnings.push(format!(
+                "Key `{};{}` of type `{}` at byte {} is truncated",
+                hdr.obj_name, hdr.cycle, hdr.class_name, pos
+            ));
+            break;
+        }
+        match hdr.class_name.as_str() {
+            "TBasket" => {
+                scan.baskets.insert(pos);
+            }
+            // The top directory, the list of keys and the free segments
+            "TFile" => {}
+            "TList" if hdr.obj_name == "StreamerInfo" => scan.streamer_info = Some(hdr),
+            _ if hdr.seek_pdir == begin => scan.keys.push(hdr),
+            // Keys of sub-directories
+            _ => {}
+        }
+        pos = end;
+    }
+    scan.end = pos;
+    Ok(scan)
+}
+
+impl RootFile {
+    /// Open a file like `RootFile::new` but recover it if its list of
+    /// keys is missing or corrupt, as is often the case for files of
+    /// crashed jobs. The keys are then found by scanning the file;
+    /// whatever could not be recovered is reported in the warnings of
+    /// the returned file. I/O errors are not recovered from.
+    pub async fn recover<S: Into<Source>>(source: S) -> Result<Self, Error> {
+        let source = source.into();
+        let mut hdr = read_header(&source).await?;
+        let size = source.size().await?;
+        let err = match read_keys(&source, &hdr, Some(size)).await? {
+            Ok(keys) => return Ok(RootFile::from_keys(source, hdr, &keys)),
+            Err(e) => e,
+        };
+        let mut scan = scan(&source, hdr.begin as u64, size).await?;
+        let mut warnings = vec![format!(
+            "The list of keys is missing or corrupt ({}); recovered {} keys by scanning the file",
+            err,
+            scan.keys.len()
+        )];
+        warnings.append(&mut scan.warnings);
+        match &scan.streamer_info {
+            Some(info) => {
+                hdr.seek_info = info.seek_key;
+                hdr.nbytes_info = info.total_size as i32;
+            }
+            None => warnings.push("The StreamerInfo of the file was not found".to_string()),
+        }
+        hdr.end = scan.end;
+        let items: Vec<_> = scan
+            .keys
+            .iter()
+            .map(|k_hdr| FileItem::new(k_hdr, source.clone()))
+            .collect();
+
+        // Check which baskets of the recovered trees are available
+        let mut referenced = HashSet::new();
+        for item in items.iter().filter(|item| item.class_name() == "TTree") {
+            let name = format!("{};{}", item.object_name(), item.cycle());
+            let branches = item
+                .as_lazy_tree()
+                .await
+                .and_then(|tree| Ok(tree.branches()?.into_iter().cloned().collect()));
+            let branches: Vec<_> = match branches {
+                Ok(branches) => branches,
+                Err(e) => {
+                    warnings.push(format!("Failed to read tree `{}`: {}", name, e));
+                    continue;
+                }
+            };
+            let (mut n_baskets, mut n_missing) = (0, 0);
+            for branch in &branches {
+                for container in branch.containers() {
+                    if let Container::OnDisk(_, seek, _) = container {
+                        n_baskets += 1;
+                        if !scan.baskets.contains(seek) {
+                            n_missing += 1;
+                        }
+                        referenced.insert(*seek);
+                    }
+                }
+            }
+            if n_missing > 0 {
+                warnings.push(format!(
+                    "{} of the {} baskets of tree `{}` are missing",
+                    n_missing, n_baskets, name
+                ));
+            }
+        }
+        let n_orphans = scan.baskets.difference(&referenced).count();
+        if n_orphans > 0 {
+            warnings.push(format!(
+                "{} baskets were written after the last saved header of their tree and are lost",
+                n_orphans
+            ));
+        }
+        Ok(RootFile {
+            source,
+            hdr,
+            items,
+            warnings,
+        })
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::path::Path;
+
+    use failure::Error;
+
+    use crate::core::RootFile;
+
+    #[tokio::test]
+    async fn recover_truncated_file() {
+        let path = Path::new("./src/test_data/simple.root");
+        let intact = RootFile::recover(path).await.unwrap();
+        assert!(intact.warnings().is_empty());
+
+        let buf = std::fs::read(path).unwrap();
+        let hdr = intact.items()[0].key_header();
+        // Cut the file right after the tree
+        let tree_end = (hdr.seek_key + hdr.total_size as u64) as usize;
+        let path = std::env::temp_dir().join(format!(
+            "root-io-recover-truncated-{}.root",
+            std::process::id()
+        ));
+        std::fs::write(&path, &buf[..tree_end + 10]).unwrap();
+        let opened = RootFile::new(path.as_path()).await;
+        let recovered = async {
+            let f = RootFile::recover(path.as_path()).await?;
+            let tree = f.get("tree")?.as_tree().await;
+            Ok::<_, Error>((f, tree))
+        }
+        .await;
+        std::fs::remove_file(&path).unwrap();
+        // Recovery is opt-in
+        assert!(opened.is_err());
+        let (f, tree) = recovered.unwrap();
+        assert!(!f.warnings().is_empty());
+        assert_eq!(f.items().len(), 1);
+        assert_eq!(tree.unwrap().entries(), 4);
+    }
+}
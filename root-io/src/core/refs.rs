@@ -0,0 +1,162 @@
+use nom::{
+    combinator::verify,
+    multi::{count, length_value},
+    number::complete::*,
+    IResult,
+};
+
+use crate::core::parsers::*;
+use crate::core::types::*;
+
+/// Mask selecting the object number from the unique ID of a
+/// referenced object; the upper bits are only used in memory
+const OBJECT_NUMBER_MASK: u32 = 0x00FF_FFFF;
+
+/// A persistent reference to another object (ROOT's `TRef`). The
+/// referenced object is identified by its unique ID within the
+/// process (`TProcessID`) which created it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ref {
+    unique_id: u32,
+    pid: u16,
+}
+
+impl Ref {
+    /// Object number of the referenced object within its process
+    pub fn object_number(&self) -> u32 {
+        self.unique_id & OBJECT_NUMBER_MASK
+    }
+
+    /// Number of the `TProcessID` of the referenced object. The
+    /// process is stored in the file under the key `ProcessID<pid>`;
+    /// see `RootFile::process_id`.
+    pub fn pid(&self) -> u16 {
+        self.pid
+    }
+}
+
+/// An array of references to objects of the same process (ROOT's
+/// `TRefArray`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefArray {
+    name: String,
+    pid: u16,
+    unique_ids: Vec<u32>,
+}
+
+impl RefArray {
+    /// The name of this array
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The references stored in this array. Empty slots have the
+    /// object number 0.
+    pub fn refs(&self) -> Vec<Ref> {
+        self.unique_ids
+            .iter()
+            .map(|&unique_id| Ref {
+                unique_id,
+                pid: self.pid,
+            })
+            .collect()
+    }
+}
+
+/// Identifies the process which created referenced objects (ROOT's
+/// `TProcessID`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessId {
+    name: String,
+    guid: String,
+}
+
+impl ProcessId {
+    /// The name of this process ID (e.g. `ProcessID0`)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The globally unique ID of the process
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+}
+
+/// Parse a `TRef`. References identifying their process by UUID
+/// rather than by a `TProcessID` are not supported.
+pub(crate) fn tref(i: &[u8]) -> IResult<&[u8], Ref> {
+    let (i, tobj) = verify(tobject, |o| !o.bits.contains(TObjectFlags::HAS_UUID))(i)?;
+    let (i, pid) = be_u16(i)?;
+    Ok((
+        i,
+        Ref {
+            unique_id: tobj.id,
+            pid,
+        },
+    ))
+}
+
+/// Parse a `TRefArray`
+pub(crate) fn trefarray(i: &[u8]) -> IResult<&[u8], RefArray> {
+    let (i, _ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, name) = string(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, _low) = be_i32(i)?;
+    let (i, pid) = be_u16(i)?;
+    let (i, unique_ids) = count(be_u32, n as usize)(i)?;
+    Ok((
+        i,
+        RefArray {
+            name,
+            pid,
+            unique_ids,
+        },
+    ))
+}
+
+/// Parse a `TProcessID`
+pub(crate) fn tprocessid(i: &[u8]) -> IResult<&[u8], ProcessId> {
+    let (i, _ver) = be_u16(i)?;
+    let (i, named) = length_value(checked_byte_count, tnamed)(i)?;
+    Ok((
+        i,
+        ProcessId {
+            name: named.name,
+            guid: named.title,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils::Writer;
+
+    #[test]
+    fn parse_refs() {
+        let mut w = Writer::default();
+        w.tobject_with(0x0100_002A, 0x0300_0000);
+        w.bytes(3u16.to_be_bytes());
+        let (_, r) = tref(&w.buf).unwrap();
+        assert_eq!(r.object_number(), 42);
+        assert_eq!(r.pid(), 3);
+
+        let mut w = Writer::default();
+        w.bytes([0, 1]);
+        w.tobject();
+        w.string("arr");
+        w.bytes(2i32.to_be_bytes());
+        w.bytes(0i32.to_be_bytes());
+        w.bytes(1u16.to_be_bytes());
+        w.bytes(7u32.to_be_bytes());
+        w.bytes(0u32.to_be_bytes());
+        let (_, arr) = trefarray(&w.buf).unwrap();
+        assert_eq!(arr.name(), "arr");
+        let refs = arr.refs();
+        assert_eq!(refs[0].object_number(), 7);
+        assert_eq!(refs[1].object_number(), 0);
+        assert!(refs.iter().all(|r| r.pid() == 1));
+    }
+}
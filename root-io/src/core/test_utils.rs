@@ -0,0 +1,94 @@
+//! Helpers to build buffers of streamed objects in tests
+
+use std::collections::HashMap;
+
+use crate::MAP_OFFSET;
+
+/// Writes objects the way ROOT streams them into the buffer of a key
+#[derive(Default)]
+pub(crate) struct Writer {
+    pub(crate) buf: Vec<u8>,
+    /// Class tags of the classes written so far
+    classes: HashMap<String, u32>,
+}
+
+impl Writer {
+    /// Reserve space for a byte count
+    pub(crate) fn begin(&mut self) -> usize {
+        self.buf.extend([0; 4]);
+        self.buf.len() - 4
+    }
+
+    /// Write the byte count reserved at `pos`
+    pub(crate) fn end(&mut self, pos: usize) {
+        let n = (self.buf.len() - pos - 4) as u32;
+        self.buf[pos..pos + 4].copy_from_slice(&(0x4000_0000 | n).to_be_bytes());
+    }
+
+    /// Write what `members` writes preceded by its byte count
+    pub(crate) fn with_byte_count(&mut self, members: impl FnOnce(&mut Self)) {
+        let pos = self.begin();
+        members(self);
+        self.end(pos);
+    }
+
+    /// Write the given bytes as they are
+    pub(crate) fn bytes(&mut self, bytes: impl AsRef<[u8]>) {
+        self.buf.extend(bytes.as_ref());
+    }
+
+    /// Write a short string preceded by its length
+    pub(crate) fn string(&mut self, s: &str) {
+        self.buf.push(s.len() as u8);
+        self.buf.extend(s.as_bytes());
+    }
+
+    /// Write a `TObject` with the given unique ID and bits
+    pub(crate) fn tobject_with(&mut self, id: u32, bits: u32) {
+        self.buf.extend([0, 1]);
+        self.buf.extend(id.to_be_bytes());
+        self.buf.extend(bits.to_be_bytes());
+    }
+
+    /// Write a `TObject` with default values
+    pub(crate) fn tobject(&mut self) {
+        self.tobject_with(0, 0x0300_0000);
+    }
+
+    /// Write the members of a `TNamed`
+    pub(crate) fn tnamed_members(&mut self, name: &str, title: &str) {
+        self.buf.extend([0, 1]);
+        self.tobject();
+        self.string(name);
+        self.string(title);
+    }
+
+    /// Write a `TNamed` preceded by its byte count as done for base
+    /// classes
+    pub(crate) fn tnamed(&mut self, name: &str, title: &str) {
+        self.with_byte_count(|w| w.tnamed_members(name, title));
+    }
+
+    /// Write an object through a pointer; its class is defined
+    /// the first time and referenced afterwards
+    pub(crate) fn object(&mut self, class: &str, members: impl FnOnce(&mut Self)) {
+        let pos = self.begin();
+        match self.classes.get(class) {
+            Some(tag) => self.buf.extend((0x8000_0000 | tag).to_be_bytes()),
+            None => {
+                self.classes
+                    .insert(class.to_string(), pos as u32 + MAP_OFFSET as u32);
+                self.buf.extend(u32::MAX.to_be_bytes());
+                self.buf.extend(class.as_bytes());
+                self.buf.push(0);
+            }
+        }
+        self.with_byte_count(members);
+        self.end(pos);
+    }
+
+    /// Write a null pointer
+    pub(crate) fn null(&mut self) {
+        self.buf.extend(0u32.to_be_bytes());
+    }
+}
@@ -10,14 +10,14 @@ pub struct TKeyHeader {
     pub(crate) total_size: u32,
     version: u16,
     pub(crate) uncomp_len: u32,
-    datime: u32,
+    pub(crate) datime: u32,
     pub(crate) key_len: i16,
-    cycle: i16,
+    pub(crate) cycle: i16,
     pub(crate) seek_key: SeekPointer,
-    seek_pdir: SeekPointer,
+    pub(crate) seek_pdir: SeekPointer,
     pub(crate) class_name: String,
     pub(crate) obj_name: String,
-    obj_title: String,
+    pub(crate) obj_title: String,
 }
 
 /// A `TKey` wraps a streamed oject. The object is decompress when
@@ -75,7 +75,7 @@ pub fn tkey(input: &[u8]) -> nom::IResult<&[u8], TKey> {
     let (input, hdr) = tkey_header(input)?;
     let (input, obj) = take(hdr.total_size - hdr.key_len as u32)(input)?;
     let obj = if hdr.uncomp_len as usize > obj.len() {
-        decompress(obj).unwrap().1
+        decompress(obj)?.1
     } else {
         obj.to_vec()
     };
@@ -10,9 +10,10 @@ use nom::{
 use quote::*;
 
 use crate::{
-    code_gen::rust::{ToRustParser, ToRustType},
-    code_gen::utils::{alias_or_lifetime, sanitize, type_is_core},
+    code_gen::rust::{Classes, InlineParser, ToRustParser, ToRustType},
+    code_gen::utils::{field_name, rust_ident, type_is_core},
     core::*,
+    schema::MemberSchema,
 };
 
 /// Union of all posible `TStreamers`. See figure at
@@ -84,6 +85,8 @@ pub(crate) enum TStreamer {
 pub(crate) struct TStreamerElement {
     ver: u16,
     name: TNamed,
+    /// The raw id from which `el_type` was derived
+    type_id: i32,
     el_type: TypeID,
     size: i32,
     array_len: i32,
@@ -225,7 +228,9 @@ fn tstreamerelement(i: &[u8]) -> IResult<&[u8], TStreamerElement> {
         unimplemented!();
     }
     let (i, name) = parse_sized_object(tnamed)(i)?;
-    let (i, el_type) = map_res(be_i32, |id| TypeID::new(id, &name.title))(i)?;
+    let (i, (type_id, el_type)) = map_res(be_i32, |id| {
+        TypeID::new(id, &name.title).map(|el_type| (id, el_type))
+    })(i)?;
     let (i, size) = be_i32(i)?;
     let (i, array_len) = be_i32(i)?;
     let (i, array_dim) = be_i32(i)?;
@@ -242,6 +247,7 @@ fn tstreamerelement(i: &[u8]) -> IResult<&[u8], TStreamerElement> {
         TStreamerElement {
             ver,
             name,
+            type_id,
             el_type,
             size,
             array_len,
@@ -278,204 +284,283 @@ impl TStreamer {
     }
     /// The name of the member/field to be used in the generated struct
     pub(crate) fn member_name(&self) -> Ident {
-        let name = sanitize(&self.elem().name.name.to_lowercase());
-        Ident::new(name)
+        Ident::new(field_name(&self.elem().name.name))
     }
-}
 
-impl ToTokens for TStreamer {
-    /// Converts TStreamer to "\n///comment \n name: type"
-    fn to_tokens(&self, tokens: &mut Tokens) {
-        // insert a new line befor and after the comment!
-        tokens.append("\n/// ");
-        self.member_comment().to_tokens(tokens);
-        tokens.append("\n");
-        self.member_name().to_tokens(tokens);
-        tokens.append(": ");
-        self.type_name().to_tokens(tokens);
+    /// The name of the base class if this is a `Base`
+    pub(crate) fn base_class(&self) -> Option<&str> {
+        match self {
+            TStreamer::Base { el, .. } => Some(&el.name.name),
+            _ => None,
+        }
     }
-}
 
-impl ToRustType for TStreamer {
-    fn type_name(&self) -> Tokens {
+    /// The class of a base class or of an embedded object
+    pub(crate) fn member_class(&self) -> Option<&str> {
         use self::TypeID::*;
-        let name = Ident::new(alias_or_lifetime(&self.elem().name.name.to_owned()));
+        let el = self.elem();
         match self {
-            TStreamer::Base { ref el, .. } => {
-                match el.el_type {
-                    Object | Base | Named | TObject => quote! {#name},
-                    // Not sure about the following branch...
-                    InvalidOrCounter(-1) => quote! {#name},
-                    _ => panic!("{:#?}", self),
-                }
-            }
-            TStreamer::BasicType { ref el } => match el.el_type {
-                Primitive(ref id) => id.type_name(),
+            TStreamer::Base { .. } => Some(&el.name.name),
+            TStreamer::Object { .. }
+            | TStreamer::ObjectPointer { .. }
+            | TStreamer::ObjectAny { .. }
+            | TStreamer::ObjectAnyPointer { .. } => match el.el_type {
+                Object | Any | Objectp | Unknown(68) => Some(el.pointee()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type of the field holding this member in a generated
+    /// struct. If `classes` is not given, all classes which are not
+    /// handled by this crate are assumed to be generated.
+    pub(crate) fn member_type(&self, classes: Option<&Classes>) -> Tokens {
+        use self::TypeID::*;
+        let el = self.elem();
+        let class_type = |class: &str| class_type(class, classes);
+        match self {
+            TStreamer::Base { .. } => match el.el_type {
+                TObject => quote! {TObject},
+                Named => quote! {TNamed},
+                // Not sure about the following branch...
+                InvalidOrCounter(-1) => quote! {Vec<u8>},
+                _ => class_type(&el.name.name),
+            },
+            TStreamer::BasicType { .. } => match el.el_type {
+                Primitive(ref id) => id.field_type(),
                 Offset(ref id) => {
-                    let s = Ident::new(format!("[{}; {}]", id.type_name(), el.array_len));
+                    let s = Ident::new(format!("[{}; {}]", id.field_type(), el.array_len));
                     quote! {#s}
                 }
                 _ => panic!("{:#?}", self),
             },
-            TStreamer::BasicPointer { ref el, .. } => {
-                match el.el_type {
-                    Array(ref id) => {
-                        // Arrays are preceeded by a byte and then have a length given by a
-                        // previous member
-                        let s = Ident::new(format!("Vec<{}>", id.type_name()));
-                        quote! {#s}
-                    }
-                    _ => panic!("{:#?}", self),
+            TStreamer::BasicPointer { .. } => match el.el_type {
+                Array(ref id) => {
+                    let t = id.field_type();
+                    quote! {Vec<#t>}
                 }
-            }
-            TStreamer::Object { ref el } => match el.el_type {
-                Object => quote! {#name},
                 _ => panic!("{:#?}", self),
             },
-            TStreamer::ObjectPointer { ref el } => {
-                match el.el_type {
-                    // Pointers may be null!
-                    ObjectP | Objectp => quote! {Option<#name>},
-                    _ => panic!("{:#?}", self),
-                }
-            }
-            TStreamer::ObjectAny { ref el } | &TStreamer::ObjectAnyPointer { ref el } => {
-                match el.el_type {
-                    Any => quote! {#name},
-                    AnyP => quote! {#name},
-                    // No idea what this is; probably an array of custom type? Found in AliESDs
-                    Unknown(82) => quote! {Vec<u8>},
-                    _ => panic!("{:#?}", self),
-                }
+            // Kept as raw bytes for now
+            TStreamer::Loop { .. } => quote! {Vec<u8>},
+            TStreamer::Object { .. }
+            | TStreamer::ObjectPointer { .. }
+            | TStreamer::ObjectAny { .. }
+            | TStreamer::ObjectAnyPointer { .. } => match el.el_type {
+                Object | Any | Objectp | Unknown(68) => class_type(el.pointee()),
+                // Pointers may be null!
+                ObjectP | AnyP => quote! {Object},
+                // Fixed size arrays of objects; kept as raw bytes
+                Unknown(81) | Unknown(82) => quote! {Vec<Vec<u8>>},
+                _ => panic!("{:#?}", self),
+            },
+            TStreamer::String { .. } | TStreamer::StlString { .. } => quote! {String},
+            TStreamer::Stl {
+                vtype: StlTypeID::Vector,
+                ctype: Primitive(ref id),
+                ..
+            } => {
+                let t = id.field_type();
+                quote! {Vec<#t>}
             }
-            TStreamer::String { ref el } | TStreamer::StlString { ref el, .. } => {
-                match el.el_type {
-                    String | Streamer => quote! {String},
-                    _ => panic!("{:#?}", self),
-                }
+            // Other STL containers are kept as raw bytes
+            TStreamer::Stl { .. } => quote! {Vec<u8>},
+        }
+    }
+
+    /// Description of this member in the schema of its class
+    pub(crate) fn schema(&self) -> MemberSchema {
+        use self::TStreamer::*;
+        let el = self.elem();
+        let (streamer, counter) = match self {
+            Base { .. } => ("TStreamerBase", None),
+            BasicType { .. } => ("TStreamerBasicType", None),
+            BasicPointer { cname, .. } => ("TStreamerBasicPointer", Some(cname.to_owned())),
+            Loop { cname, .. } => ("TStreamerLoop", Some(cname.to_owned())),
+            Object { .. } => ("TStreamerObject", None),
+            ObjectPointer { .. } => ("TStreamerObjectPointer", None),
+            ObjectAny { .. } => ("TStreamerObjectAny", None),
+            ObjectAnyPointer { .. } => ("TStreamerObjectAnyPointer", None),
+            String { .. } => ("TStreamerString", None),
+            Stl { .. } => ("TStreamerSTL", None),
+            StlString { .. } => ("TStreamerSTLstring", None),
+        };
+        MemberSchema {
+            name: el.name.name.to_owned(),
+            streamer: streamer.to_string(),
+            type_id: el.type_id,
+            type_name: el.type_name.to_owned(),
+            size: el.size,
+            array_length: el.array_len,
+            array_dim: el.array_dim,
+            max_index: el.max_idx.to_owned(),
+            comment: el.name.title.to_owned(),
+            counter,
+        }
+    }
+}
+
+impl TStreamerElement {
+    /// The class pointed to by a pointer member or the class of an
+    /// embedded object
+    fn pointee(&self) -> &str {
+        self.type_name.trim_end_matches('*')
+    }
+}
+
+/// The element type of a `TArray` class
+fn tarray_element(class: &str) -> Option<PrimitiveID> {
+    use self::PrimitiveID::*;
+    Some(match class.strip_prefix("TArray")? {
+        "C" => KChar,
+        "S" => KShort,
+        "I" => KInt,
+        "L" => KLong,
+        "L64" => KLong64,
+        "F" => KFloat,
+        "D" => KDouble,
+        _ => return None,
+    })
+}
+
+/// The type of a data member holding an object of the class `class`
+fn class_type(class: &str, classes: Option<&Classes>) -> Tokens {
+    let is_generated = match classes {
+        Some(classes) => classes.is_generated(class),
+        None => !type_is_core(class) && !is_decodable(class),
+    };
+    match class {
+        "TObject" => quote! {TObject},
+        "TNamed" => quote! {TNamed},
+        "TString" => quote! {String},
+        _ if is_generated => {
+            let name = Ident::new(rust_ident(class));
+            quote! {#name}
+        }
+        _ => match tarray_element(class) {
+            Some(id) => {
+                let t = id.field_type();
+                quote! {Vec<#t>}
             }
-            TStreamer::Stl { ref vtype, .. } => match vtype {
-                StlTypeID::Vector => {
-                    quote! {Stl_vec}
-                }
-                StlTypeID::Bitset => {
-                    quote! {Stl_bitset}
-                }
-                StlTypeID::String => {
-                    quote! {Stl_string}
-                }
-                StlTypeID::Map => {
-                    quote! {Stl_map}
-                }
-                StlTypeID::MultiMap => {
-                    quote! {Stl_map}
-                }
-            },
-            _ => panic!("{:#?}", self),
+            // Decoded at runtime
+            None => quote! {Object},
+        },
+    }
+}
+
+/// Parser of a data member holding an object of the class `class`
+fn class_parser(class: &str, classes: &Classes) -> InlineParser {
+    match class {
+        "TObject" => InlineParser::new(quote! {tobject}),
+        "TNamed" => InlineParser::new(quote! {length_value(checked_byte_count, tnamed)}),
+        "TString" => InlineParser::new(quote! {string}),
+        _ if classes.is_generated(class) => {
+            let parser = Ident::new(rust_ident(class).to_lowercase());
+            InlineParser::new(quote! {length_value(checked_byte_count, |i| #parser(i, context))})
+                .with_context()
         }
+        _ => match tarray_element(class) {
+            Some(id) => {
+                let parser = id.to_inline_parser().to_parser();
+                InlineParser::call(quote! {tarray(#parser, i)})
+            }
+            None => {
+                let class = Ident::new(format!("{:?}", class));
+                InlineParser::new(
+                    quote! {length_value(checked_byte_count, |i| class_object(#class, i, context))},
+                )
+                .with_context()
+            }
+        },
+    }
+}
+
+impl ToRustType for TStreamer {
+    fn type_name(&self) -> Tokens {
+        self.member_type(None)
     }
 }
 
 impl ToRustParser for TStreamer {
-    fn to_inline_parser(&self) -> Tokens {
+    fn to_inline_parser(&self, classes: &Classes) -> InlineParser {
         use self::TypeID::*;
-        let name = match self {
-            //  `Base` types, i.e. types from which the current object inherited;
-            // In that case the name is actually the type
-            TStreamer::Base { .. } => &self.elem().name.name,
-            _ => &self.elem().type_name,
-        };
-        // Most core-types do not need the context, but some do
-        let name = if type_is_core(name.as_str()) && name != "TObjArray" {
-            name.to_lowercase()
-        } else {
-            format!("apply!({}, &context)", name.to_lowercase())
-        };
-
-        let name = Ident::new(name);
-
+        let el = self.elem();
+        let raw_bytes = quote! {map(length_data(checked_byte_count), <[u8]>::to_vec)};
         match self {
-            TStreamer::Base { ref el, .. } => match el.el_type {
-                Object | Base | Named => quote! {length_value!(checked_byte_count, #name)},
-                TObject => quote! {#name},
+            TStreamer::Base { .. } => match el.el_type {
+                TObject => InlineParser::new(quote! {tobject}),
+                Named => InlineParser::new(quote! {length_value(checked_byte_count, tnamed)}),
                 InvalidOrCounter(-1) => {
-                    let size = el.size;
-                    quote! {map!(take!(#size), |v| v.to_vec())}
+                    let size = Ident::new(format!("{}usize", el.size));
+                    InlineParser::new(quote! {map(take(#size), <[u8]>::to_vec)})
                 }
-                _ => panic!("{:#?}", self),
+                _ => class_parser(&el.name.name, classes),
             },
-            TStreamer::BasicType { ref el } => {
-                match el.el_type {
-                    Primitive(ref id) => id.to_inline_parser(),
-                    // Offsets are floating points with a custom mantissa
-                    // By default, parse as Vec<u8>
-                    Offset(_) => {
-                        let size = el.size;
-                        quote! {map!(take!(#size), |v| v.to_vec())}
-                    }
-                    _ => panic!("{:#?}", self),
+            TStreamer::BasicType { .. } => match el.el_type {
+                Primitive(ref id) => id.to_inline_parser(),
+                Offset(ref id) => {
+                    let (parser, len) = (
+                        id.to_inline_parser().to_parser(),
+                        Ident::new(el.array_len.to_string()),
+                    );
+                    InlineParser::new(
+                        quote! {map_res(count(#parser, #len), std::convert::TryInto::try_into)},
+                    )
                 }
-            }
+                _ => panic!("{:#?}", self),
+            },
             TStreamer::BasicPointer {
-                ref el, ref cname, ..
-            } => {
-                let n_entries_array = Ident::new(cname.to_lowercase());
-                match el.el_type {
-                    Array(ref id) => {
-                        // Arrays are preceeded by a byte and then have a length given by a
-                        // previous member
-                        let b_par = id.to_inline_parser();
-                        quote! {preceded!(be_u8, count!(#b_par, #n_entries_array as usize))}
-                    }
-                    _ => panic!("{:#?}", self),
+                ref cname,
+                ref ccls,
+                ..
+            } => match el.el_type {
+                // Arrays are preceeded by a byte and then have a length given by a
+                // previous member
+                Array(ref id) => {
+                    let parser = id.to_inline_parser().to_parser();
+                    let n_entries = classes
+                        .member_path(classes.current(), ccls, cname)
+                        .unwrap_or_else(|| field_name(cname));
+                    let n_entries = Ident::new(n_entries);
+                    InlineParser::new(quote! {basic_pointer(#parser, #n_entries as usize)})
                 }
-            }
-            TStreamer::Object { ref el } => match el.el_type {
-                Object => quote! {length_value!(checked_byte_count, #name)},
                 _ => panic!("{:#?}", self),
             },
-            TStreamer::ObjectPointer { ref el } => {
-                match el.el_type {
-                    // Pointers may be null!
-                    ObjectP => quote! {switch!(peek!(be_u32),
-                    0 => map!(call!(be_u32), |_| None) |
-                    _ => map!(call!(_curried_raw), Some))},
-                    _ => panic!("{:#?}", self),
-                }
-            }
-            TStreamer::ObjectAny { ref el } | &TStreamer::ObjectAnyPointer { ref el } => {
-                match el.el_type {
-                    Any => quote! {#name},
-                    AnyP => quote! {#name},
-                    // No idea what this is; probably an array of custom type? Found in AliESDs
-                    Unknown(82) => quote! {map!(eof!(), |o| o.to_vec())},
-                    _ => panic!("{:#?}", self),
+            TStreamer::Loop { .. } => InlineParser::new(raw_bytes),
+            TStreamer::Object { .. }
+            | TStreamer::ObjectPointer { .. }
+            | TStreamer::ObjectAny { .. }
+            | TStreamer::ObjectAnyPointer { .. } => match el.el_type {
+                Object | Any | Objectp | Unknown(68) => class_parser(el.pointee(), classes),
+                ObjectP | AnyP => {
+                    InlineParser::call(quote! {object_pointer(i, context)}).with_context()
                 }
-            }
-            TStreamer::String { ref el } | TStreamer::StlString { ref el, .. } => {
-                match el.el_type {
-                    String | Streamer => quote! {string},
-                    _ => panic!("{:#?}", self),
-                }
-            }
-            TStreamer::Stl { ref vtype, .. } => match vtype {
-                StlTypeID::Vector => {
-                    quote! {stl_vec}
-                }
-                StlTypeID::Bitset => {
-                    quote! {stl_bitset}
-                }
-                StlTypeID::String => {
-                    quote! {stl_string}
-                }
-                StlTypeID::Map => {
-                    quote! {stl_map}
-                }
-                StlTypeID::MultiMap => {
-                    quote! {stl_multimap}
+                Unknown(81) | Unknown(82) => {
+                    let len = Ident::new(el.array_len.to_string());
+                    InlineParser::new(quote! {count(#raw_bytes, #len)})
                 }
+                _ => panic!("{:#?}", self),
             },
-            _ => panic!("{:#?}", self),
+            TStreamer::String { .. } => InlineParser::new(quote! {string}),
+            // STL members are preceded by their size and version
+            TStreamer::StlString { .. } => InlineParser::new(
+                quote! {length_value(checked_byte_count, preceded(be_u16, string))},
+            ),
+            TStreamer::Stl {
+                vtype: StlTypeID::Vector,
+                ctype: Primitive(ref id),
+                ..
+            } => {
+                let parser = id.to_inline_parser().to_parser();
+                InlineParser::new(quote! {
+                    length_value(
+                        checked_byte_count,
+                        preceded(be_u16, length_count(map(be_i32, |n| n as usize), #parser))
+                    )
+                })
+            }
+            TStreamer::Stl { .. } => InlineParser::new(raw_bytes),
         }
     }
 }
@@ -5,9 +5,12 @@ use nom::{combinator::eof, multi::length_value, number::complete::*, IResult};
 use quote::*;
 
 use crate::{
-    code_gen::rust::{ToNamedRustParser, ToRustParser, ToRustStruct, ToRustType},
-    code_gen::utils::type_is_core,
+    code_gen::rust::{
+        Classes, InlineParser, ToNamedRustParser, ToRustParser, ToRustStruct, ToRustType,
+    },
+    code_gen::utils::{rust_ident, type_is_core},
     core::*,
+    schema::ClassSchema,
 };
 
 #[derive(Debug)]
@@ -47,74 +50,109 @@ pub(crate) fn tstreamerinfo<'s>(
     ))
 }
 
+impl TStreamerInfo {
+    /// Name of the described class
+    pub(crate) fn name(&self) -> &str {
+        &self.named.name
+    }
+
+    /// Names of the base classes of the described class
+    pub(crate) fn base_classes(&self) -> impl Iterator<Item = &str> {
+        self.data_members.iter().filter_map(|m| m.base_class())
+    }
+
+    /// Names of the base classes and the classes of embedded objects
+    pub(crate) fn dependencies(&self) -> impl Iterator<Item = &str> {
+        self.data_members.iter().filter_map(|m| m.member_class())
+    }
+
+    /// Description of the class in the schema of the file
+    pub(crate) fn schema(&self) -> ClassSchema {
+        ClassSchema {
+            name: self.named.name.to_owned(),
+            version: self.new_class_version,
+            checksum: self.checksum,
+            members: self.data_members.iter().map(|m| m.schema()).collect(),
+        }
+    }
+}
+
 impl ToRustParser for TStreamerInfo {
     /// Generate a parser that can parse an an object described by this TStreamer
-    #[rustfmt::skip::macros(do_parse)]
-    fn to_inline_parser(&self) -> Tokens {
-        if type_is_core(self.named.name.as_str()) {
-            // Don't generate a parser if its a core type
-            return quote!(#(self.named.name.to_lowercase()));
-        }
-        let struct_name = Ident::new(self.named.name.as_str());
-        let member_names: &Vec<Ident> =
-            &self.data_members.iter().map(|m| m.member_name()).collect();
-        let member_parsers: &Vec<Tokens> = &self
-            .data_members
-            .iter()
-            .map(|m| m.to_inline_parser())
-            .collect();
-        quote! {
-        do_parse!(ver: be_u16 >>
-                  #(#member_names : #member_parsers >> )*
-                  ({let phantom = PhantomData;
-                    #struct_name {
-                        phantom,
-                        ver,
-                        #(#member_names),*
-                    }})
-        )}
+    fn to_inline_parser(&self, _classes: &Classes) -> InlineParser {
+        let parser_name = self.parser_name();
+        InlineParser::call(quote! {#parser_name(i, context)}).with_context()
     }
 }
 
 impl ToNamedRustParser for TStreamerInfo {
     fn parser_name(&self) -> Tokens {
-        let ret = Ident::new(self.named.name.to_lowercase());
+        let ret = Ident::new(rust_ident(self.name()).to_lowercase());
         quote!(#ret)
     }
 
-    fn to_named_parser(&self) -> Tokens {
-        if type_is_core(self.named.name.as_str()) {
+    fn to_named_parser(&self, classes: &Classes) -> Tokens {
+        if !classes.is_generated(self.name()) {
             // Don't generate a parser if its a core type
             return quote! {};
         }
+        let classes = classes.within(self.name());
         let parser_name = self.parser_name();
-        let parser = self.to_inline_parser();
         let struct_name = self.type_name();
+        let member_names: &Vec<Ident> =
+            &self.data_members.iter().map(|m| m.member_name()).collect();
+        let parsers: Vec<_> = self
+            .data_members
+            .iter()
+            .map(|m| m.to_inline_parser(&classes))
+            .collect();
+        let member_calls: Vec<Tokens> = parsers.iter().map(InlineParser::to_call).collect();
+        let body = quote! {
+            let (i, ver) = be_u16(i)?;
+            #(let (i, #member_names) = #member_calls?;)*
+            Ok((i, #struct_name {
+                ver,
+                #(#member_names),*
+            }))
+        };
+        // Not all parsers need the context
+        let context = if parsers.iter().any(InlineParser::uses_context) {
+            quote!(context)
+        } else {
+            quote!(_context)
+        };
         quote! {
-            pub fn #parser_name<'s>(input: &'s[u8], context: &'s Context<'s>)
-                                    -> IResult<&'s[u8], #struct_name<'s>> {
-                value!(input, #parser)
+            pub fn #parser_name<'s>(i: &'s [u8], #context: &'s Context)
+                                    -> IResult<&'s [u8], #struct_name> {
+                #body
             }
         }
     }
 }
 
 impl ToRustStruct for TStreamerInfo {
-    /// Generate a struct corresponding to this TStreamerInfo
-    fn to_struct(&self) -> Tokens {
-        if type_is_core(self.named.name.as_str()) {
+    /// Generate a struct corresponding to this TStreamerInfo. Base
+    /// classes are embedded as fields.
+    fn to_struct(&self, classes: &Classes) -> Tokens {
+        if !classes.is_generated(self.name()) {
             return quote! {};
         }
         let name = self.type_name();
-        let fields = &self.data_members;
         let ver_comment = self.type_doc();
+        let fields = self.data_members.iter().map(|m| {
+            let (comment, name) = (m.member_comment(), m.member_name());
+            let comment = match comment.as_ref().trim() {
+                "" => Ident::new(""),
+                cmt => Ident::new(format!("\n/// {}\n", cmt)),
+            };
+            let field_type = m.member_type(Some(classes));
+            quote! {#comment pub #name: #field_type}
+        });
         quote! {
-            #[derive(Debug)]
-            pub struct #name<'s> {
-                /// Gurantee that this object does not outlive its underlying slice
-                phantom: PhantomData<&'s[u8]>,
+            #[derive(Debug, Clone)]
+            pub struct #name {
                 #ver_comment
-                ver: u16,
+                pub ver: u16,
                 #(#fields), *
             }
         }
@@ -127,7 +165,7 @@ impl ToRustType for TStreamerInfo {
         quote!(#ret)
     }
     fn type_name(&self) -> Tokens {
-        let ret = Ident::new(self.named.name.as_str());
+        let ret = Ident::new(rust_ident(self.name()));
         quote!(#ret)
     }
 }
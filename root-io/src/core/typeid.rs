@@ -4,7 +4,7 @@ use failure::Error;
 use quote::*;
 use regex::Regex;
 
-use crate::code_gen::rust::{ToRustParser, ToRustType};
+use crate::code_gen::rust::{InlineParser, ToRustType};
 
 /// Integer ID describing a streamed type in a `TStreamer`
 #[derive(Debug, Clone)]
@@ -63,7 +63,7 @@ pub(crate) enum PrimitiveID {
 }
 
 impl PrimitiveID {
-    fn new(id: i32, comment_str: &str) -> Result<PrimitiveID, Error> {
+    pub(crate) fn new(id: i32, comment_str: &str) -> Result<PrimitiveID, Error> {
         use PrimitiveID::*;
         Ok(match id {
             1 => KChar,
@@ -182,36 +182,80 @@ impl ToRustType for TypeID {
     }
 }
 
-impl ToRustParser for PrimitiveID {
-    fn to_inline_parser(&self) -> Tokens {
-        match self {
-            PrimitiveID::KChar => quote! {nom::number::complete::be_i8},
-            PrimitiveID::KShort => quote! {nom::number::complete::be_i16},
-            PrimitiveID::KInt => quote! {nom::number::complete::be_i32},
-            PrimitiveID::KCounter => quote! {nom::number::complete::be_i32},
-            PrimitiveID::KLong => quote! {nom::number::complete::be_i64},
-            PrimitiveID::KFloat => quote! {nom::number::complete::be_f32},
-            PrimitiveID::KCharStar => quote! { c_string },
-            PrimitiveID::KDouble => quote! {nom::number::complete::be_f64},
+impl PrimitiveID {
+    /// The type of a field of a generated struct holding this primitive
+    pub(crate) fn field_type(&self) -> Tokens {
+        use PrimitiveID::*;
+        let t = match self {
+            KChar | KLegacyChar => "i8",
+            KCharStar => "String",
+            KDouble32(..) => "f64",
+            KFloat16 => "f32",
+            KBool => "bool",
+            _ => self.type_name_str(),
+        };
+        let t = Ident::new(t);
+        quote!(#t)
+    }
+
+    /// The parser of this primitive as used in generated code
+    pub(crate) fn to_inline_parser(&self) -> InlineParser {
+        let parser = match self {
+            PrimitiveID::KChar => quote! {be_i8},
+            PrimitiveID::KShort => quote! {be_i16},
+            PrimitiveID::KInt => quote! {be_i32},
+            PrimitiveID::KCounter => quote! {be_i32},
+            PrimitiveID::KLong => quote! {be_i64},
+            PrimitiveID::KFloat => quote! {be_f32},
+            // Strings are preceded by their length
+            PrimitiveID::KCharStar => quote! {
+                map(length_data(be_i32), |s| String::from_utf8_lossy(s).into_owned())
+            },
+            PrimitiveID::KDouble => quote! {be_f64},
             // This one is nasty! Check the
             // TFileBuffer.cxx sources in ROOT and:
             // https://root.cern/root/html606/classTBufferFile.html#a44c2adb6fb1194ec999b84aed259e5bc
             // and
             // https://root.cern/root/html606/TStreamerElement_8cxx.html#a4d6c86845bee19cf28c93a531ec50f29
             PrimitiveID::KDouble32(min, max, nbits) => {
-                quote!(parse_custom_mantissa(#min, #max, #nbits))
+                let nbits = *nbits as usize;
+                if *min == nbits as f64 + 0.1 {
+                    // Truncated mantissa without a range
+                    quote! {map(|i| parse_custom_mantissa(i, #nbits), f64::from)}
+                } else if min >= max {
+                    // Without a range and a mantissa of at least 15
+                    // bits, the value is stored as a float
+                    quote! {map(be_f32, f64::from)}
+                } else {
+                    // Integer in the range [min, max]
+                    let steps = if nbits == 32 {
+                        f64::from(u32::MAX)
+                    } else {
+                        (1u64 << nbits) as f64
+                    };
+                    let expr = Ident::new(format!(
+                        "{:?} + f64::from(v) * {:?}",
+                        min,
+                        (max - min) / steps
+                    ));
+                    quote! {map(be_u32, |v| #expr)}
+                }
             }
-            PrimitiveID::KLegacyChar => unimplemented!("{:?}: type not implemented, yet", self),
-            PrimitiveID::KUChar => quote! {nom::number::complete::be_u8},
-            PrimitiveID::KUShort => quote! {nom::number::complete::be_u16},
-            PrimitiveID::KUInt => quote! {nom::number::complete::be_u32},
-            PrimitiveID::KULong => quote! {nom::number::complete::be_u64},
-            PrimitiveID::KBits => quote! {nom::number::complete::be_u32},
-            PrimitiveID::KLong64 => quote! {nom::number::complete::be_i64},
-            PrimitiveID::KULong64 => quote! {nom::number::complete::be_u64},
-            PrimitiveID::KBool => quote! {nom::number::complete::be_u8},
-            PrimitiveID::KFloat16 => quote! {custom_float16},
-        }
+            PrimitiveID::KLegacyChar => quote! {be_i8},
+            PrimitiveID::KUChar => quote! {be_u8},
+            PrimitiveID::KUShort => quote! {be_u16},
+            PrimitiveID::KUInt => quote! {be_u32},
+            PrimitiveID::KULong => quote! {be_u64},
+            PrimitiveID::KBits => quote! {be_u32},
+            PrimitiveID::KLong64 => quote! {be_i64},
+            PrimitiveID::KULong64 => quote! {be_u64},
+            PrimitiveID::KBool => quote! {map(be_u8, |v| v != 0)},
+            // Float16 without a range has a truncated mantissa of 12 bits
+            PrimitiveID::KFloat16 => {
+                return InlineParser::call(quote! {parse_custom_mantissa(i, 12)})
+            }
+        };
+        InlineParser::new(parser)
     }
 }
 
@@ -285,3 +329,25 @@ fn evaluate_range_element(comment_str: &str) -> Result<f64, Error> {
         Ok(val)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double32_parsers() {
+        let parser = |comment| {
+            PrimitiveID::new(9, comment)
+                .unwrap()
+                .to_inline_parser()
+                .to_parser()
+        };
+        assert_eq!(parser("[0,0]"), quote! {map(be_f32, f64::from)});
+        assert_eq!(parser("[0,0,20]"), quote! {map(be_f32, f64::from)});
+        assert_eq!(
+            parser("[0,0,10]"),
+            quote! {map(|i| parse_custom_mantissa(i, 10usize), f64::from)}
+        );
+        assert_eq!(parser("no range"), quote! {be_f32});
+    }
+}
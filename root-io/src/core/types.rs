@@ -1,4 +1,6 @@
 use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
 
 use crate::core::Source;
 
@@ -19,6 +21,9 @@ bitflags! {
     pub(crate) struct TObjectFlags: u32 {
         const IS_ON_HEAP = 0x0100_0000;
         const IS_REFERENCED = 1 << 4;
+        const HAS_UUID = 1 << 7;
+        /// Set for `TClonesArray`s which are streamed member-wise
+        const BYPASS_STREAMER = 1 << 12;
     }
 }
 
@@ -59,8 +64,9 @@ pub struct Raw<'s> {
     pub(crate) obj: &'s [u8],
 }
 
-/// The context from which we are currently parsing
-#[derive(Debug)]
+/// The context from which we are currently parsing. Cloning a
+/// `Context` is cheap since the underlying buffer is shared.
+#[derive(Clone)]
 pub struct Context {
     /// Path to file of this context
     pub(crate) source: Source,
@@ -69,7 +75,31 @@ pub struct Context {
     /// Usually something like TKey-length + 4
     pub(crate) offset: u64,
     /// The full buffer we are working on
-    pub(crate) s: Vec<u8>,
+    pub(crate) s: Arc<Vec<u8>>,
+}
+
+impl Context {
+    /// Position of `sub` within the buffer of this context. Returns
+    /// `None` if `sub` is not a slice of this context's buffer.
+    pub(crate) fn range_of(&self, sub: &[u8]) -> Option<Range<usize>> {
+        let start = (sub.as_ptr() as usize).checked_sub(self.s.as_ptr() as usize)?;
+        let end = start + sub.len();
+        if end <= self.s.len() {
+            Some(start..end)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("source", &self.source)
+            .field("offset", &self.offset)
+            .field("s", &format_args!("Buffer of {} bytes", self.s.len()))
+            .finish()
+    }
 }
 
 impl<'s> fmt::Debug for Raw<'s> {
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use failure::Error;
+use nom::{combinator::map, multi::count, number::complete::*, IResult};
+
+use crate::core::*;
+use crate::tree_reader::Container;
+
+/// A problem found by `RootFile::verify`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    seek: u64,
+    len: u64,
+    message: String,
+}
+
+impl Issue {
+    /// Position of the affected region in the file
+    pub fn seek(&self) -> u64 {
+        self.seek
+    }
+
+    /// Size of the affected region in bytes
+    pub fn size(&self) -> u64 {
+        self.len
+    }
+
+    /// Description of the problem
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "bytes {}..{}: {}",
+            self.seek,
+            self.seek.saturating_add(self.len),
+            self.message
+        )
+    }
+}
+
+/// The result of `RootFile::verify`
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    keys: usize,
+    baskets: usize,
+    free_segments: Vec<(u64, u64)>,
+    issues: Vec<Issue>,
+}
+
+impl VerifyReport {
+    /// Number of checked keys
+    pub fn keys(&self) -> usize {
+        self.keys
+    }
+
+    /// Number of checked baskets
+    pub fn baskets(&self) -> usize {
+        self.baskets
+    }
+
+    /// The unused segments of the file as (first, last) byte
+    pub fn free_segments(&self) -> &[(u64, u64)] {
+        &self.free_segments
+    }
+
+    /// The problems found in the file
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    /// Was the file found to be intact?
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn issue(&mut self, seek: u64, len: u64, message: String) {
+        self.issues.push(Issue { seek, len, message });
+    }
+}
+
+/// Parse a `TFree` segment
+fn tfree(i: &[u8]) -> IResult<&[u8], (u64, u64)> {
+    let (i, ver) = be_i16(i)?;
+    if ver > 1000 {
+        let (i, first) = be_u64(i)?;
+        let (i, last) = be_u64(i)?;
+        Ok((i, (first, last)))
+    } else {
+        let (i, first) = map(be_u32, u64::from)(i)?;
+        let (i, last) = map(be_u32, u64::from)(i)?;
+        Ok((i, (first, last)))
+    }
+}
+
+impl RootFile {
+    /// The unused segments of this file as (first, last) byte. The
+    /// last segment usually extends from the end of the file to
+    /// the largest possible file size.
+    pub async fn free_segments(&self) -> Result<Vec<(u64, u64)>, Error> {
+        let hdr = &self.hdr;
+        if hdr.seek_free == 0 {
+            return Ok(vec![]);
+        }
+        let len = u64::try_from(hdr.nbytes_free)
+            .map_err(|_| format_err!("Negative size of the list of free segments"))?;
+        let n = usize::try_from(hdr.n_entries_free)
+            .map_err(|_| format_err!("Negative number of free segments"))?;
+        let buf = self.source.fetch(hdr.seek_free, len).await?;
+        let (_, key) = tkey(&buf).map_err(|_| format_err!("Failed to parse key of free list"))?;
+        let (_, segments) =
+            count(tfree, n)(&key.obj).map_err(|_| format_err!("Failed to parse free segments"))?;
+        Ok(segments)
+    }
+
+    /// Check the integrity of this file. This reads the list of free
+    /// segments and all keys as well as all baskets of the trees in
+    /// this file, checks their positions against the end of the file
+    /// and decompresses them. Problems are collected in the returned
+    /// report; an `Err` is only returned if the check itself failed
+    /// (e.g. due to an I/O error of a remote file).
+    ///
+    /// This is useful to find truncated or otherwise corrupt files,
+    /// e.g. after an interrupted download.
+    pub async fn verify(&self) -> Result<VerifyReport, Error> {
+        let mut report = VerifyReport::default();
+        let end = self.hdr.end;
+        let size = self.source.size().await?;
+        if size < end {
+            report.issue(
+                size,
+                end - size,
+                format!("File is shorter than the {} bytes given in its header", end),
+            );
+        }
+        let (seek_free, nbytes_free) = (self.hdr.seek_free, self.hdr.nbytes_free);
+        match u64::try_from(nbytes_free) {
+            Err(_) => report.issue(
+                seek_free,
+                0,
+                format!("Negative size {} of the list of free segments", nbytes_free),
+            ),
+            Ok(len) if seek_free.checked_add(len).is_none_or(|last| last > end) => report.issue(
+                seek_free,
+                len,
+                "List of free segments extends beyond the end of the file".to_string(),
+            ),
+            Ok(len) => match self.free_segments().await {
+                Ok(segments) => report.free_segments = segments,
+                Err(e) => report.issue(
+                    seek_free,
+                    len,
+                    format!("Corrupt list of free segments: {}", e),
+                ),
+            },
+        }
+        let (seek_info, nbytes_info) = (self.hdr.seek_info, self.hdr.nbytes_info);
+        match u64::try_from(nbytes_info) {
+            Ok(len) => {
+                self.verify_record(&mut report, seek_info, len, "StreamerInfo")
+                    .await;
+            }
+            Err(_) => report.issue(
+                seek_info,
+                0,
+                format!("Negative size {} of the StreamerInfo", nbytes_info),
+            ),
+        }
+
+        for item in &self.items {
+            report.keys += 1;
+            let hdr = item.key_header();
+            let name = format!("{};{}", hdr.obj_name, hdr.cycle);
+            if !self
+                .verify_record(&mut report, hdr.seek_key, hdr.total_size as u64, &name)
+                .await
+            {
+                continue;
+            }
+            if item.class_name() != "TTree" {
+                continue;
+            }
+            let branches = item
+                .as_lazy_tree()
+                .await
+                .and_then(|tree| Ok(tree.branches()?.into_iter().cloned().collect()));
+            let branches: Vec<_> = match branches {
+                Ok(branches) => branches,
+                Err(e) => {
+                    report.issue(
+                        hdr.seek_key,
+                        hdr.total_size as u64,
+                        format!("Failed to parse tree `{}`: {}", name, e),
+                    );
+                    continue;
+                }
+            };
+            for branch in branches {
+                for container in branch.containers() {
+                    report.baskets += 1;
+                    let what = format!("basket of branch `{}` of `{}`", branch.name, name);
+                    match container {
+                        Container::OnDisk(_, seek, len) => {
+                            self.verify_record(&mut report, *seek, *len, &what).await;
+                        }
+                        Container::InMemory(..) => {
+                            if let Err(e) = container.to_owned().raw_data().await {
+                                report.issue(
+                                    hdr.seek_key,
+                                    hdr.total_size as u64,
+                                    format!("Corrupt {}: {}", what, e),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Check a record (a key and its payload) of the given length at
+    /// `seek`. Returns `true` if no problem was found.
+    async fn verify_record(
+        &self,
+        report: &mut VerifyReport,
+        seek: u64,
+        len: u64,
+        what: &str,
+    ) -> bool {
+        if seek.checked_add(len).is_none_or(|last| last > self.hdr.end) {
+            let msg = format!("`{}` extends beyond the end of the file", what);
+            report.issue(seek, len, msg);
+            return false;
+        }
+        if seek < self.hdr.begin as u64 {
+            let msg = format!("`{}` starts before the first key", what);
+            report.issue(seek, len, msg);
+            return false;
+        }
+        let overlap = report
+            .free_segments
+            .iter()
+            .find(|&&(first, last)| seek <= last && seek + len > first)
+            .copied();
+        if let Some((first, last)) = overlap {
+            let msg = format!("`{}` overlaps the free segment {}..={}", what, first, last);
+            report.issue(seek, len, msg);
+        }
+        let buf = match self.source.fetch(seek, len).await {
+            Ok(buf) if buf.len() as u64 == len => buf,
+            _ => {
+                report.issue(seek, len, format!("Failed to read `{}`", what));
+                return false;
+            }
+        };
+        let hdr = match tkey_header(&buf) {
+            Ok((_, hdr)) => hdr,
+            Err(_) => {
+                report.issue(seek, len, format!("Corrupt key header of `{}`", what));
+                return false;
+            }
+        };
+        if hdr.total_size as u64 != len || hdr.seek_key != seek || hdr.key_len <= 0 {
+            let msg = format!(
+                "Key header of `{}` does not match its position and size",
+                what
+            );
+            report.issue(seek, len, msg);
+            return false;
+        }
+        let payload = &buf[(hdr.key_len as usize).min(buf.len())..];
+        if (hdr.uncomp_len as usize) > payload.len() {
+            let msg = match decompress(payload) {
+                Ok((_, obj)) if obj.len() == hdr.uncomp_len as usize => None,
+                Ok((_, obj)) => Some(format!(
+                    "`{}` decompresses to {} bytes instead of {}",
+                    what,
+                    obj.len(),
+                    hdr.uncomp_len
+                )),
+                Err(_) => Some(format!("Failed to decompress `{}`", what)),
+            };
+            if let Some(msg) = msg {
+                report.issue(seek, len, msg);
+                return false;
+            }
+        }
+        overlap.is_none()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::path::Path;
+
+    use crate::core::RootFile;
+
+    #[tokio::test]
+    async fn verify_intact_files() {
+        for name in &["simple.root", "HZZ-lz4.root", "sample-6.10.05-zlib.root"] {
+            let path = Path::new("./src/test_data").join(name);
+            let f = RootFile::new(path.as_path()).await.unwrap();
+            let report = f.verify().await.unwrap();
+            assert!(report.is_ok(), "{}: {:?}", name, report.issues());
+            assert_eq!(report.keys(), f.items().len());
+            assert!(report.baskets() > 0);
+            assert!(!report.free_segments().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_corrupt_header() {
+        let mut buf = std::fs::read("./src/test_data/simple.root").unwrap();
+        // Small files use 32 bit pointers; set fEND to 0 and fNbytesFree
+        // to a negative value
+        buf[12..16].copy_from_slice(&0u32.to_be_bytes());
+        buf[20..24].copy_from_slice(&(-10i32).to_be_bytes());
+        let path = std::env::temp_dir().join(format!(
+            "root-io-verify-corrupt-header-{}.root",
+            std::process::id()
+        ));
+        std::fs::write(&path, &buf).unwrap();
+        let report = async { RootFile::new(path.as_path()).await?.verify().await }.await;
+        std::fs::remove_file(&path).unwrap();
+        let report = report.unwrap();
+        let messages: Vec<_> = report.issues().iter().map(|i| i.message()).collect();
+        assert!(messages.contains(&"Negative size -10 of the list of free segments"));
+        // Everything extends beyond the end of the file
+        assert!(messages
+            .iter()
+            .any(|m| m.contains("extends beyond the end of the file")));
+        assert!(messages.iter().all(|m| !m.starts_with("File is shorter")));
+    }
+
+    #[tokio::test]
+    async fn verify_corrupt_file() {
+        let mut buf = std::fs::read("./src/test_data/HZZ.root").unwrap();
+        // Overwrite some baskets in the middle of the file
+        let mid = buf.len() / 2;
+        buf[mid..mid + 1000].iter_mut().for_each(|b| *b = 0);
+        let path = std::env::temp_dir().join(format!(
+            "root-io-verify-corrupt-{}.root",
+            std::process::id()
+        ));
+        std::fs::write(&path, &buf).unwrap();
+        let report = async { RootFile::new(path.as_path()).await?.verify().await }.await;
+        std::fs::remove_file(&path).unwrap();
+        let report = report.unwrap();
+        assert!(!report.is_ok());
+        assert!(report.issues().iter().all(|i| i.seek() < mid as u64 + 1000));
+    }
+}
@@ -24,13 +24,13 @@ extern crate quote;
 extern crate failure;
 extern crate flate2;
 extern crate lzma_rs;
-extern crate reqwest;
-
-extern crate alice_open_data;
 
 // pub mod core_types;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
 mod code_gen;
 pub mod core;
+pub mod schema;
 pub mod test_utils;
 mod tests;
 pub mod tree_reader;
@@ -38,7 +38,7 @@ pub mod tree_reader;
 // Contains the stream_zip macro
 pub mod utils;
 
-pub use crate::core::{FileItem, RootFile, Source};
+pub use crate::core::{Fetch, FileItem, Object, RootFile, Source};
 
 /// Offset when using Context; should be in `Context`, maybe?
 const MAP_OFFSET: u64 = 2;
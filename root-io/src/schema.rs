@@ -0,0 +1,116 @@
+//! A machine readable description of the content of a ROOT file.
+//!
+//! The schema covers the classes described in the `TStreamerInfo` of
+//! a file as well as the layout of its `TTree`s. All types can be
+//! (de)serialized with `serde`, which makes it easy to store them
+//! e.g. as JSON and to compare the schemas of different files.
+//!
+//! # Example
+//! ```
+//! use root_io::RootFile;
+//! # use std::path::Path;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let f = RootFile::new(Path::new("./src/test_data/simple.root")).await.unwrap();
+//! let schema = f.schema().await.unwrap();
+//! assert_eq!(schema.trees[0].name, "tree");
+//! # }
+//! ```
+use serde::{Deserialize, Serialize};
+
+/// Schema of a whole file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileSchema {
+    /// All classes described in the streamer info of the file
+    pub classes: Vec<ClassSchema>,
+    /// All trees stored in the file
+    pub trees: Vec<TreeSchema>,
+}
+
+/// Description of a class as found in its `TStreamerInfo`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassSchema {
+    /// Name of the class
+    pub name: String,
+    /// Version of the class
+    pub version: u32,
+    /// Checksum of the class layout
+    pub checksum: u32,
+    /// Base classes and data members in the order in which they are streamed
+    pub members: Vec<MemberSchema>,
+}
+
+/// A base class or data member of a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemberSchema {
+    /// Name of the member; the class name for base classes
+    pub name: String,
+    /// Class of the streamer element, e.g. `TStreamerBasicType`
+    pub streamer: String,
+    /// ROOT's type id of this member (`TStreamerElement::fType`)
+    pub type_id: i32,
+    /// The C++ type of this member
+    pub type_name: String,
+    /// Size of this member in bytes
+    pub size: i32,
+    /// Total number of elements of fixed size arrays
+    pub array_length: i32,
+    /// Number of dimensions of fixed size arrays
+    pub array_dim: i32,
+    /// Maximum index of each array dimension
+    pub max_index: Vec<u32>,
+    /// The comment of this member
+    pub comment: String,
+    /// Member holding the number of elements of variable sized arrays
+    pub counter: Option<String>,
+}
+
+/// Layout of a `TTree`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TreeSchema {
+    /// Name of the tree
+    pub name: String,
+    /// Cycle of the tree in the file
+    pub cycle: i16,
+    /// Number of entries in the tree
+    pub entries: i64,
+    /// The top level branches of the tree
+    pub branches: Vec<BranchSchema>,
+}
+
+/// Layout of a `TBranch` and its sub-branches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchSchema {
+    /// Name of the branch
+    pub name: String,
+    /// Number of entries in the branch
+    pub entries: i64,
+    /// Total size of the baskets of this branch after compression
+    pub compressed_bytes: i64,
+    /// Total size of the baskets of this branch before compression
+    pub uncompressed_bytes: i64,
+    /// The leaves of this branch
+    pub leaves: Vec<LeafSchema>,
+    /// The sub-branches of this branch
+    pub branches: Vec<BranchSchema>,
+}
+
+/// Description of a `TLeaf`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeafSchema {
+    /// Name of the leaf
+    pub name: String,
+    /// Title of the leaf; usually its C++ type declaration
+    pub title: String,
+    /// Class of the leaf, e.g. `TLeafI`
+    pub class: String,
+    /// The Rust type of a single element; `None` if reading this leaf
+    /// is not supported
+    pub type_name: Option<String>,
+    /// Number of fixed length elements per entry
+    pub length: i32,
+    /// Leaf holding the number of elements per entry; `None` if the
+    /// number of elements is fixed
+    pub counter: Option<String>,
+}
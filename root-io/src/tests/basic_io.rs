@@ -30,7 +30,7 @@ fn list_of_rules() {
     let context = Context {
         source: PathBuf::from("").into(),
         offset: 0,
-        s: vec![],
+        s: vec![].into(),
     };
     use nom::HexDisplay;
     println!("{}", s.to_hex(16));
@@ -1,5 +1,8 @@
 use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
 
+use failure::Error;
 use futures::prelude::*;
 use nom::{
     combinator::verify,
@@ -10,10 +13,20 @@ use nom::{
 };
 
 use crate::{
-    code_gen::rust::ToRustType, core::parsers::*, core::types::*,
-    tree_reader::container::Container, tree_reader::leafs::TLeaf,
+    code_gen::rust::ToRustType, core::parsers::*, core::types::*, core::Compression,
+    schema::BranchSchema, tree_reader::container::Container, tree_reader::leafs::TLeaf,
+    tree_reader::value::*,
 };
 
+/// The entries to be read from a branch
+#[derive(Debug, Clone)]
+pub(crate) enum Selection<'a> {
+    /// A range of entries
+    Range(Range<i64>),
+    /// A sorted list of entries
+    Entries(&'a [i64]),
+}
+
 /// A `TBranch` describes one "Column" of a `TTree`
 /// Even though this class is described in the `TStreamerInfo` of a ROOT
 /// file, it is hard coded in this library to provide a reliable API
@@ -65,6 +78,31 @@ impl TBranch {
         }
     }
 
+    /// The direct sub-branches of this branch
+    pub fn sub_branches(&self) -> &[TBranch] {
+        &self.fbranches
+    }
+
+    /// Total size of the baskets of this branch before compression
+    pub fn uncompressed_bytes(&self) -> i64 {
+        self.ftotbytes
+    }
+
+    /// Total size of the baskets of this branch after compression
+    pub fn compressed_bytes(&self) -> i64 {
+        self.fzipbytes
+    }
+
+    /// Number of baskets holding the data of this branch
+    pub fn baskets(&self) -> usize {
+        self.containers.len()
+    }
+
+    /// Compression setting of this branch; may be inherited from the file
+    pub fn compression(&self) -> Compression {
+        Compression::new(self.fcompress)
+    }
+
     /// Access to the `Containers` containing the data of this branch
     pub(crate) fn containers(&self) -> &[Container] {
         &self.containers
@@ -75,6 +113,22 @@ impl TBranch {
         self.name.to_owned()
     }
 
+    /// Number of entries in this branch
+    pub fn entries(&self) -> i64 {
+        self.fentries
+    }
+
+    /// Name of the leaf holding the number of elements per entry of
+    /// this branch; `None` if the number of elements is fixed.
+    pub(crate) fn counter_name(&self) -> Option<String> {
+        self.fleaves.iter().find_map(|l| l.counter_name())
+    }
+
+    /// Does this branch have a leaf of the given name?
+    pub(crate) fn has_leaf(&self, name: &str) -> bool {
+        self.fleaves.iter().any(|l| l.name() == name)
+    }
+
     /// The type(s) of the elements in this branch For some reason,
     /// there may be situations where a branch has several leaves and thus types.
     pub fn element_types(&self) -> Vec<String> {
@@ -84,6 +138,19 @@ impl TBranch {
             .collect()
     }
 
+    /// Description of this branch and its sub-branches in the schema
+    /// of its tree
+    pub fn schema(&self) -> BranchSchema {
+        BranchSchema {
+            name: self.name.to_owned(),
+            entries: self.fentries,
+            compressed_bytes: self.fzipbytes,
+            uncompressed_bytes: self.ftotbytes,
+            leaves: self.fleaves.iter().map(|l| l.schema()).collect(),
+            branches: self.fbranches.iter().map(|b| b.schema()).collect(),
+        }
+    }
+
     /// Create an iterator over the data of a column (`TBranch`) with a
     /// constant number of element per entry (or at least not a
     /// variable number of entries which depends on an external list of
@@ -108,7 +175,7 @@ impl TBranch {
     ///# () {
     ///     let path = Path::new("./src/test_data/simple.root");
     ///     let f = RootFile::new(path).await.expect("Failed to open file");
-    ///     let tree = f.items()[0].as_tree().await.unwrap();
+    ///     let tree = f.get("tree").unwrap().as_tree().await.unwrap();
     ///     let numbers = tree
     ///         .branch_by_name("one").unwrap()
     ///         // Must pass parser as closure
@@ -136,6 +203,111 @@ impl TBranch {
             .flatten()
     }
 
+    /// The containers of this branch together with the number of
+    /// their first entry
+    pub(crate) fn containers_and_first_entries(&self) -> impl Iterator<Item = (&Container, i64)> {
+        self.containers
+            .iter()
+            .zip(self.fbasketentry.iter().copied())
+    }
+
+    /// Stream over the entries of this branch decoded according to the
+    /// type information of its leaves. `counts` holds the number of
+    /// elements of each entry and must be given if (and only if) this
+    /// branch has a variable number of elements per entry. If
+    /// `selection` is given, only those entries are returned and
+    /// baskets without any selected entries are not read at all.
+    pub(crate) fn as_value_stream(
+        &self,
+        counts: Option<Vec<u32>>,
+        selection: Option<Selection>,
+    ) -> Result<impl Stream<Item = Result<Value, Error>>, Error> {
+        let decoder = EntryDecoder::new(&self.fleaves, counts.is_some())?;
+        let name = self.name();
+        // The range of entries to read from each basket and the list
+        // of selected entries within it; `None` if all are selected
+        let baskets: Vec<_> = self
+            .containers_and_first_entries()
+            .enumerate()
+            .map(|(idx, (container, first))| {
+                let end = self.fbasketentry.get(idx + 1).copied().unwrap_or(i64::MAX);
+                let (range, sel) = match &selection {
+                    None => (first..end, None),
+                    Some(Selection::Range(range)) => {
+                        (range.start.max(first)..range.end.min(end), None)
+                    }
+                    Some(Selection::Entries(entries)) => {
+                        let lo = entries.partition_point(|e| *e < first);
+                        let hi = entries.partition_point(|e| *e < end);
+                        match &entries[lo..hi] {
+                            [] => (0..0, None),
+                            sel => (sel[0]..sel[sel.len() - 1] + 1, Some(sel.to_vec())),
+                        }
+                    }
+                };
+                (container.to_owned(), first, range, sel)
+            })
+            .filter(|(_, _, range, _)| !range.is_empty())
+            .collect();
+        let s = stream::iter(baskets)
+            .then(|(basket, first, range, sel)| async move {
+                basket
+                    .raw_data()
+                    .await
+                    .map(|data| (first, data, range, sel))
+            })
+            .map(move |basket| {
+                let (first, (n_entries_in_basket, buffer), range, sel) = match basket {
+                    Ok(basket) => basket,
+                    Err(e) => return stream::iter(vec![Err(e)]),
+                };
+                let mut buffer = buffer.as_slice();
+                let mut entries = Vec::with_capacity(n_entries_in_basket as usize);
+                for entry in first..(first + n_entries_in_basket as i64) {
+                    // No need to decode the rest of this basket
+                    if entry >= range.end {
+                        break;
+                    }
+                    let n = match &counts {
+                        Some(counts) => match counts.get(entry as usize) {
+                            Some(&n) => Some(n as usize),
+                            None => {
+                                entries.push(Err(format_err!(
+                                    "Counter of branch `{}` has fewer entries than the branch",
+                                    name
+                                )));
+                                break;
+                            }
+                        },
+                        None => None,
+                    };
+                    match decoder.decode(buffer, n) {
+                        Ok((rest, value)) => {
+                            buffer = rest;
+                            let is_selected = entry >= range.start
+                                && sel
+                                    .as_ref()
+                                    .is_none_or(|sel| sel.binary_search(&entry).is_ok());
+                            if is_selected {
+                                entries.push(Ok(value));
+                            }
+                        }
+                        Err(e) => {
+                            entries.push(Err(format_err!(
+                                "Failed to decode entry of branch `{}`: {:?}",
+                                name,
+                                e.map(|e| e.code)
+                            )));
+                            break;
+                        }
+                    }
+                }
+                stream::iter(entries)
+            })
+            .flatten();
+        Ok(s)
+    }
+
     /// Iterator over the data of a column (`TBranch`) with a variable
     /// number of elements per entry.  See the file
     /// [`read_esd.rs`](https://github.com/cbourjau/root-io/blob/master/src/tests/read_esd.rs)
@@ -184,6 +356,22 @@ pub fn tbranch_hdr<'s>(raw: &Raw<'s>, ctxt: &'s Context) -> IResult<&'s [u8], TB
     }
 }
 
+/// Read only the name of a `TBranch` (or of one of its subclasses)
+/// without parsing the rest of it.
+pub(crate) fn tbranch_name<'s>(raw: &Raw<'s>) -> IResult<&'s [u8], String> {
+    let i = match raw.classinfo {
+        "TBranchElement" | "TBranchObject" => {
+            let (i, _ver) = be_u16(raw.obj)?;
+            let (i, _cnt) = checked_byte_count(i)?;
+            i
+        }
+        _ => raw.obj,
+    };
+    let (i, _ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    Ok((i, tnamed.name))
+}
+
 pub fn tbranch<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TBranch> {
     let (i, _ver) = verify(be_u16, |v| [11, 12].contains(v))(i)?;
     let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
@@ -205,35 +393,8 @@ pub fn tbranch<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TBran
     let (i, fleaves) = length_value(checked_byte_count, |i| {
         tobjarray(TLeaf::parse_from_raw, i, context)
     })(i)?;
-    let (i, fbaskets) = length_value(checked_byte_count, |i| {
-        tobjarray(|r, _context| Ok((&[], r.obj)), i, context)
-    })(i)?;
-    let (i, fbasketbytes) = preceded(be_u8, count(be_i32, fmaxbaskets as usize))(i)?;
-    let (i, fbasketentry) = preceded(be_u8, count(be_i64, fmaxbaskets as usize))(i)?;
-    let (i, fbasketseek) = preceded(be_u8, count(be_u64, fmaxbaskets as usize))(i)?;
-    let (i, ffilename) = string(i)?;
-
+    let (i, (containers, fbasketentry)) = baskets(i, context, fwritebasket, fmaxbaskets)?;
     let name = tnamed.name;
-    let fbaskets = fbaskets
-        .into_iter()
-        .filter(|s| !s.is_empty())
-        .map(|s| Container::InMemory(s.to_vec()));
-    let nbaskets = fwritebasket as usize;
-    let fbasketbytes = fbasketbytes
-        .into_iter()
-        .take(nbaskets)
-        .map(|val| val as usize);
-    let fbasketentry = fbasketentry.into_iter().take(nbaskets).collect();
-    let fbasketseek = fbasketseek.into_iter().take(nbaskets);
-    let source = if ffilename.is_empty() {
-        context.source.to_owned()
-    } else {
-        unimplemented!("Root files referencing other Root files is not implemented")
-    };
-    let containers_disk = fbasketseek
-        .zip(fbasketbytes)
-        .map(|(seek, len)| Container::OnDisk(source.clone(), seek, len as u64));
-    let containers = fbaskets.chain(containers_disk).collect();
     Ok((
         i,
         TBranch {
@@ -256,3 +417,195 @@ pub fn tbranch<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TBran
         },
     ))
 }
+
+/// Parse the baskets of a branch together with the number of their
+/// first entry. `fwritebasket` baskets were written to disk. Baskets
+/// stored inline with the branch (e.g. the last basket of a tree
+/// which was autosaved but never closed) hold the most recent entries
+/// and hence come after those on disk; just like their first entries
+/// in `fBasketEntry`.
+fn baskets<'s>(
+    i: &'s [u8],
+    context: &'s Context,
+    fwritebasket: i32,
+    fmaxbaskets: i32,
+) -> IResult<&'s [u8], (Vec<Container>, Vec<i64>)> {
+    let (i, fbaskets) = length_value(checked_byte_count, |i| {
+        tobjarray(|r, _context| Ok((&[], r.obj)), i, context)
+    })(i)?;
+    let (i, fbasketbytes) = preceded(be_u8, count(be_i32, fmaxbaskets as usize))(i)?;
+    let (i, fbasketentry) = preceded(be_u8, count(be_i64, fmaxbaskets as usize))(i)?;
+    let (i, fbasketseek) = preceded(be_u8, count(be_u64, fmaxbaskets as usize))(i)?;
+    let (i, ffilename) = string(i)?;
+
+    // Baskets stored inline with the branch are not copied; the
+    // container rather points into the buffer of the context
+    let fbaskets: Vec<_> = fbaskets
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .map(|s| match context.range_of(s) {
+            Some(range) => Container::InMemory(context.s.clone(), range),
+            None => Container::InMemory(Arc::new(s.to_vec()), 0..s.len()),
+        })
+        .collect();
+    let nbaskets = fwritebasket as usize;
+    let fbasketentry = fbasketentry
+        .into_iter()
+        .take(nbaskets + fbaskets.len())
+        .collect();
+    let source = if ffilename.is_empty() {
+        context.source.to_owned()
+    } else {
+        unimplemented!("Root files referencing other Root files is not implemented")
+    };
+    let containers = fbasketseek
+        .into_iter()
+        .zip(fbasketbytes)
+        .take(nbaskets)
+        .map(|(seek, len)| Container::OnDisk(source.clone(), seek, len as u64))
+        .chain(fbaskets)
+        .collect();
+    Ok((i, (containers, fbasketentry)))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::path::Path;
+
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::core::RootFile;
+
+    /// Serialize the baskets of a branch with `max` slots as read by
+    /// `baskets`. The baskets at `disk` are stored on disk while
+    /// `inline` is stored with the branch in the slot after them.
+    fn serialize_baskets(
+        max: usize,
+        disk: &[(u64, u64)],
+        inline: &[u8],
+        entries: &[i64],
+    ) -> Vec<u8> {
+        let byte_count = |len: usize| (0x4000_0000 | len as u32).to_be_bytes();
+        // TObjArray of the baskets; unused slots are null pointers
+        let mut arr = vec![0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        arr.extend((max as i32).to_be_bytes());
+        arr.extend(0i32.to_be_bytes());
+        for slot in 0..max {
+            if slot == disk.len() {
+                let mut obj = 0xFFFF_FFFFu32.to_be_bytes().to_vec();
+                obj.extend(b"TBasket\0");
+                obj.extend(byte_count(inline.len()));
+                obj.extend(inline);
+                arr.extend(byte_count(obj.len()));
+                arr.extend(obj);
+            } else {
+                arr.extend(0u32.to_be_bytes());
+            }
+        }
+        let mut buf = byte_count(arr.len()).to_vec();
+        buf.extend(arr);
+        buf.push(0);
+        for slot in 0..max {
+            buf.extend(
+                disk.get(slot)
+                    .map_or(0, |(_, len)| *len as i32)
+                    .to_be_bytes(),
+            );
+        }
+        buf.push(0);
+        for slot in 0..max {
+            buf.extend(entries.get(slot).copied().unwrap_or(0).to_be_bytes());
+        }
+        buf.push(0);
+        for slot in 0..max {
+            buf.extend(disk.get(slot).map_or(0, |(seek, _)| *seek).to_be_bytes());
+        }
+        // Empty file name
+        buf.push(0);
+        buf
+    }
+
+    /// Position and size of the only basket of `branch`
+    fn only_basket(branch: &TBranch) -> (u64, u64) {
+        match branch.containers() {
+            [Container::OnDisk(_, seek, len)] => (*seek, *len),
+            _ => panic!("Expected a single basket on disk"),
+        }
+    }
+
+    #[tokio::test]
+    async fn inline_basket() {
+        let f = RootFile::new(Path::new("./src/test_data/HZZ.root"))
+            .await
+            .unwrap();
+        let tree = f.get("events").unwrap().as_tree().await.unwrap();
+        // Emulate an autosaved tree whose last basket is still stored
+        // with the branch. The entries of `NJet` are on disk and the
+        // following ones (taken from `NMuon`) are stored inline.
+        let jets = tree.branch_by_name("NJet").unwrap();
+        let muons = tree.branch_by_name("NMuon").unwrap();
+        let (seek, len) = only_basket(muons);
+        let inline = f.source.fetch(seek, len).await.unwrap();
+        let n = tree.entries();
+        let context = Context {
+            source: f.source.clone(),
+            offset: 0,
+            s: Arc::new(serialize_baskets(3, &[only_basket(jets)], &inline, &[0, n])),
+        };
+        let (_, (containers, fbasketentry)) = baskets(&context.s, &context, 1, 3).unwrap();
+        assert!(matches!(
+            containers.as_slice(),
+            [Container::OnDisk(..), Container::InMemory(..)]
+        ));
+        assert_eq!(fbasketentry, &[0, n]);
+        let autosaved = TBranch {
+            fwritebasket: 1,
+            fbasketentry,
+            containers,
+            ..jets.clone()
+        };
+
+        let mut expected: Vec<_> = jets
+            .as_value_stream(None, None)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let muon_values: Vec<_> = muons
+            .as_value_stream(None, None)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        expected.extend(muon_values);
+        let values: Vec<_> = autosaved
+            .as_value_stream(None, None)
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(values, expected);
+
+        // Entries of the basket on disk and of the inline one
+        let selection = [0, n - 1, n, 2 * n - 1];
+        let values: Vec<_> = autosaved
+            .as_value_stream(None, Some(Selection::Entries(&selection)))
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let selected: Vec<_> = selection
+            .iter()
+            .map(|&e| expected[e as usize].clone())
+            .collect();
+        assert_eq!(values, selected);
+        let values: Vec<_> = autosaved
+            .as_value_stream(None, Some(Selection::Range(n - 1..n + 1)))
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(values, expected[n as usize - 1..n as usize + 1]);
+    }
+}
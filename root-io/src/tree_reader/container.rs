@@ -1,14 +1,22 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
 use failure::Error;
 use nom::combinator::rest;
+use nom::error::{make_error, ErrorKind};
 use nom::number::complete::*;
 use nom::*;
 
 use crate::core::*;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub(crate) enum Container {
-    /// Decompressed content of a `TBasket`
-    InMemory(Vec<u8>),
+    /// Content of a `TBasket` which was stored inline with its
+    /// `TBranch`. The basket is not copied out of the buffer of the
+    /// `TTree` it was found in; instead, we keep a reference to
+    /// that buffer and the position of the basket within it.
+    InMemory(Arc<Vec<u8>>, Range<usize>),
     /// Filename, start byte, and len of a `TBasket` on disk
     OnDisk(Source, u64, u64),
 }
@@ -16,13 +24,16 @@ pub(crate) enum Container {
 impl Container {
     /// Return the number of entries and the data; reading it from disk if necessary
     pub(crate) async fn raw_data(self) -> Result<(u32, Vec<u8>), Error> {
-        let buf = match self {
-            Container::InMemory(buf) => buf,
-            Container::OnDisk(source, seek, len) => source.fetch(seek, len).await?,
-        };
-        match tbasket2vec(buf.as_slice()) {
+        let parse = |buf: &[u8]| match tbasket2vec(buf) {
             Ok((_, v)) => Ok(v),
-            _ => Err(format_err!("tbasket2vec parser failed")),
+            Err(e) => Err(format_err!(
+                "Failed to read basket: {:?}",
+                e.map(|e| e.code)
+            )),
+        };
+        match self {
+            Container::InMemory(buf, range) => parse(&buf[range]),
+            Container::OnDisk(source, seek, len) => parse(&source.fetch(seek, len).await?),
         }
     }
     // /// For debugging: Try to find the file of this container. Out of luck if the container was inlined
@@ -35,6 +46,23 @@ impl Container {
     // }
 }
 
+impl fmt::Debug for Container {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Container::InMemory(_, range) => f
+                .debug_tuple("InMemory")
+                .field(&format_args!("Buffer of {} bytes", range.len()))
+                .finish(),
+            Container::OnDisk(source, seek, len) => f
+                .debug_tuple("OnDisk")
+                .field(source)
+                .field(seek)
+                .field(len)
+                .finish(),
+        }
+    }
+}
+
 /// Return a tuple indicating the number of elements in this basket
 /// and the content as a Vec<u8>
 fn tbasket2vec(input: &[u8]) -> IResult<&[u8], (u32, Vec<u8>)> {
@@ -47,14 +75,18 @@ fn tbasket2vec(input: &[u8]) -> IResult<&[u8], (u32, Vec<u8>)> {
     let (input, _flag) = be_i8(input)?;
     let (input, buf) = rest(input)?;
     let buf = if hdr.uncomp_len as usize > buf.len() {
-        decompress(buf).unwrap().1
+        decompress(buf)?.1
     } else {
         buf.to_vec()
     };
     // Not the whole buffer is filled, no, no, no, that
     // would be to easy! Its only filled up to `last`,
     // whereby we have to take the key_len into account...
-    let useful_bytes = (last - hdr.key_len as u32) as usize;
+    let useful_bytes = last
+        .checked_sub(hdr.key_len as u32)
+        .map(|n| n as usize)
+        .filter(|&n| n <= buf.len())
+        .ok_or_else(|| nom::Err::Failure(make_error(input, ErrorKind::Eof)))?;
     Ok((
         input,
         (n_entry_buf, buf.as_slice()[..useful_bytes].to_vec()),
@@ -0,0 +1,381 @@
+use nom::{
+    combinator::{cond, peek},
+    error::{make_error, ErrorKind},
+    multi::{count, length_value},
+    number::complete::*,
+    IResult,
+};
+
+use crate::core::parsers::*;
+use crate::core::types::*;
+
+/// Number of entries covered by each `TEntryListBlock`
+const BLOCK_SIZE: i64 = 4000;
+
+/// A selection of entries of a `Tree` as stored in a `TEntryList` or
+/// a `TEventList`. Entry lists spanning several trees (e.g. those
+/// created for a `TChain`) hold one sub-list per tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryList {
+    name: String,
+    tree_name: String,
+    file_name: String,
+    /// Sorted entry numbers
+    entries: Vec<i64>,
+    /// Lists of the individual trees if this list spans several trees
+    sub_lists: Vec<EntryList>,
+}
+
+impl EntryList {
+    /// Create a new list selecting the given entries of the tree
+    /// `tree_name`. An empty `tree_name` matches any tree.
+    pub fn new(tree_name: &str, mut entries: Vec<i64>) -> Self {
+        entries.sort_unstable();
+        entries.dedup();
+        Self {
+            name: String::new(),
+            tree_name: tree_name.to_string(),
+            file_name: String::new(),
+            entries,
+            sub_lists: vec![],
+        }
+    }
+
+    /// The name of this list
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of the tree this list applies to. Empty for `TEventList`s
+    /// and lists spanning several trees.
+    pub fn tree_name(&self) -> &str {
+        &self.tree_name
+    }
+
+    /// Name of the file containing the tree this list applies to
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// The selected entry numbers in ascending order. Empty if this
+    /// list spans several trees; see `sub_lists`.
+    pub fn entries(&self) -> &[i64] {
+        &self.entries
+    }
+
+    /// The lists of the individual trees if this list spans several trees
+    pub fn sub_lists(&self) -> &[EntryList] {
+        &self.sub_lists
+    }
+
+    /// Total number of selected entries (including those of the sub-lists)
+    pub fn len(&self) -> usize {
+        self.entries.len() + self.sub_lists.iter().map(EntryList::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Is the given entry selected by this list?
+    pub fn contains(&self, entry: i64) -> bool {
+        self.entries.binary_search(&entry).is_ok()
+    }
+
+    /// Find the list applying to the tree `tree_name` in the file
+    /// `file_name`. This is either this list itself or one of its
+    /// sub-lists. The sub-lists of a list created for a `TChain`
+    /// usually apply to trees of the same name and are told apart by
+    /// the names of their files. A relative path matches the absolute
+    /// paths ending in it; without a `file_name` any file matches.
+    /// Returns `None` if no or several (ambiguous) sub-lists match.
+    pub fn for_tree(&self, tree_name: &str, file_name: Option<&str>) -> Option<&EntryList> {
+        if self.sub_lists.is_empty() {
+            let tree_matches = self.tree_name.is_empty() || self.tree_name == tree_name;
+            let file_matches = match file_name {
+                Some(file_name) if !self.file_name.is_empty() => {
+                    same_file(&self.file_name, file_name)
+                }
+                _ => true,
+            };
+            return if tree_matches && file_matches {
+                Some(self)
+            } else {
+                None
+            };
+        }
+        let mut matches = self
+            .sub_lists
+            .iter()
+            .filter_map(|l| l.for_tree(tree_name, file_name));
+        match (matches.next(), matches.next()) {
+            (Some(l), None) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+/// Do the given file names refer to the same file? A relative path
+/// matches an absolute one ending in it.
+fn same_file(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let short = short.trim_start_matches("./");
+    long.strip_suffix(short)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+}
+
+/// Parse the entry numbers stored in a `TEntryListBlock` relative to
+/// the first entry of that block
+fn tentrylistblock(i: &[u8]) -> IResult<&[u8], Vec<i64>> {
+    let (i, _ver) = be_u16(i)?;
+    let (i, _tobj) = tobject(i)?;
+    let (i, npassed) = be_i32(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, is_array) = be_u8(i)?;
+    let (i, indices) = cond(is_array != 0, count(be_u16, n as usize))(i)?;
+    let (i, ftype) = be_i32(i)?;
+    let (i, passing) = be_u8(i)?;
+    let indices = indices.unwrap_or_default();
+    let entries = match (ftype, passing != 0) {
+        // Bit mask of all entries in this block
+        (0, _) => indices
+            .iter()
+            .enumerate()
+            .flat_map(|(word_idx, word)| {
+                (0..16)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| word_idx as i64 * 16 + bit)
+            })
+            .collect(),
+        // List of passing entries
+        (1, true) => indices.iter().map(|&idx| idx as i64).collect(),
+        // List of entries which did *not* pass
+        (1, false) => (0..BLOCK_SIZE)
+            .filter(|idx| !indices.contains(&(*idx as u16)))
+            .take(npassed as usize)
+            .collect(),
+        _ => return Err(nom::Err::Failure(make_error(i, ErrorKind::Switch))),
+    };
+    Ok((i, entries))
+}
+
+/// Parse a `TEventList`
+fn teventlist(i: &[u8]) -> IResult<&[u8], EntryList> {
+    let (i, _ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    let (i, n) = be_i32(i)?;
+    let (i, _fsize) = be_i32(i)?;
+    let (i, _fdelta) = be_i32(i)?;
+    let (i, _freapply) = be_u8(i)?;
+    let (i, is_array) = be_u8(i)?;
+    let (i, entries) = cond(is_array != 0, count(be_i64, n as usize))(i)?;
+    let mut list = EntryList::new("", entries.unwrap_or_default());
+    list.name = tnamed.name;
+    Ok((i, list))
+}
+
+/// Parse a `TEntryList`
+fn tentrylist<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], EntryList> {
+    let (i, _ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    let (i, sub_lists) = match peek(be_u32)(i)? {
+        (i, 0) => be_u32(i).map(|(i, _)| (i, vec![]))?,
+        (i, _) => {
+            let (i, r) = raw(i, context)?;
+            let (_, lists) = tlist(r.obj, context)?;
+            let lists = lists
+                .iter()
+                .map(|r| entry_list(r, context).map(|(_, l)| l))
+                .collect::<Result<_, _>>()?;
+            (i, lists)
+        }
+    };
+    let (i, _fnblocks) = be_i32(i)?;
+    let (i, blocks) = match peek(be_u32)(i)? {
+        (i, 0) => be_u32(i).map(|(i, _)| (i, vec![]))?,
+        (i, _) => {
+            let (i, r) = raw(i, context)?;
+            let (_, blocks) = tobjarray(|r, _| tentrylistblock(r.obj), r.obj, context)?;
+            (i, blocks)
+        }
+    };
+    let (i, n) = be_i64(i)?;
+    let (i, _fentriestoprocess) = be_i64(i)?;
+    let (i, tree_name) = string(i)?;
+    let (i, file_name) = string(i)?;
+    let entries: Vec<_> = blocks
+        .into_iter()
+        .enumerate()
+        .flat_map(|(block_idx, entries)| {
+            let offset = block_idx as i64 * BLOCK_SIZE;
+            entries.into_iter().map(move |e| e + offset)
+        })
+        .collect();
+    if sub_lists.is_empty() && entries.len() as i64 != n {
+        return Err(nom::Err::Failure(make_error(i, ErrorKind::Verify)));
+    }
+    Ok((
+        i,
+        EntryList {
+            name: tnamed.name,
+            tree_name,
+            file_name,
+            entries,
+            sub_lists,
+        },
+    ))
+}
+
+/// Parse a `TEntryList` or a `TEventList`
+pub(crate) fn entry_list<'s>(raw: &Raw<'s>, context: &'s Context) -> IResult<&'s [u8], EntryList> {
+    match raw.classinfo {
+        "TEntryList" => tentrylist(raw.obj, context),
+        "TEventList" => teventlist(raw.obj),
+        _ => Err(nom::Err::Failure(make_error(raw.obj, ErrorKind::Tag))),
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::core::test_utils::Writer;
+    use crate::core::Source;
+    use crate::MAP_OFFSET;
+
+    /// Write a `TEntryListBlock` holding the given indices
+    fn block(w: &mut Writer, npassed: i32, indices: &[u16], ftype: i32, passing: bool) {
+        w.bytes([0, 1]);
+        w.tobject();
+        w.bytes(npassed.to_be_bytes());
+        w.bytes((indices.len() as i32).to_be_bytes());
+        w.bytes([1]);
+        indices.iter().for_each(|i| w.bytes(i.to_be_bytes()));
+        w.bytes(ftype.to_be_bytes());
+        w.bytes([passing as u8]);
+    }
+
+    #[test]
+    fn entry_list_blocks() {
+        let parse = |npassed, indices: &[u16], ftype, passing| {
+            let mut w = Writer::default();
+            block(&mut w, npassed, indices, ftype, passing);
+            tentrylistblock(&w.buf).unwrap().1
+        };
+        assert_eq!(parse(3, &[0b101, 0, 0b1], 0, true), vec![0, 2, 32]);
+        assert_eq!(parse(2, &[7, 3999], 1, true), vec![7, 3999]);
+        assert_eq!(parse(4, &[1, 2], 1, false), vec![0, 3, 4, 5]);
+    }
+
+    #[test]
+    fn event_list() {
+        let mut w = Writer::default();
+        w.bytes([0, 4]);
+        w.tnamed("elis", "");
+        w.bytes(2i32.to_be_bytes());
+        w.bytes(100i32.to_be_bytes());
+        w.bytes(100i32.to_be_bytes());
+        w.bytes([0, 1]);
+        w.bytes(42i64.to_be_bytes());
+        w.bytes(7i64.to_be_bytes());
+        let list = teventlist(&w.buf).unwrap().1;
+        assert_eq!(list.name(), "elis");
+        assert_eq!(list.entries(), &[7, 42]);
+        assert!(list.contains(42) && !list.contains(8));
+        assert_eq!(list.for_tree("anything", None), Some(&list));
+    }
+
+    /// Write the members of a `TEntryList` selecting `passing` of the
+    /// first block of entries of the given tree
+    fn entry_list_members(w: &mut Writer, tree: &str, file: &str, passing: &[u16]) {
+        w.bytes([0, 2]);
+        w.tnamed("elist", "");
+        // fLists
+        w.null();
+        w.bytes(1i32.to_be_bytes());
+        w.object("TObjArray", |w| {
+            w.bytes([0, 3]);
+            w.tobject();
+            w.string("");
+            w.bytes(1i32.to_be_bytes());
+            w.bytes(0i32.to_be_bytes());
+            w.object("TEntryListBlock", |w| {
+                block(w, passing.len() as i32, passing, 1, true)
+            });
+        });
+        w.bytes((passing.len() as i64).to_be_bytes());
+        w.bytes((passing.len() as i64).to_be_bytes());
+        w.string(tree);
+        w.string(file);
+        // fReapply
+        w.bytes([0]);
+    }
+
+    #[test]
+    fn chain_entry_list() {
+        let files = [
+            "/data/000139038/AliESDs.root",
+            "/data/000139173/AliESDs.root",
+        ];
+        let mut w = Writer::default();
+        w.with_byte_count(|w| {
+            w.bytes([0, 2]);
+            w.tnamed("elist", "");
+            w.object("TList", |w| {
+                w.bytes([0, 5]);
+                w.tobject();
+                w.string("");
+                w.bytes(2i32.to_be_bytes());
+                w.object("TEntryList", |w| {
+                    entry_list_members(w, "esdTree", files[0], &[1, 5])
+                });
+                w.string("");
+                w.object("TEntryList", |w| {
+                    entry_list_members(w, "esdTree", files[1], &[3])
+                });
+                w.string("");
+            });
+            w.bytes(0i32.to_be_bytes());
+            // fBlocks
+            w.null();
+            w.bytes(3i64.to_be_bytes());
+            w.bytes(3i64.to_be_bytes());
+            w.string("esdTree");
+            w.string(files[1]);
+            w.bytes([0]);
+        });
+
+        let context = Context {
+            source: Source::new(Path::new("unused.root")),
+            offset: MAP_OFFSET,
+            s: Arc::new(w.buf),
+        };
+        let (_, list) = length_value(checked_byte_count, |i| {
+            entry_list(
+                &Raw {
+                    classinfo: "TEntryList",
+                    obj: i,
+                },
+                &context,
+            )
+        })(&context.s)
+        .unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.sub_lists().len(), 2);
+        assert_eq!(list.sub_lists()[1].file_name(), files[1]);
+
+        // Both sub-lists apply to trees named `esdTree`
+        assert!(list.for_tree("esdTree", None).is_none());
+        let first = list.for_tree("esdTree", Some(files[0])).unwrap();
+        assert_eq!(first.entries(), &[1, 5]);
+        let second = list
+            .for_tree("esdTree", Some("000139173/AliESDs.root"))
+            .unwrap();
+        assert_eq!(second.entries(), &[3]);
+        assert!(list.for_tree("esdTree", Some("AliESDs.root")).is_none());
+        assert!(list.for_tree("esdTree", Some("other.root")).is_none());
+        assert!(list.for_tree("other", Some(files[0])).is_none());
+    }
+}
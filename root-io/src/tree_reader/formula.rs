@@ -0,0 +1,668 @@
+//! A small expression language in the spirit of ROOT's
+//! `TTreeFormula`. Expressions such as
+//! `abs(PrimaryVertex.AliVertex.fPosition[2]) < 10 && Tracks.fTPCncls > 70`
+//! may be used to compute derived columns or to select entries of a
+//! `Tree`.
+//!
+//! Supported are
+//! - numbers and branch names as identifiers; dimensions in branch
+//!   names (e.g. `fPosition[3]`) may be omitted,
+//! - the aliases of the tree (see `Tree::aliases`),
+//! - the arithmetic operators `+ - * / %`, comparisons `== != < <= > >=`
+//!   and the logical operators `&& || !`,
+//! - indexing of arrays with `[]`,
+//! - the functions `abs`, `sqrt`, `exp`, `log`, `log10`, `sin`, `cos`,
+//!   `tan`, `asin`, `acos`, `atan`, `floor`, `ceil`, `atan2`, `pow`,
+//!   `min` and `max` (optionally prefixed with `TMath::`),
+//! - the reductions `Sum$`, `Length$`, `Max$` and `Min$` over arrays;
+//!   the trailing `$` is optional and `Max` and `Min` are reductions
+//!   if called with a single argument.
+//!
+//! Operations on arrays are applied element-wise; scalars are
+//! broadcasted. Booleans are represented as `1.0` and `0.0`.
+//! Accessing an array out of its bounds yields `NaN`, such that
+//! comparisons with such a value are false.
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use failure::Error;
+use futures::prelude::*;
+
+use crate::tree_reader::{Tree, Value};
+
+/// Aliases may refer to other aliases; this limits the depth to
+/// catch cyclic definitions
+const MAX_ALIAS_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+/// Operators sorted such that longer ones come first
+const OPERATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", "[", "]",
+    ",",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let mut len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            // Exponent
+            if rest[len..].starts_with(['e', 'E']) {
+                let exp = rest[len + 1..].trim_start_matches(['+', '-']);
+                let exp_len = exp.find(|c: char| !c.is_ascii_digit()).unwrap_or(exp.len());
+                if exp_len > 0 {
+                    len = rest.len() - exp.len() + exp_len;
+                }
+            }
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| format_err!("Invalid number `{}`", &rest[..len]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let mut len = 0;
+            loop {
+                let tail = &rest[len..];
+                match tail.chars().next() {
+                    Some(c) if c.is_alphanumeric() || "_.$".contains(c) => len += c.len_utf8(),
+                    Some(':') if tail.starts_with("::") => len += 2,
+                    _ => break,
+                }
+            }
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| format_err!("Unexpected character `{}` in `{}`", c, s))?;
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    /// The operator and its left binding power
+    fn from_token(t: &Token) -> Option<(Self, u8)> {
+        use BinOp::*;
+        Some(match t {
+            Token::Op("||") => (Or, 1),
+            Token::Op("&&") => (And, 2),
+            Token::Op("==") => (Eq, 3),
+            Token::Op("!=") => (Ne, 3),
+            Token::Op("<") => (Lt, 4),
+            Token::Op("<=") => (Le, 4),
+            Token::Op(">") => (Gt, 4),
+            Token::Op(">=") => (Ge, 4),
+            Token::Op("+") => (Add, 5),
+            Token::Op("-") => (Sub, 5),
+            Token::Op("*") => (Mul, 6),
+            Token::Op("/") => (Div, 6),
+            Token::Op("%") => (Rem, 6),
+            _ => return None,
+        })
+    }
+
+    fn apply(self, a: f64, b: f64) -> f64 {
+        use BinOp::*;
+        let from_bool = |b| if b { 1.0 } else { 0.0 };
+        match self {
+            Or => from_bool(a != 0.0 || b != 0.0),
+            And => from_bool(a != 0.0 && b != 0.0),
+            Eq => from_bool(a == b),
+            Ne => from_bool(a != b),
+            Lt => from_bool(a < b),
+            Le => from_bool(a <= b),
+            Gt => from_bool(a > b),
+            Ge => from_bool(a >= b),
+            Add => a + b,
+            Sub => a - b,
+            Mul => a * b,
+            Div => a / b,
+            Rem => a % b,
+        }
+    }
+}
+
+/// Binding power of prefix operators
+const PREFIX_BP: u8 = 7;
+
+/// Element-wise mathematical functions
+enum MathFn {
+    Unary(fn(f64) -> f64),
+    Binary(fn(f64, f64) -> f64),
+}
+
+const MATH_FUNCTIONS: &[(&str, MathFn)] = &[
+    ("abs", MathFn::Unary(f64::abs)),
+    ("sqrt", MathFn::Unary(f64::sqrt)),
+    ("exp", MathFn::Unary(f64::exp)),
+    ("log", MathFn::Unary(f64::ln)),
+    ("log10", MathFn::Unary(f64::log10)),
+    ("sin", MathFn::Unary(f64::sin)),
+    ("cos", MathFn::Unary(f64::cos)),
+    ("tan", MathFn::Unary(f64::tan)),
+    ("asin", MathFn::Unary(f64::asin)),
+    ("acos", MathFn::Unary(f64::acos)),
+    ("atan", MathFn::Unary(f64::atan)),
+    ("floor", MathFn::Unary(f64::floor)),
+    ("ceil", MathFn::Unary(f64::ceil)),
+    ("atan2", MathFn::Binary(f64::atan2)),
+    ("pow", MathFn::Binary(f64::powf)),
+    ("power", MathFn::Binary(f64::powf)),
+    ("min", MathFn::Binary(f64::min)),
+    ("max", MathFn::Binary(f64::max)),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    /// Index into `MATH_FUNCTIONS`
+    Math(usize),
+    Sum,
+    Length,
+    Max,
+    Min,
+}
+
+impl Func {
+    /// Find the function of the given name called with `n_args` arguments
+    fn new(name: &str, n_args: usize) -> Result<Self, Error> {
+        let lower = name.strip_prefix("TMath::").unwrap_or(name);
+        let lower = lower.strip_suffix('$').unwrap_or(lower).to_lowercase();
+        let func = match (lower.as_str(), n_args) {
+            ("sum", _) => Func::Sum,
+            ("length", _) => Func::Length,
+            // Reductions if called with a single argument
+            ("max", 1) => Func::Max,
+            ("min", 1) => Func::Min,
+            (lower, _) => MATH_FUNCTIONS
+                .iter()
+                .position(|(f, _)| *f == lower)
+                .map(Func::Math)
+                .ok_or_else(|| format_err!("Unknown function `{}`", name))?,
+        };
+        let expected = match func {
+            Func::Math(idx) => match MATH_FUNCTIONS[idx].1 {
+                MathFn::Unary(_) => 1,
+                MathFn::Binary(_) => 2,
+            },
+            _ => 1,
+        };
+        if expected != n_args {
+            return Err(format_err!(
+                "Function `{}` takes {} argument(s) but {} were given",
+                name,
+                expected,
+                n_args
+            ));
+        }
+        Ok(func)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Ident(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), Error> {
+        match self.next() {
+            Some(Token::Op(o)) if o == op => Ok(()),
+            Some(t) => Err(format_err!("Expected `{}` but found {:?}", op, t)),
+            None => Err(format_err!(
+                "Expected `{}` but reached end of expression",
+                op
+            )),
+        }
+    }
+
+    /// Parse an expression whose operators bind stronger than `min_bp`
+    fn expr(&mut self, min_bp: u8) -> Result<Expr, Error> {
+        let mut lhs = match self.next() {
+            Some(Token::Number(n)) => Expr::Number(n),
+            Some(Token::Op("-")) => Expr::Neg(Box::new(self.expr(PREFIX_BP)?)),
+            Some(Token::Op("+")) => self.expr(PREFIX_BP)?,
+            Some(Token::Op("!")) => Expr::Not(Box::new(self.expr(PREFIX_BP)?)),
+            Some(Token::Op("(")) => {
+                let e = self.expr(0)?;
+                self.expect(")")?;
+                e
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::Op("(")) => {
+                self.next();
+                let mut args = vec![self.expr(0)?];
+                while self.peek() == Some(&Token::Op(",")) {
+                    self.next();
+                    args.push(self.expr(0)?);
+                }
+                self.expect(")")?;
+                Expr::Call(Func::new(&name, args.len())?, args)
+            }
+            Some(Token::Ident(name)) => Expr::Ident(name),
+            Some(t) => return Err(format_err!("Unexpected token {:?}", t)),
+            None => return Err(format_err!("Unexpected end of expression")),
+        };
+        loop {
+            match self.peek() {
+                Some(Token::Op("[")) => {
+                    self.next();
+                    let idx = self.expr(0)?;
+                    self.expect("]")?;
+                    lhs = Expr::Index(Box::new(lhs), Box::new(idx));
+                }
+                Some(t) => match BinOp::from_token(t) {
+                    Some((op, bp)) if bp > min_bp => {
+                        self.next();
+                        let rhs = self.expr(bp)?;
+                        lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                    }
+                    _ => break,
+                },
+                None => break,
+            }
+        }
+        Ok(lhs)
+    }
+}
+
+impl Expr {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.expr(0)?;
+        match parser.next() {
+            None => Ok(expr),
+            Some(t) => Err(format_err!("Unexpected token {:?} in `{}`", t, s)),
+        }
+    }
+
+    /// Apply `f` to all sub-expressions in depth first order
+    fn try_map(self, f: &mut impl FnMut(Expr) -> Result<Expr, Error>) -> Result<Expr, Error> {
+        let boxed = |e: Box<Expr>, f: &mut _| e.try_map(f).map(Box::new);
+        let e = match self {
+            Expr::Neg(e) => Expr::Neg(boxed(e, f)?),
+            Expr::Not(e) => Expr::Not(boxed(e, f)?),
+            Expr::Binary(op, a, b) => Expr::Binary(op, boxed(a, f)?, boxed(b, f)?),
+            Expr::Index(a, i) => Expr::Index(boxed(a, f)?, boxed(i, f)?),
+            Expr::Call(func, args) => Expr::Call(
+                func,
+                args.into_iter()
+                    .map(|a| a.try_map(f))
+                    .collect::<Result<_, _>>()?,
+            ),
+            e => e,
+        };
+        f(e)
+    }
+
+    /// Replace the aliases of `tree` by their definitions
+    fn expand_aliases(self, tree: &Tree, depth: usize) -> Result<Expr, Error> {
+        let aliases = tree.aliases();
+        if aliases.is_empty() {
+            return Ok(self);
+        }
+        self.try_map(&mut |e| match e {
+            Expr::Ident(ref name) => match aliases.iter().find(|(alias, _)| alias == name) {
+                Some(_) if depth >= MAX_ALIAS_DEPTH => {
+                    Err(format_err!("Alias `{}` is defined recursively", name))
+                }
+                Some((_, def)) => Expr::parse(def)?.expand_aliases(tree, depth + 1),
+                None => Ok(e),
+            },
+            e => Ok(e),
+        })
+    }
+
+    fn identifiers<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Ident(name) => {
+                if !out.contains(&name.as_str()) {
+                    out.push(name)
+                }
+            }
+            Expr::Neg(e) | Expr::Not(e) => e.identifiers(out),
+            Expr::Binary(_, a, b) | Expr::Index(a, b) => {
+                a.identifiers(out);
+                b.identifiers(out);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.identifiers(out)),
+        }
+    }
+
+    /// Evaluate this expression for one entry. `columns` maps
+    /// identifiers to the position of their values in `row`
+    fn eval(&self, columns: &HashMap<String, usize>, row: &[Eval]) -> Result<Eval, Error> {
+        let eval = |e: &Expr| e.eval(columns, row);
+        Ok(match self {
+            Expr::Number(n) => Eval::Scalar(*n),
+            Expr::Ident(name) => row[columns[name]].clone(),
+            Expr::Neg(e) => eval(e)?.map(&|v| -v),
+            Expr::Not(e) => eval(e)?.map(&|v| if v == 0.0 { 1.0 } else { 0.0 }),
+            Expr::Binary(op, a, b) => eval(a)?.zip_with(eval(b)?, &|a, b| op.apply(a, b))?,
+            Expr::Index(a, idx) => match (eval(a)?, eval(idx)?) {
+                (Eval::Array(els), Eval::Scalar(idx)) => {
+                    if idx < 0.0 || idx.fract() != 0.0 {
+                        return Err(format_err!("Invalid index {}", idx));
+                    }
+                    els.into_iter()
+                        .nth(idx as usize)
+                        .unwrap_or(Eval::Scalar(f64::NAN))
+                }
+                (Eval::Scalar(_), _) => return Err(format_err!("Cannot index a scalar")),
+                (_, Eval::Array(_)) => return Err(format_err!("Index must be a scalar")),
+            },
+            Expr::Call(func, args) => {
+                let mut args = args.iter().map(eval);
+                let a = args.next().unwrap()?;
+                match func {
+                    Func::Math(idx) => match &MATH_FUNCTIONS[*idx].1 {
+                        MathFn::Unary(f) => a.map(f),
+                        MathFn::Binary(f) => a.zip_with(args.next().unwrap()?, f)?,
+                    },
+                    Func::Length => Eval::Scalar(match a {
+                        Eval::Array(els) => els.len() as f64,
+                        Eval::Scalar(_) => 1.0,
+                    }),
+                    Func::Sum => a.reduce(0.0, &|a, b| a + b)?,
+                    Func::Max => a.reduce(0.0, &f64::max)?,
+                    Func::Min => a.reduce(0.0, &f64::min)?,
+                }
+            }
+        })
+    }
+}
+
+/// Intermediate value of an evaluation
+#[derive(Debug, Clone, PartialEq)]
+enum Eval {
+    Scalar(f64),
+    Array(Vec<Eval>),
+}
+
+impl Eval {
+    fn from_value(v: Value) -> Result<Self, Error> {
+        match v {
+            Value::Array(els) => Ok(Eval::Array(
+                els.into_iter()
+                    .map(Eval::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            v => v
+                .as_f64()
+                .map(Eval::Scalar)
+                .ok_or_else(|| format_err!("Non-numeric value {:?} in expression", v)),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Eval::Scalar(v) => Value::F64(v),
+            Eval::Array(els) => Value::Array(els.into_iter().map(Eval::into_value).collect()),
+        }
+    }
+
+    /// Is this value (or any of its elements) non-zero?
+    fn any(&self) -> bool {
+        match self {
+            Eval::Scalar(v) => *v != 0.0,
+            Eval::Array(els) => els.iter().any(Eval::any),
+        }
+    }
+
+    fn map(self, f: &dyn Fn(f64) -> f64) -> Eval {
+        match self {
+            Eval::Scalar(v) => Eval::Scalar(f(v)),
+            Eval::Array(els) => Eval::Array(els.into_iter().map(|e| e.map(f)).collect()),
+        }
+    }
+
+    /// Combine two values element-wise; scalars are broadcasted
+    fn zip_with(self, other: Eval, f: &dyn Fn(f64, f64) -> f64) -> Result<Eval, Error> {
+        Ok(match (self, other) {
+            (Eval::Scalar(a), Eval::Scalar(b)) => Eval::Scalar(f(a, b)),
+            (Eval::Scalar(a), b) => b.map(&|b| f(a, b)),
+            (a, Eval::Scalar(b)) => a.map(&|a| f(a, b)),
+            (Eval::Array(a), Eval::Array(b)) => {
+                if a.len() != b.len() {
+                    return Err(format_err!(
+                        "Arrays of different lengths ({} and {}) in expression",
+                        a.len(),
+                        b.len()
+                    ));
+                }
+                Eval::Array(
+                    a.into_iter()
+                        .zip(b)
+                        .map(|(a, b)| a.zip_with(b, f))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        })
+    }
+
+    /// Reduce the outermost dimension of an array with `f`. Empty
+    /// arrays reduce to `empty`
+    fn reduce(self, empty: f64, f: &dyn Fn(f64, f64) -> f64) -> Result<Eval, Error> {
+        match self {
+            Eval::Array(els) => {
+                let mut els = els.into_iter();
+                match els.next() {
+                    Some(first) => els.try_fold(first, |acc, e| acc.zip_with(e, f)),
+                    None => Ok(Eval::Scalar(empty)),
+                }
+            }
+            scalar => Ok(scalar),
+        }
+    }
+}
+
+/// A parsed expression which can be evaluated for each entry of a `Tree`
+///
+/// # Example
+/// ```
+/// use futures::TryStreamExt;
+/// use std::path::Path;
+///
+/// use root_io::tree_reader::Formula;
+/// use root_io::RootFile;
+///
+/// #[tokio::main]
+///# async fn main
+///
+///# () {
+///     let path = Path::new("./src/test_data/simple.root");
+///     let f = RootFile::new(path).await.expect("Failed to open file");
+///     let tree = f.get("tree").unwrap().as_tree().await.unwrap();
+///     let cut: Formula = "one > 2 && two < 4".parse().unwrap();
+///     let passed: Vec<bool> = cut.cut(&tree).await.unwrap().try_collect().await.unwrap();
+///     assert_eq!(passed, vec![false, false, true, false]);
+///# }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl FromStr for Formula {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Ok(Formula {
+            expr: Expr::parse(s)?,
+        })
+    }
+}
+
+impl Formula {
+    /// The identifiers (branch names or aliases) used in this expression
+    pub fn identifiers(&self) -> Vec<&str> {
+        let mut out = vec![];
+        self.expr.identifiers(&mut out);
+        out
+    }
+
+    /// Evaluate this expression for each entry of `tree`. Scalar
+    /// results are returned as `Value::F64`, arrays as `Value::Array`
+    pub async fn evaluate(
+        &self,
+        tree: &Tree,
+    ) -> Result<impl Stream<Item = Result<Value, Error>>, Error> {
+        let expr = self.expr.clone().expand_aliases(tree, 0)?;
+        let mut idents = vec![];
+        expr.identifiers(&mut idents);
+        let branches = idents
+            .iter()
+            .map(|ident| tree.branch_by_formula_name(ident).map(|b| b.name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns: HashMap<_, _> = idents
+            .iter()
+            .enumerate()
+            .map(|(i, ident)| (ident.to_string(), i))
+            .collect();
+        let branches: Vec<_> = branches.iter().map(String::as_str).collect();
+        let rows = tree.select(&branches).await?;
+        // Expressions without branches are still evaluated once per entry
+        let n_entries = tree.entries() as usize;
+        let rows = if branches.is_empty() {
+            stream::iter(std::iter::repeat_with(|| Ok(vec![])).take(n_entries)).left_stream()
+        } else {
+            rows.right_stream()
+        };
+        Ok(rows.map(move |row| {
+            let row = row?
+                .into_iter()
+                .map(Eval::from_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(expr.eval(&columns, &row)?.into_value())
+        }))
+    }
+
+    /// Evaluate this expression as a selection for each entry of
+    /// `tree`. An entry passes if the expression is non-zero. If the
+    /// expression results in an array, the entry passes if any of
+    /// its elements is non-zero.
+    pub async fn cut(&self, tree: &Tree) -> Result<impl Stream<Item = Result<bool, Error>>, Error> {
+        let values = self.evaluate(tree).await?;
+        Ok(values.map(|v| Ok(Eval::from_value(v?)?.any())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(n: f64) -> Box<Expr> {
+        Box::new(Expr::Number(n))
+    }
+
+    #[test]
+    fn precedence() {
+        use BinOp::*;
+        assert_eq!(
+            Expr::parse("1 + 2 * 3 < 4 && !5").unwrap(),
+            Expr::Binary(
+                And,
+                Box::new(Expr::Binary(
+                    Lt,
+                    Box::new(Expr::Binary(
+                        Add,
+                        num(1.0),
+                        Box::new(Expr::Binary(Mul, num(2.0), num(3.0)))
+                    )),
+                    num(4.0)
+                )),
+                Box::new(Expr::Not(num(5.0)))
+            )
+        );
+        assert_eq!(
+            Expr::parse("-a.b[2] - 1e-3").unwrap(),
+            Expr::Binary(
+                Sub,
+                Box::new(Expr::Neg(Box::new(Expr::Index(
+                    Box::new(Expr::Ident("a.b".to_string())),
+                    num(2.0)
+                )))),
+                num(1e-3)
+            )
+        );
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("(1 + 2").is_err());
+        assert!(Expr::parse("foo(1)").is_err());
+        assert!(Expr::parse("pow(1)").is_err());
+    }
+
+    #[test]
+    fn evaluation() {
+        let columns: HashMap<_, _> = vec![("x".to_string(), 0), ("n".to_string(), 1)]
+            .into_iter()
+            .collect();
+        let row = vec![
+            Eval::Array(vec![Eval::Scalar(1.0), Eval::Scalar(-4.0)]),
+            Eval::Scalar(2.0),
+        ];
+        let eval = |s| Expr::parse(s).unwrap().eval(&columns, &row).unwrap();
+        assert_eq!(eval("Sum$(abs(x))"), Eval::Scalar(5.0));
+        assert_eq!(eval("Length$(x) == n"), Eval::Scalar(1.0));
+        assert_eq!(eval("Max$(x) + TMath::Max(x[1], 3)"), Eval::Scalar(4.0));
+        assert_eq!(
+            eval("x * n > 0"),
+            Eval::Array(vec![Eval::Scalar(1.0), Eval::Scalar(0.0)])
+        );
+        assert!(eval("x[2]").any());
+        assert!(!eval("x[2] > 0").any());
+    }
+}
@@ -0,0 +1,143 @@
+use nom::{
+    combinator::cond,
+    multi::{count, length_value},
+    number::complete::*,
+    IResult,
+};
+
+use crate::core::parsers::*;
+
+/// An index of a `Tree` mapping pairs of (major, minor) values (e.g.
+/// run and event number) to entry numbers. This is the equivalent of
+/// ROOT's `TTreeIndex`. The index is either stored with the tree or
+/// built with `Tree::build_index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeIndex {
+    major_name: String,
+    minor_name: String,
+    /// (major, minor) values sorted in ascending order
+    values: Vec<(i64, i64)>,
+    /// Entry number of each element in `values`
+    entries: Vec<i64>,
+}
+
+impl TreeIndex {
+    /// Create an index from the (major, minor) values of each entry
+    pub(crate) fn new(major_name: &str, minor_name: &str, values: Vec<(i64, i64)>) -> Self {
+        let mut pairs: Vec<_> = values.into_iter().zip(0..).collect();
+        // Stable sort such that duplicates point to the first entry
+        pairs.sort_by_key(|(value, _)| *value);
+        let (values, entries) = pairs.into_iter().unzip();
+        Self {
+            major_name: major_name.to_string(),
+            minor_name: minor_name.to_string(),
+            values,
+            entries,
+        }
+    }
+
+    /// Expression of the major value (e.g. the run number)
+    pub fn major_name(&self) -> &str {
+        &self.major_name
+    }
+
+    /// Expression of the minor value (e.g. the event number)
+    pub fn minor_name(&self) -> &str {
+        &self.minor_name
+    }
+
+    /// Number of entries in this index
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Entry number of the given (major, minor) pair. If several
+    /// entries have the same values, the first one is returned.
+    pub fn get(&self, major: i64, minor: i64) -> Option<i64> {
+        let pos = self.values.partition_point(|v| *v < (major, minor));
+        match self.values.get(pos) {
+            Some(v) if *v == (major, minor) => Some(self.entries[pos]),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `TTreeIndex`
+pub(crate) fn ttreeindex(i: &[u8]) -> IResult<&[u8], TreeIndex> {
+    let (i, ver) = be_u16(i)?;
+    // TVirtualIndex base class
+    let (i, _tvirtualindex) = length_value(checked_byte_count, |i| {
+        let (i, _ver) = be_u16(i)?;
+        length_value(checked_byte_count, tnamed)(i)
+    })(i)?;
+    let (i, major_name) = string(i)?;
+    let (i, minor_name) = string(i)?;
+    let (i, n) = be_i64(i)?;
+    let i64_array = |i| {
+        let (i, is_array) = be_u8(i)?;
+        let (i, vals) = cond(is_array != 0, count(be_i64, n as usize))(i)?;
+        Ok((i, vals.unwrap_or_default()))
+    };
+    let (i, index_values) = i64_array(i)?;
+    let (i, index_values_minor) = cond(ver >= 2, i64_array)(i)?;
+    let (i, entries) = i64_array(i)?;
+    let values = match index_values_minor {
+        Some(minor) => index_values.into_iter().zip(minor).collect(),
+        // Old versions combine both values into one number
+        None => index_values
+            .into_iter()
+            .map(|v| (v >> 31, v & 0x7fff_ffff))
+            .collect(),
+    };
+    Ok((
+        i,
+        TreeIndex {
+            major_name,
+            minor_name,
+            values,
+            entries,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils::Writer;
+
+    #[test]
+    fn tree_index() {
+        let index = TreeIndex::new("run", "event", vec![(2, 1), (1, 5), (1, 3), (1, 5)]);
+        assert_eq!(index.get(1, 3), Some(2));
+        assert_eq!(index.get(1, 5), Some(1));
+        assert_eq!(index.get(2, 1), Some(0));
+        assert_eq!(index.get(2, 2), None);
+    }
+
+    #[test]
+    fn parse_tree_index() {
+        let mut w = Writer::default();
+        w.bytes([0, 2]);
+        // TVirtualIndex holding a TNamed with empty name and title
+        w.with_byte_count(|w| {
+            w.bytes([0, 1]);
+            w.tnamed("", "");
+        });
+        w.string("run");
+        w.string("ev");
+        w.bytes(2i64.to_be_bytes());
+        for arr in &[[1i64, 2], [7, 3], [1, 0]] {
+            w.bytes([1]);
+            arr.iter().for_each(|v| w.bytes(v.to_be_bytes()));
+        }
+        let (_, index) = ttreeindex(&w.buf).unwrap();
+        assert_eq!(index.major_name(), "run");
+        assert_eq!(index.minor_name(), "ev");
+        assert_eq!(index.get(2, 3), Some(0));
+        assert_eq!(index.get(1, 7), Some(1));
+    }
+}
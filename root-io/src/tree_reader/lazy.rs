@@ -0,0 +1,103 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use failure::Error;
+use nom::{
+    error::{make_error, ErrorKind},
+    IResult,
+};
+
+use crate::core::{Context, Raw};
+
+/// Signature of the parsers whose execution may be deferred by `Lazy`
+type RawParser<T> = for<'s> fn(&Raw<'s>, &'s Context) -> IResult<&'s [u8], T>;
+
+/// An object within the buffer of a `Context` which is only parsed
+/// when it is accessed for the first time. The buffer itself is
+/// shared and not copied.
+pub(crate) struct Lazy<T> {
+    context: Context,
+    classinfo: String,
+    range: Range<usize>,
+    parser: RawParser<T>,
+    parsed: OnceLock<T>,
+}
+
+impl<T> Lazy<T> {
+    /// Defer the parsing of `raw` with `parser`. Returns `None` if
+    /// `raw` does not point into the buffer of `context`.
+    pub(crate) fn new(raw: &Raw, context: &Context, parser: RawParser<T>) -> Option<Self> {
+        let range = context.range_of(raw.obj)?;
+        Some(Self {
+            context: context.clone(),
+            classinfo: raw.classinfo.to_string(),
+            range,
+            parser,
+            parsed: OnceLock::new(),
+        })
+    }
+
+    /// The unparsed object
+    pub(crate) fn raw(&self) -> Raw<'_> {
+        Raw {
+            classinfo: &self.classinfo,
+            obj: &self.context.s[self.range.clone()],
+        }
+    }
+
+    /// Has this object been parsed already?
+    #[cfg(test)]
+    pub(crate) fn is_parsed(&self) -> bool {
+        self.parsed().is_some()
+    }
+
+    /// The parsed object if it was accessed before
+    pub(crate) fn parsed(&self) -> Option<&T> {
+        self.parsed.get()
+    }
+
+    /// Get the parsed object; parsing it if this did not happen, yet
+    pub(crate) fn get(&self) -> Result<&T, Error> {
+        if let Some(obj) = self.parsed.get() {
+            return Ok(obj);
+        }
+        let obj = (self.parser)(&self.raw(), &self.context)
+            .map(|(_, obj)| obj)
+            .map_err(|e| {
+                format_err!(
+                    "Failed to parse `{}`: {:?}",
+                    self.classinfo,
+                    e.map(|e| e.code)
+                )
+            })?;
+        Ok(self.parsed.get_or_init(|| obj))
+    }
+}
+
+/// Parser returning a `Lazy` object which defers the actual parsing
+/// of `raw` with `parser` to its first access.
+pub(crate) fn lazy<'s, T>(
+    raw: &Raw<'s>,
+    context: &'s Context,
+    parser: RawParser<T>,
+) -> IResult<&'s [u8], Lazy<T>> {
+    match Lazy::new(raw, context, parser) {
+        Some(obj) => Ok((&[], obj)),
+        None => Err(nom::Err::Failure(make_error(raw.obj, ErrorKind::Verify))),
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.parsed.get() {
+            Some(obj) => obj.fmt(f),
+            None => write!(
+                f,
+                "Unparsed `{}` of {} bytes",
+                self.classinfo,
+                self.range.len()
+            ),
+        }
+    }
+}
@@ -1,15 +1,21 @@
 use std::fmt::Debug;
 
 use nom::{
-    combinator::{map_res, peek, verify},
+    combinator::{map, map_res, peek, verify},
     multi::length_value,
     number::complete::*,
     IResult,
 };
 
+use failure::Error;
 use quote::{Ident, Tokens};
 
-use crate::{code_gen::rust::ToRustType, core::*};
+use crate::{
+    code_gen::rust::ToRustType,
+    core::*,
+    schema::LeafSchema,
+    tree_reader::value::{Value, ValueParser},
+};
 
 /// Parse a bool from a big endian u8
 fn be_bool(i: &[u8]) -> IResult<&[u8], bool> {
@@ -32,6 +38,179 @@ impl TLeaf {
     pub(crate) fn parse_from_raw<'s>(raw: &Raw<'s>, ctxt: &'s Context) -> IResult<&'s [u8], Self> {
         Self::parse(raw.obj, ctxt, raw.classinfo)
     }
+
+    /// The name of this leaf
+    pub(crate) fn name(&self) -> &str {
+        &self.variant.base().tnamed.name
+    }
+
+    /// Number of fixed length elements per entry
+    pub(crate) fn len(&self) -> usize {
+        self.variant.base().flen.max(1) as usize
+    }
+
+    /// Is this leaf a string (`TLeafC`)? The elements of strings are
+    /// not counted by `len`.
+    pub(crate) fn is_string(&self) -> bool {
+        matches!(self.variant, TLeafVariant::TLeafC(_))
+    }
+
+    /// Name of the leaf holding the number of elements of this leaf in
+    /// each entry; `None` if the number of elements is fixed. If the
+    /// counter is not stored explicitly, it is taken from the title
+    /// of the leaf (e.g. `fX[Tracks_]`).
+    pub(crate) fn counter_name(&self) -> Option<String> {
+        let base = self.variant.base();
+        if let Some(counter) = &base.fleafcount {
+            return Some(counter.base().tnamed.name.to_owned());
+        }
+        let title = base.tnamed.title.trim_end();
+        let dim = title.strip_suffix(']')?.rsplit('[').next()?;
+        if dim.is_empty() || dim.chars().all(|c| c.is_ascii_digit()) {
+            None
+        } else {
+            Some(dim.to_string())
+        }
+    }
+
+    /// Is this a `Double32_t` leaf whose title gives a range (e.g.
+    /// `x[0,10,16]`) such that its values are not stored as floats
+    /// but packed into integers or with a truncated mantissa?
+    fn is_packed_double32(&self) -> bool {
+        if !matches!(self.variant, TLeafVariant::TLeafD32(_)) {
+            return false;
+        }
+        let title = &self.variant.base().tnamed.title;
+        let range = title
+            .match_indices('[')
+            .map(|(pos, _)| &title[pos..])
+            .find(|s| s.split(']').next().is_some_and(|r| r.contains(',')));
+        match range.map(|range| PrimitiveID::new(9, range)) {
+            None => false,
+            // Without a range and with at least 15 bits the values are floats
+            Some(Ok(PrimitiveID::KDouble32(min, max, nbits))) => {
+                min < max || min == nbits as f64 + 0.1
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Parser for a single element of this leaf
+    pub(crate) fn element_parser(&self) -> Result<ValueParser, Error> {
+        use TLeafVariant::*;
+        let unsigned = self.variant.base().fisunsigned;
+        if self.is_packed_double32() {
+            return Err(format_err!(
+                "Leaf `{}` of type Double32_t with range `{}` is not supported",
+                self.name(),
+                self.variant.base().tnamed.title
+            ));
+        }
+        Ok(match &self.variant {
+            TLeafO(_) => |i| map(be_bool, Value::Bool)(i),
+            TLeafB(_) if unsigned => |i| map(be_u8, Value::U8)(i),
+            TLeafB(_) => |i| map(be_i8, Value::I8)(i),
+            TLeafS(_) if unsigned => |i| map(be_u16, Value::U16)(i),
+            TLeafS(_) => |i| map(be_i16, Value::I16)(i),
+            TLeafI(_) if unsigned => |i| map(be_u32, Value::U32)(i),
+            TLeafI(_) => |i| map(be_i32, Value::I32)(i),
+            TLeafL(_) if unsigned => |i| map(be_u64, Value::U64)(i),
+            TLeafL(_) => |i| map(be_i64, Value::I64)(i),
+            TLeafF(_) | TLeafD32(_) => |i| map(be_f32, Value::F32)(i),
+            TLeafD(_) => |i| map(be_f64, Value::F64)(i),
+            TLeafC(_) => |i| map(string, Value::String)(i),
+            TLeafElement(l) => l.element_parser()?,
+        })
+    }
+
+    /// The Rust type of a single element of this leaf; `None` if
+    /// reading this leaf is not supported
+    pub(crate) fn element_type(&self) -> Option<&'static str> {
+        use TLeafVariant::*;
+        let unsigned = self.variant.base().fisunsigned;
+        if self.is_packed_double32() {
+            return None;
+        }
+        Some(match &self.variant {
+            TLeafO(_) => "bool",
+            TLeafB(_) => {
+                if unsigned {
+                    "u8"
+                } else {
+                    "i8"
+                }
+            }
+            TLeafS(_) => {
+                if unsigned {
+                    "u16"
+                } else {
+                    "i16"
+                }
+            }
+            TLeafI(_) => {
+                if unsigned {
+                    "u32"
+                } else {
+                    "i32"
+                }
+            }
+            TLeafL(_) => {
+                if unsigned {
+                    "u64"
+                } else {
+                    "i64"
+                }
+            }
+            TLeafF(_) | TLeafD32(_) => "f32",
+            TLeafD(_) => "f64",
+            TLeafC(_) => "String",
+            TLeafElement(l) => return l.element_type(),
+        })
+    }
+
+    /// Description of this leaf in the schema of its tree
+    pub(crate) fn schema(&self) -> LeafSchema {
+        use TLeafVariant::*;
+        let base = self.variant.base();
+        let class = match &self.variant {
+            TLeafB(_) => "TLeafB",
+            TLeafS(_) => "TLeafS",
+            TLeafI(_) => "TLeafI",
+            TLeafL(_) => "TLeafL",
+            TLeafF(_) => "TLeafF",
+            TLeafD(_) => "TLeafD",
+            TLeafC(_) => "TLeafC",
+            TLeafO(_) => "TLeafO",
+            TLeafD32(_) => "TLeafD32",
+            TLeafElement(_) => "TLeafElement",
+        };
+        LeafSchema {
+            name: base.tnamed.name.to_owned(),
+            title: base.tnamed.title.to_owned(),
+            class: class.to_string(),
+            type_name: self.element_type().map(String::from),
+            length: base.flen,
+            counter: self.counter_name(),
+        }
+    }
+}
+
+impl TLeafVariant {
+    fn base(&self) -> &TLeafBase {
+        use TLeafVariant::*;
+        match self {
+            TLeafB(l) => &l.base,
+            TLeafS(l) => &l.base,
+            TLeafI(l) => &l.base,
+            TLeafL(l) => &l.base,
+            TLeafF(l) => &l.base,
+            TLeafD(l) => &l.base,
+            TLeafC(l) => &l.base,
+            TLeafO(l) => &l.base,
+            TLeafD32(l) => &l.base,
+            TLeafElement(l) => &l.base,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -139,6 +318,68 @@ impl TLeafElement {
         let (i, ftype) = map_res(be_i32, |id| TypeID::new(id, "FIXME!"))(i)?;
         Ok((i, Self { base, fid, ftype }))
     }
+
+    /// The Rust type of the values produced by `element_parser`
+    fn element_type(&self) -> Option<&'static str> {
+        use PrimitiveID::*;
+        let id = match &self.ftype {
+            TypeID::Primitive(id) => id,
+            TypeID::InvalidOrCounter(-1) => return Some("u32"),
+            _ => return None,
+        };
+        Some(match id {
+            KChar => "i8",
+            KShort => "i16",
+            KInt | KCounter => "i32",
+            KLong | KLong64 => "i64",
+            KFloat => "f32",
+            KDouble => "f64",
+            KUChar => "u8",
+            KUShort => "u16",
+            KUInt | KBits => "u32",
+            KULong | KULong64 => "u64",
+            KBool => "bool",
+            KCharStar => "String",
+            _ => return None,
+        })
+    }
+
+    fn element_parser(&self) -> Result<ValueParser, Error> {
+        use PrimitiveID::*;
+        let id = match &self.ftype {
+            TypeID::Primitive(id) => id,
+            // Counter of a split `TClonesArray`
+            TypeID::InvalidOrCounter(-1) => return Ok(|i| map(be_u32, Value::U32)(i)),
+            ty => {
+                return Err(format_err!(
+                    "Leaf `{}` of type {:?} is not supported",
+                    self.base.tnamed.name,
+                    ty
+                ))
+            }
+        };
+        Ok(match id {
+            KChar => |i| map(be_i8, Value::I8)(i),
+            KShort => |i| map(be_i16, Value::I16)(i),
+            KInt | KCounter => |i| map(be_i32, Value::I32)(i),
+            KLong | KLong64 => |i| map(be_i64, Value::I64)(i),
+            KFloat => |i| map(be_f32, Value::F32)(i),
+            KDouble => |i| map(be_f64, Value::F64)(i),
+            KUChar => |i| map(be_u8, Value::U8)(i),
+            KUShort => |i| map(be_u16, Value::U16)(i),
+            KUInt | KBits => |i| map(be_u32, Value::U32)(i),
+            KULong | KULong64 => |i| map(be_u64, Value::U64)(i),
+            KBool => |i| map(be_bool, Value::Bool)(i),
+            KCharStar => |i| map(string, Value::String)(i),
+            id => {
+                return Err(format_err!(
+                    "Leaf `{}` of type {:?} is not supported",
+                    self.base.tnamed.name,
+                    id
+                ))
+            }
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -213,18 +454,57 @@ fn arrayfy_maybe(ty_name: &str, len: usize) -> Tokens {
 
 impl ToRustType for TLeaf {
     fn type_name(&self) -> Tokens {
-        use TLeafVariant::*;
-        let (type_name, len) = match &self.variant {
-            TLeafO(l) => ("bool", l.base.flen),
-            TLeafB(l) => (if l.base.fisunsigned { "u8" } else { "i8" }, l.base.flen),
-            TLeafS(l) => (if l.base.fisunsigned { "u16" } else { "i16" }, l.base.flen),
-            TLeafI(l) => (if l.base.fisunsigned { "u32" } else { "i32" }, l.base.flen),
-            TLeafL(l) => (if l.base.fisunsigned { "u64" } else { "i64" }, l.base.flen),
-            TLeafF(l) => ("f32", l.base.flen),
-            TLeafD(l) => ("f64", l.base.flen),
-            TLeafC(l) => ("String", l.base.flen),
-            l => todo!("{:?}", l),
+        let type_name = match self.element_type() {
+            Some(type_name) => type_name,
+            // Packed values cannot be read but are doubles
+            None if self.is_packed_double32() => "f64",
+            None => todo!("{:?}", self.variant),
         };
-        arrayfy_maybe(type_name, len as usize)
+        arrayfy_maybe(type_name, self.variant.base().flen as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double32(title: &str) -> TLeaf {
+        let base = TLeafBase {
+            ver: 2,
+            tnamed: TNamed {
+                name: "x".to_string(),
+                title: title.to_string(),
+            },
+            flen: 1,
+            flentype: 4,
+            foffset: 0,
+            fisrange: false,
+            fisunsigned: false,
+            fleafcount: None,
+        };
+        TLeaf {
+            variant: TLeafVariant::TLeafD32(TLeafD32 {
+                base,
+                fminimum: 0.0,
+                fmaximum: 0.0,
+            }),
+        }
+    }
+
+    #[test]
+    fn double32_ranges() {
+        for title in &["x", "x[3]", "x[n]", "x[0,0]", "x[0,0,20]"] {
+            let leaf = double32(title);
+            assert_eq!(leaf.element_type(), Some("f32"), "{}", title);
+            let (_, value) = leaf.element_parser().unwrap()(&1.5f32.to_be_bytes()).unwrap();
+            assert_eq!(value, Value::F32(1.5));
+        }
+        for title in &["x[0,10,16]", "x[n][0,10]", "x[0,0,10]"] {
+            let leaf = double32(title);
+            assert_eq!(leaf.element_type(), None, "{}", title);
+            assert!(leaf.element_parser().is_err(), "{}", title);
+            assert_eq!(leaf.type_name().to_string(), "f64");
+            assert_eq!(leaf.schema().type_name, None);
+        }
     }
 }
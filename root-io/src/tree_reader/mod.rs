@@ -7,17 +7,36 @@
 
 mod branch;
 mod container;
+mod entry_list;
+mod formula;
+mod index;
+mod lazy;
 mod leafs;
+mod ref_table;
 mod tree;
+mod value;
 
+pub(crate) use self::container::Container;
+pub(crate) use self::entry_list::entry_list;
+
+pub use self::branch::TBranch;
+pub use self::entry_list::EntryList;
+pub use self::formula::Formula;
+pub use self::index::TreeIndex;
+pub use self::ref_table::RefTable;
 pub use self::tree::{ttree, Tree};
+pub use self::value::Value;
 
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use std::path::PathBuf;
     use tokio;
 
-    use crate::core::RootFile;
+    use futures::{StreamExt, TryStreamExt};
+    use nom::number::complete::{be_f32, be_i32};
+
+    use super::{EntryList, Formula, TBranch, Value};
+    use crate::core::{CompressionAlgorithm, RootFile};
 
     #[tokio::test]
     async fn simple_tree() {
@@ -25,6 +44,291 @@ mod tests {
         let f = RootFile::new(path.as_path())
             .await
             .expect("Failed to open file");
-        f.items()[0].as_tree().await.unwrap();
+        f.get("tree").unwrap().as_tree().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn lazy_tree() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let eager = f.get("events").unwrap().as_tree().await.unwrap();
+        let lazy = f.get("events").unwrap().as_lazy_tree().await.unwrap();
+        assert!(lazy.fbranches.iter().all(|b| !b.is_parsed()));
+
+        let branch = lazy.branch_by_name("Jet_Px").unwrap();
+        // Only the requested branch was parsed
+        assert_eq!(lazy.fbranches.iter().filter(|b| b.is_parsed()).count(), 1);
+
+        let lazy_values: Vec<_> = branch.as_fixed_size_iterator(|i| be_f32(i)).collect().await;
+        let eager_values: Vec<_> = eager
+            .branch_by_name("Jet_Px")
+            .unwrap()
+            .as_fixed_size_iterator(|i| be_f32(i))
+            .collect()
+            .await;
+        assert_eq!(lazy_values, eager_values);
+        assert_eq!(
+            lazy.branch_names_and_types().unwrap(),
+            eager.branch_names_and_types().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn select_jagged() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("events").unwrap().as_tree().await.unwrap();
+        let rows: Vec<_> = tree
+            .select(&["NJet", "Jet_Px"])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            rows.len() as i64,
+            tree.branch_by_name("NJet").unwrap().entries()
+        );
+
+        let n_jets: Vec<_> = tree
+            .branch_by_name("NJet")
+            .unwrap()
+            .as_fixed_size_iterator(|i| be_i32(i))
+            .collect()
+            .await;
+        let jet_px: Vec<_> = tree
+            .branch_by_name("Jet_Px")
+            .unwrap()
+            .as_var_size_iterator(|i| be_f32(i), n_jets.iter().map(|&n| n as u32).collect())
+            .collect()
+            .await;
+        for ((row, n), px) in rows.iter().zip(n_jets).zip(jet_px) {
+            let px: Vec<_> = px.into_iter().map(Value::F32).collect();
+            assert_eq!(row, &vec![Value::I32(n), Value::Array(px)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn select_split_counter() {
+        // The counter `GenParticle_` is a leaf of the parent branch
+        let path = PathBuf::from("./src/test_data/mc10events.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("Events").unwrap().as_lazy_tree().await.unwrap();
+        let rows: Vec<_> = tree
+            .select(&["GenParticle.pt"])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows.len() as i64, tree.entries());
+        assert!(rows
+            .iter()
+            .any(|r| matches!(&r[0], Value::Array(a) if !a.is_empty())));
+        // Only the top level branch holding both, the requested branch
+        // and its counter, was parsed
+        assert_eq!(tree.fbranches.iter().filter(|b| b.is_parsed()).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn select_types() {
+        let path = PathBuf::from("./src/test_data/small-flat-tree.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("tree").unwrap().as_tree().await.unwrap();
+        let rows: Vec<_> = tree
+            .select(&["Int32", "Float64", "Str", "ArrayInt32", "SliceInt32"])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 100);
+        for row in rows {
+            assert!(matches!(row[0], Value::I32(_)));
+            assert!(matches!(row[1], Value::F64(_)));
+            assert!(matches!(row[2], Value::String(_)));
+            assert!(matches!(&row[3], Value::Array(a) if a.len() == 10));
+            assert!(matches!(&row[4], Value::Array(_)));
+        }
+        assert!(tree.select(&["Int32", "NoSuchBranch"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn formula() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("events").unwrap().as_tree().await.unwrap();
+        let formula: Formula = "Sum$(Jet_Px * 2) + Length$(Jet_Px) - NJet".parse().unwrap();
+        assert_eq!(formula.identifiers(), vec!["Jet_Px", "NJet"]);
+        let values: Vec<_> = formula
+            .evaluate(&tree)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let rows: Vec<_> = tree
+            .select(&["Jet_Px"])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(values.len(), rows.len());
+        for (value, row) in values.iter().zip(rows) {
+            let expected = match &row[0] {
+                Value::Array(px) => px.iter().map(|v| 2.0 * v.as_f64().unwrap()).sum::<f64>(),
+                _ => panic!("Jet_Px should be jagged"),
+            };
+            assert!((value.as_f64().unwrap() - expected).abs() < 1e-9);
+        }
+
+        let cut: Formula = "abs(Jet_Px[0]) > 20 && NJet > 1".parse().unwrap();
+        let passed: Vec<bool> = cut.cut(&tree).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(passed.len(), values.len());
+        assert!(passed.iter().any(|&p| p) && !passed.iter().all(|&p| p));
+        assert!("NoSuchBranch > 1"
+            .parse::<Formula>()
+            .unwrap()
+            .evaluate(&tree)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn select_entries() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("events").unwrap().as_tree().await.unwrap();
+        let names = ["NJet", "Jet_Px", "EventWeight"];
+        let all: Vec<_> = tree
+            .select(&names)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let last = tree.entries() - 1;
+        let list = EntryList::new("events", vec![last, 5, 0, 1200]);
+        let selected: Vec<_> = tree
+            .select_entries(&names, &list)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let expected: Vec<_> = [0, 5, 1200, last]
+            .iter()
+            .map(|&e| all[e as usize].clone())
+            .collect();
+        assert_eq!(selected, expected);
+
+        let other_tree = EntryList::new("other", vec![0]);
+        assert!(tree.select_entries(&names, &other_tree).await.is_err());
+        let out_of_range = EntryList::new("", vec![last + 1]);
+        assert!(tree.select_entries(&names, &out_of_range).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn select_range() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("events").unwrap().as_tree().await.unwrap();
+        let names = ["NJet", "Jet_Px"];
+        let all: Vec<_> = tree
+            .select(&names)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let selected: Vec<_> = tree
+            .select_range(&names, 10..20)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(selected, all[10..20]);
+        // The range is truncated to the entries of the tree
+        let last = tree.entries();
+        let selected: Vec<_> = tree
+            .select_range(&names, last - 2..last + 10)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(selected, all[all.len() - 2..]);
+    }
+
+    #[tokio::test]
+    async fn sizes() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let tree = f.get("events").unwrap().as_lazy_tree().await.unwrap();
+        let branches = tree.top_level_branches().unwrap();
+        assert_eq!(branches.len(), 51);
+        assert!(branches.iter().all(|b| b.sub_branches().is_empty()));
+        let sum = |bytes: fn(&&TBranch) -> i64| branches.iter().map(bytes).sum::<i64>();
+        // The sizes of the tree are the sums over its branches
+        assert_eq!(sum(|b| b.uncompressed_bytes()), tree.uncompressed_bytes());
+        assert_eq!(sum(|b| b.compressed_bytes()), tree.compressed_bytes());
+        assert!(tree.compressed_bytes() < tree.uncompressed_bytes());
+        assert_eq!(branches[0].baskets(), 1);
+        assert_eq!(branches[0].compression().to_string(), "default-1");
+        assert_eq!(f.compression().algorithm(), CompressionAlgorithm::Global);
+    }
+
+    #[tokio::test]
+    async fn build_index() {
+        let path = PathBuf::from("./src/test_data/HZZ.root");
+        let f = RootFile::new(path.as_path())
+            .await
+            .expect("Failed to open file");
+        let mut tree = f.get("events").unwrap().as_tree().await.unwrap();
+        assert!(tree.index().is_none());
+        assert!(tree.get_entry_with_index(0, 0).is_err());
+
+        tree.build_index("NJet", "NMuon * 100 + NElectron")
+            .await
+            .unwrap();
+        let rows: Vec<_> = tree
+            .select(&["NJet", "NMuon", "NElectron"])
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let mut first_entries = std::collections::HashMap::new();
+        for (entry, row) in rows.iter().enumerate() {
+            let v: Vec<_> = row.iter().map(|v| v.as_i64().unwrap()).collect();
+            first_entries
+                .entry((v[0], v[1] * 100 + v[2]))
+                .or_insert(entry as i64);
+        }
+        for ((major, minor), entry) in first_entries {
+            assert_eq!(
+                tree.get_entry_with_index(major, minor).unwrap(),
+                Some(entry)
+            );
+        }
+        assert_eq!(tree.get_entry_with_index(-1, 0).unwrap(), None);
     }
 }
@@ -0,0 +1,192 @@
+use failure::Error;
+use nom::{
+    combinator::{cond, map},
+    multi::{count, length_data, length_value},
+    number::complete::*,
+    IResult,
+};
+
+use crate::core::parsers::*;
+use crate::core::types::*;
+use crate::core::{ProcessId, Ref};
+use crate::tree_reader::branch::{tbranch, tbranch_name, TBranch};
+
+/// The table of a `Tree` recording in which branch each object
+/// referenced by a `Ref` is stored (ROOT's `TBranchRef` and
+/// `TRefTable`). References are resolved per entry: the referenced
+/// object is found in the same entry of the returned branch.
+///
+/// Only references to objects written to the same tree can be
+/// resolved. Decoding the referenced object itself is left to the
+/// caller.
+#[derive(Debug)]
+pub struct RefTable {
+    /// The branch holding the table of each entry
+    branch: TBranch,
+    /// Names of the branches holding referenced objects
+    parents: Vec<String>,
+    /// GUIDs of the processes in the order used by the table
+    process_guids: Vec<String>,
+}
+
+impl RefTable {
+    /// Names of the branches which may hold referenced objects
+    pub fn parents(&self) -> &[String] {
+        &self.parents
+    }
+
+    /// GUIDs of the processes of the referenced objects
+    pub fn process_guids(&self) -> &[String] {
+        &self.process_guids
+    }
+
+    /// Find the branch holding the object referenced by `reference`
+    /// in the given entry. `process` is the `TProcessID` of the
+    /// reference (see `RootFile::process_id`). Returns `Ok(None)` if
+    /// the object is not stored in this tree.
+    pub async fn resolve(
+        &self,
+        entry: i64,
+        reference: &Ref,
+        process: &ProcessId,
+    ) -> Result<Option<&str>, Error> {
+        // Files written before the GUIDs were recorded only know a single process
+        let process_idx = if self.process_guids.is_empty() {
+            Some(0)
+        } else {
+            self.process_guids.iter().position(|g| g == process.guid())
+        };
+        let process_idx = match process_idx {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        let parent_ids = self.parent_ids(entry).await?;
+        Ok(
+            parent_of(&parent_ids, process_idx, reference.object_number())
+                .and_then(|parent| self.parents.get(parent))
+                .map(String::as_str),
+        )
+    }
+
+    /// The parent IDs of each referenced object in the given entry;
+    /// one array per process
+    async fn parent_ids(&self, entry: i64) -> Result<Vec<Vec<i32>>, Error> {
+        let (container, first) = self
+            .branch
+            .containers_and_first_entries()
+            .take_while(|(_, first)| *first <= entry)
+            .last()
+            .filter(|_| entry < self.branch.entries())
+            .ok_or_else(|| format_err!("Entry {} not found in reference table", entry))?;
+        let (_, buffer) = container.to_owned().raw_data().await?;
+        // Entries have variable sizes; skip those preceding `entry`
+        let (_, tables) =
+            count(ref_table_entry, (entry - first + 1) as usize)(&buffer).map_err(|e| {
+                format_err!(
+                    "Failed to parse reference table of entry {}: {:?}",
+                    entry,
+                    e.map(|e| e.code)
+                )
+            })?;
+        Ok(tables.into_iter().last().unwrap_or_default())
+    }
+}
+
+/// Index of the parent of the given object. Parent IDs are stored
+/// with an offset of one; zero marks unknown objects.
+fn parent_of(parent_ids: &[Vec<i32>], process_idx: usize, object_number: u32) -> Option<usize> {
+    let id = *parent_ids.get(process_idx)?.get(object_number as usize)?;
+    usize::try_from(id - 1).ok()
+}
+
+/// Parse the table of a single entry
+fn ref_table_entry(i: &[u8]) -> IResult<&[u8], Vec<Vec<i32>>> {
+    let (i, first) = be_i32(i)?;
+    if first < 0 {
+        // Number of processes followed by one array per process
+        count(
+            |i| {
+                let (i, n) = be_i32(i)?;
+                count(be_i32, n as usize)(i)
+            },
+            -first as usize,
+        )(i)
+    } else {
+        // Old format with a single process
+        map(count(be_i32, first as usize), |ids| vec![ids])(i)
+    }
+}
+
+/// Parse a `TBranchRef` including its `TRefTable`
+pub(crate) fn tbranchref<'s>(
+    branch_ref: &Raw<'s>,
+    context: &'s Context,
+) -> IResult<&'s [u8], RefTable> {
+    let (i, _ver) = be_u16(branch_ref.obj)?;
+    let (i, branch) = length_value(checked_byte_count, |i| tbranch(i, context))(i)?;
+    let (i, table) = raw(i, context)?;
+
+    let t = table.obj;
+    let (t, ver) = be_u16(t)?;
+    let (t, _tobj) = tobject(t)?;
+    let (t, _fsize) = be_i32(t)?;
+    let (t, parents) = raw(t, context)?;
+    let parents = match parents.classinfo {
+        "" => vec![],
+        _ => {
+            let branch_name = |r: &Raw<'s>, _| match r.classinfo {
+                "" => Ok((r.obj, String::new())),
+                _ => tbranch_name(r),
+            };
+            tobjarray(branch_name, parents.obj, context)?.1
+        }
+    };
+    // The owner is the tree itself; skip it
+    let (t, owner) = classinfo(t)?;
+    let (t, _) = cond(
+        !matches!(owner, ClassInfo::References(_)),
+        length_data(checked_byte_count),
+    )(t)?;
+    let (_, process_guids) = cond(
+        ver >= 3,
+        length_value(checked_byte_count, |i| {
+            let (i, _ver) = be_u16(i)?;
+            let (i, n) = be_i32(i)?;
+            count(string, n as usize)(i)
+        }),
+    )(t)?;
+    Ok((
+        i,
+        RefTable {
+            branch,
+            parents,
+            process_guids: process_guids.unwrap_or_default(),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ref_table_entries() {
+        let mut buf = vec![];
+        [-2i32, 2, 0, 3, 1, 2]
+            .iter()
+            .for_each(|v| buf.extend(&v.to_be_bytes()));
+        let (_, table) = ref_table_entry(&buf).unwrap();
+        assert_eq!(table, vec![vec![0, 3], vec![2]]);
+        assert_eq!(parent_of(&table, 0, 1), Some(2));
+        assert_eq!(parent_of(&table, 0, 0), None);
+        assert_eq!(parent_of(&table, 1, 0), Some(1));
+        assert_eq!(parent_of(&table, 2, 0), None);
+
+        let mut buf = vec![];
+        [2i32, 1, 1]
+            .iter()
+            .for_each(|v| buf.extend(&v.to_be_bytes()));
+        let (_, table) = ref_table_entry(&buf).unwrap();
+        assert_eq!(table, vec![vec![1, 1]]);
+    }
+}
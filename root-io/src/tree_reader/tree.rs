@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 
 use failure::Error;
+use futures::prelude::*;
 use nom::{
     combinator::{cond, peek, verify},
     multi::{count, length_data, length_value},
@@ -12,8 +14,18 @@ use nom::{
 };
 
 use crate::{
-    core::parsers::*, core::types::*, tree_reader::branch::tbranch_hdr,
-    tree_reader::branch::TBranch, tree_reader::leafs::TLeaf,
+    core::parsers::*,
+    core::types::*,
+    core::{ProcessId, Ref},
+    schema::TreeSchema,
+    tree_reader::branch::{tbranch_hdr, tbranch_name, Selection, TBranch},
+    tree_reader::entry_list::EntryList,
+    tree_reader::formula::Formula,
+    tree_reader::index::{ttreeindex, TreeIndex},
+    tree_reader::lazy::{lazy, Lazy},
+    tree_reader::leafs::TLeaf,
+    tree_reader::ref_table::{tbranchref, RefTable},
+    tree_reader::value::Value,
 };
 
 /// `TTree` potentially has members with very large `Vec<u8>` buffers
@@ -69,53 +81,141 @@ pub struct Tree {
     fmaxentryloop: i64,
     /// Number of entries to estimate histogram limits
     festimate: i64,
-    /// List of Branches
-    pub(crate) fbranches: Vec<TBranch>,
-    /// Direct pointers to individual branch leaves
-    pub(crate) fleaves: Vec<TLeaf>,
+    /// List of Branches; parsed on first access
+    pub(crate) fbranches: Vec<Lazy<TBranch>>,
+    /// Direct pointers to individual branch leaves; parsed on first access
+    pub(crate) fleaves: Vec<Lazy<TLeaf>>,
     /// List of aliases for expressions based on the tree branches.
-    faliases: Option<Vec<u8>>,
+    faliases: Vec<TNamed>,
     /// Sorted index values
     findexvalues: Vec<f64>,
     /// Index of sorted values
     findex: Vec<i32>,
     /// Pointer to the tree Index (if any)
-    ftreeindex: Option<Pointer>,
+    ftreeindex: Option<TreeIndex>,
     /// pointer to list of friend elements
     ffriends: Option<Pointer>,
     /// pointer to a list of user objects associated to this Tree
     fuserinfo: Option<Pointer>,
-    /// Branch supporting the TRefTable (if any)
-    fbranchref: Option<Pointer>,
+    /// Branch supporting the TRefTable (if any); parsed on first access
+    fbranchref: Option<Lazy<RefTable>>,
 }
 
 impl Tree {
-    /// Get all branches of a tree (including nested ones)
-    pub(crate) fn branches(&self) -> Vec<&TBranch> {
-        self.fbranches
+    /// The name of this tree
+    pub fn name(&self) -> &str {
+        &self.tnamed.name
+    }
+
+    /// Number of entries in this tree
+    pub fn entries(&self) -> i64 {
+        self.fentries
+    }
+
+    /// Total size of all branches of this tree before compression
+    pub fn uncompressed_bytes(&self) -> i64 {
+        self.ftotbytes
+    }
+
+    /// Total size of all branches of this tree after compression
+    pub fn compressed_bytes(&self) -> i64 {
+        self.fzipbytes
+    }
+
+    /// The top level branches of this tree; parsing them if necessary.
+    /// Nested branches are accessible through `TBranch::sub_branches`.
+    pub fn top_level_branches(&self) -> Result<Vec<&TBranch>, Error> {
+        self.fbranches.iter().map(|b| b.get()).collect()
+    }
+
+    /// The aliases defined for this tree as pairs of names and the
+    /// expressions they stand for (see `Formula`)
+    pub fn aliases(&self) -> Vec<(&str, &str)> {
+        self.faliases
             .iter()
-            .flat_map(|b| vec![b].into_iter().chain(b.branches().into_iter()))
+            .map(|a| (a.name.as_str(), a.title.as_str()))
             .collect()
     }
-    /// Get all the branch names and types (including nested ones) of this tree
-    /// The first element is the name, the second one is the type
-    pub fn branch_names_and_types(&self) -> Vec<(String, Vec<String>)> {
-        self.fbranches
+
+    /// Parse all branches and leaves of this tree which were not
+    /// accessed so far.
+    pub(crate) fn parse_all(&self) -> Result<(), Error> {
+        for b in &self.fbranches {
+            b.get()?;
+        }
+        for l in &self.fleaves {
+            l.get()?;
+        }
+        Ok(())
+    }
+
+    /// Get all branches of a tree (including nested ones); parsing them if necessary
+    pub(crate) fn branches(&self) -> Result<Vec<&TBranch>, Error> {
+        let mut branches = vec![];
+        for b in &self.fbranches {
+            let b = b.get()?;
+            branches.push(b);
+            branches.extend(b.branches());
+        }
+        Ok(branches)
+    }
+
+    /// Description of the layout of this tree which is stored with
+    /// the given cycle
+    pub(crate) fn schema(&self, cycle: i16) -> Result<TreeSchema, Error> {
+        let branches = self
+            .fbranches
             .iter()
-            .flat_map(|b| vec![b].into_iter().chain(b.branches().into_iter()))
+            .map(|b| b.get().map(|b| b.schema()))
+            .collect::<Result<_, _>>()?;
+        Ok(TreeSchema {
+            name: self.name().to_string(),
+            cycle,
+            entries: self.fentries,
+            branches,
+        })
+    }
+
+    /// Get all the branch names and types (including nested ones) of this tree
+    /// The first element is the name, the second one is the type.
+    /// Fails if a branch of a lazily read tree cannot be parsed.
+    pub fn branch_names_and_types(&self) -> Result<Vec<(String, Vec<String>)>, Error> {
+        Ok(self
+            .branches()?
+            .into_iter()
             .map(|b| (b.name(), b.element_types()))
-            .collect()
+            .collect())
     }
 
     pub fn branch_by_name(&self, name: &str) -> Result<&TBranch, Error> {
-        self.branches()
-            .into_iter()
+        // Nested branches are usually prefixed with the name of their
+        // top level branch. Only parse those top level branches
+        // first so that we don't have to parse the entire tree.
+        let candidates = self.fbranches.iter().filter(|b| {
+            tbranch_name(&b.raw())
+                .map(|(_, top_name)| name.starts_with(&top_name))
+                .unwrap_or(false)
+        });
+        for b in candidates {
+            let b = b.get()?;
+            if let Some(found) = Some(b)
+                .into_iter()
+                .chain(b.branches())
+                .find(|b| b.name == name)
+            {
+                return Ok(found);
+            }
+        }
+        let branches = self.branches()?;
+        branches
+            .iter()
             .find(|b| b.name == name)
+            .copied()
             .ok_or_else(|| {
                 format_err!(
                     "Branch {} not found in tree: \n {:#?}",
                     name,
-                    self.branches()
+                    branches
                         .iter()
                         .map(|b| b.name.to_owned())
                         .collect::<Vec<_>>()
@@ -124,7 +224,320 @@ impl Tree {
     }
 }
 
-/// Parse a `Tree` from the given buffer. Usually used through `FileItem::parse_with`.
+impl Tree {
+    /// The index of this tree, if one was stored with it or built
+    /// with `build_index`
+    pub fn index(&self) -> Option<&TreeIndex> {
+        self.ftreeindex.as_ref()
+    }
+
+    /// Build an index of this tree from the values of the `major` and
+    /// `minor` expressions (see `Formula`) in each entry; typically
+    /// the run and event number. Floating point values are
+    /// truncated. Use `"0"` as `minor` if only one value is needed.
+    /// A previously existing index is replaced.
+    pub async fn build_index(&mut self, major: &str, minor: &str) -> Result<(), Error> {
+        let majors = self.index_values(major).await?;
+        let minors = self.index_values(minor).await?;
+        let index = TreeIndex::new(major, minor, majors.into_iter().zip(minors).collect());
+        self.ftreeindex = Some(index);
+        Ok(())
+    }
+
+    /// Evaluate `expr` for each entry as the value of an index
+    async fn index_values(&self, expr: &str) -> Result<Vec<i64>, Error> {
+        let formula: Formula = expr.parse()?;
+        formula
+            .evaluate(self)
+            .await?
+            .map(|v| match v? {
+                Value::F64(v) => Ok(v as i64),
+                _ => Err(format_err!("Index expression `{}` is not a scalar", expr)),
+            })
+            .try_collect()
+            .await
+    }
+
+    /// Find the entry number with the given `major` and `minor` values
+    /// (e.g. run and event number) using the index of this tree. See
+    /// `build_index` if the tree does not have an index. Returns
+    /// `Ok(None)` if no such entry exists.
+    pub fn get_entry_with_index(&self, major: i64, minor: i64) -> Result<Option<i64>, Error> {
+        let index = self.index().ok_or_else(|| {
+            format_err!(
+                "Tree `{}` has no index; build one with `build_index`",
+                self.name()
+            )
+        })?;
+        Ok(index.get(major, minor))
+    }
+
+    /// The table used to resolve references (`Ref`s) to objects
+    /// stored in this tree; `None` if the tree has no such table
+    pub fn ref_table(&self) -> Result<Option<&RefTable>, Error> {
+        self.fbranchref.as_ref().map(Lazy::get).transpose()
+    }
+
+    /// Find the branch holding the object referenced by `reference`
+    /// in the given entry; see `RefTable::resolve`
+    pub async fn resolve_ref(
+        &self,
+        entry: i64,
+        reference: &Ref,
+        process: &ProcessId,
+    ) -> Result<Option<&str>, Error> {
+        let table = self
+            .ref_table()?
+            .ok_or_else(|| format_err!("Tree `{}` has no reference table", self.name()))?;
+        table.resolve(entry, reference, process).await
+    }
+
+    /// Find a branch by its name as used in a `Formula`. Dimensions
+    /// in branch names (e.g. the `[3]` in `fPosition[3]`) may be omitted.
+    pub(crate) fn branch_by_formula_name(&self, name: &str) -> Result<&TBranch, Error> {
+        let strip_dims = |name: &str| name.split('[').next().unwrap_or(name).to_owned();
+        self.branch_by_name(name).or_else(|e| {
+            self.branches()?
+                .into_iter()
+                .find(|b| strip_dims(&b.name) == name)
+                .ok_or(e)
+        })
+    }
+
+    /// Find the branch holding the counter leaf `leaf` of `branch`.
+    /// The counter is either a leaf of a parent of `branch` or of one
+    /// of their direct sub-branches (e.g. `Tracks_` of `Tracks.fX`),
+    /// or of a top level branch named after it (e.g. `NJet` of
+    /// `Jet_Px`). Only the top level branches of those candidates are
+    /// parsed.
+    fn counter_branch(&self, branch: &TBranch, leaf: &str) -> Result<&TBranch, Error> {
+        // The top level branch of `branch` has been parsed already
+        let parents = self
+            .fbranches
+            .iter()
+            .filter_map(|b| b.parsed())
+            .find_map(|b| path_to(b, branch))
+            .unwrap_or_default();
+        let found = parents
+            .iter()
+            .rev()
+            .flat_map(|&p| Some(p).into_iter().chain(p.sub_branches()))
+            .find(|b| b.has_leaf(leaf));
+        if let Some(found) = found {
+            return Ok(found);
+        }
+        let candidates = self.fbranches.iter().filter(|b| {
+            tbranch_name(&b.raw())
+                .map(|(_, top_name)| leaf.starts_with(&top_name))
+                .unwrap_or(false)
+        });
+        for b in candidates {
+            if let Some(found) = leaf_owner(b.get()?, leaf) {
+                return Ok(found);
+            }
+        }
+        Err(format_err!(
+            "Counter `{}` of branch `{}` not found in tree",
+            leaf,
+            branch.name
+        ))
+    }
+
+    /// Select the given branches and iterate over their entries
+    /// together. Each item of the returned stream is a row containing
+    /// one `Value` per requested branch (in the given order). The
+    /// types of the values are derived from the leaves of each branch.
+    ///
+    /// All branches must have the same number of entries. Branches
+    /// with a variable number of elements per entry (e.g.
+    /// `Tracks.fX`) are read together with their counter branch
+    /// which is found automatically; an error is returned if the
+    /// counter does not match the branch.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::TryStreamExt;
+    /// use std::path::Path;
+    ///
+    /// use root_io::tree_reader::Value;
+    /// use root_io::RootFile;
+    ///
+    /// #[tokio::main]
+    ///# async fn main
+    ///
+    ///# () {
+    ///     let path = Path::new("./src/test_data/simple.root");
+    ///     let f = RootFile::new(path).await.expect("Failed to open file");
+    ///     let tree = f.get("tree").unwrap().as_tree().await.unwrap();
+    ///     let rows: Vec<Vec<Value>> = tree
+    ///         .select(&["one", "three"])
+    ///         .await
+    ///         .unwrap()
+    ///         .try_collect()
+    ///         .await
+    ///         .unwrap();
+    ///     assert_eq!(rows[0], vec![Value::I32(1), Value::String("uno".to_string())]);
+    ///# }
+    /// ```
+    pub async fn select(
+        &self,
+        names: &[&str],
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, Error>>, Error> {
+        self.select_impl(names, None).await
+    }
+
+    /// Like `select` but only the entries selected by `entry_list`
+    /// are returned. Baskets without any selected entries are not
+    /// read. If `entry_list` spans several trees, the sub-list
+    /// matching the name of this tree is used. The sub-lists of a list
+    /// created for a `TChain` usually apply to trees of the same name;
+    /// pick the one of the file of this tree with `EntryList::for_tree`
+    /// first.
+    pub async fn select_entries(
+        &self,
+        names: &[&str],
+        entry_list: &EntryList,
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, Error>>, Error> {
+        let list = entry_list.for_tree(self.name(), None).ok_or_else(|| {
+            format_err!(
+                "Entry list `{}` does not apply to tree `{}`",
+                entry_list.name(),
+                self.name()
+            )
+        })?;
+        if let Some(&last) = list.entries().last() {
+            if last >= self.entries() {
+                return Err(format_err!(
+                    "Entry list selects entry {} but tree `{}` has only {} entries",
+                    last,
+                    self.name(),
+                    self.entries()
+                ));
+            }
+        }
+        self.select_impl(names, Some(Selection::Entries(list.entries())))
+            .await
+    }
+
+    /// Like `select` but only the entries in the given range are
+    /// returned. The range is truncated to the entries of this tree.
+    /// Baskets outside of the range are not read.
+    pub async fn select_range(
+        &self,
+        names: &[&str],
+        entries: Range<i64>,
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, Error>>, Error> {
+        let entries = entries.start.max(0)..entries.end.min(self.entries());
+        self.select_impl(names, Some(Selection::Range(entries)))
+            .await
+    }
+
+    async fn select_impl(
+        &self,
+        names: &[&str],
+        selection: Option<Selection<'_>>,
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, Error>>, Error> {
+        let branches = names
+            .iter()
+            .map(|name| self.branch_by_name(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = branches.first() {
+            if let Some(b) = branches.iter().find(|b| b.entries() != first.entries()) {
+                return Err(format_err!(
+                    "Branch `{}` has {} entries but branch `{}` has {}",
+                    b.name,
+                    b.entries(),
+                    first.name,
+                    first.entries()
+                ));
+            }
+        }
+        // Counters may be shared by several branches
+        let mut counters: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut streams = Vec::with_capacity(branches.len());
+        for b in branches {
+            let counts = match b.counter_name() {
+                Some(leaf) => {
+                    if !counters.contains_key(&leaf) {
+                        let counter = self.counter_branch(b, &leaf)?;
+                        if counter.entries() != b.entries() {
+                            return Err(format_err!(
+                                "Branch `{}` has {} entries but its counter `{}` has {}",
+                                b.name,
+                                b.entries(),
+                                counter.name,
+                                counter.entries()
+                            ));
+                        }
+                        let counts = counter
+                            .as_value_stream(None, None)?
+                            .map(|v| {
+                                v?.as_i64()
+                                    .filter(|&n| n >= 0)
+                                    .map(|n| n as u32)
+                                    .ok_or_else(|| {
+                                        format_err!("Invalid counter `{}`", counter.name)
+                                    })
+                            })
+                            .try_collect()
+                            .await?;
+                        counters.insert(leaf.clone(), counts);
+                    }
+                    Some(counters[&leaf].clone())
+                }
+                None => None,
+            };
+            streams.push(Box::pin(b.as_value_stream(counts, selection.clone())?));
+        }
+        let rows = stream::unfold(streams, |mut streams| async move {
+            let mut row = Vec::with_capacity(streams.len());
+            for s in streams.iter_mut() {
+                row.push(s.next().await);
+            }
+            if row.iter().all(Option::is_none) {
+                return None;
+            }
+            let row = row
+                .into_iter()
+                .map(|v| {
+                    v.unwrap_or_else(|| {
+                        Err(format_err!(
+                            "Selected branches ended after different numbers of entries"
+                        ))
+                    })
+                })
+                .collect();
+            Some((row, streams))
+        });
+        Ok(rows)
+    }
+}
+
+/// The branches from `top` down to `target`, excluding `target`
+/// itself; `None` if `target` is not a sub-branch of `top`
+fn path_to<'a>(top: &'a TBranch, target: &TBranch) -> Option<Vec<&'a TBranch>> {
+    if std::ptr::eq(top, target) {
+        return Some(vec![]);
+    }
+    top.sub_branches().iter().find_map(|b| {
+        let mut path = path_to(b, target)?;
+        path.insert(0, top);
+        Some(path)
+    })
+}
+
+/// The branch (`top` or one of its nested branches) holding the
+/// leaf of the given name
+fn leaf_owner<'a>(top: &'a TBranch, leaf: &str) -> Option<&'a TBranch> {
+    if top.has_leaf(leaf) {
+        return Some(top);
+    }
+    top.sub_branches().iter().find_map(|b| leaf_owner(b, leaf))
+}
+
+/// Parse a `Tree` from the given buffer. Usually used through
+/// `FileItem::as_tree`. The branches and leaves of the tree are not
+/// parsed until they are accessed.
 pub fn ttree<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], Tree> {
     let _curried_raw = |i| raw(i, context);
     let none_or_u8_buf = |i: &'s [u8]| match peek(be_u32)(i)? {
@@ -175,23 +588,51 @@ pub fn ttree<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], Tree> {
             (i, None)
         }
     };
-    let (i, fbranches) =
-        length_value(checked_byte_count, |i| tobjarray(tbranch_hdr, i, context))(i)?;
+    let (i, fbranches) = length_value(checked_byte_count, |i| {
+        tobjarray(|r, ctx| lazy(r, ctx, tbranch_hdr), i, context)
+    })(i)?;
     let (i, fleaves) = length_value(checked_byte_count, |i| {
-        tobjarray(TLeaf::parse_from_raw, i, context)
+        tobjarray(|r, ctx| lazy(r, ctx, TLeaf::parse_from_raw), i, context)
     })(i)?;
 
-    let (i, faliases) = none_or_u8_buf(i)?;
+    let (i, faliases) = match peek(be_u32)(i)? {
+        (i, 0) => be_u32(i).map(|(i, _)| (i, vec![]))?,
+        (i, _) => {
+            let (i, r) = raw(i, context)?;
+            let (_, aliases) = tlist(r.obj, context)?;
+            let aliases = aliases
+                .iter()
+                .map(|a| crate::core::parsers::tnamed(a.obj).map(|(_, named)| named))
+                .collect::<Result<_, _>>()?;
+            (i, aliases)
+        }
+    };
     let (i, findexvalues) = tarray(be_f64, i)?;
     let (i, findex) = tarray(be_i32, i)?;
-    let (i, ftreeindex) = none_or_u8_buf(i)?;
+    let (i, ftreeindex) = match peek(be_u32)(i)? {
+        (i, 0) => be_u32(i).map(|(i, _)| (i, None))?,
+        (i, _) => {
+            let (i, r) = raw(i, context)?;
+            // Other implementations of `TVirtualIndex` (e.g. `TChainIndex`) are not supported
+            let index = match r.classinfo {
+                "TTreeIndex" => Some(ttreeindex(r.obj)?.1),
+                _ => None,
+            };
+            (i, index)
+        }
+    };
     let (i, ffriends) = none_or_u8_buf(i)?;
     let (i, fuserinfo) = none_or_u8_buf(i)?;
-    let (i, fbranchref) = none_or_u8_buf(i)?;
-    let ftreeindex = ftreeindex.map(Pointer);
+    let (i, fbranchref) = match peek(be_u32)(i)? {
+        (i, 0) => be_u32(i).map(|(i, _)| (i, None))?,
+        (i, _) => {
+            let (i, r) = raw(i, context)?;
+            let (_, table) = lazy(&r, context, tbranchref)?;
+            (i, Some(table))
+        }
+    };
     let ffriends = ffriends.map(Pointer);
     let fuserinfo = fuserinfo.map(Pointer);
-    let fbranchref = fbranchref.map(Pointer);
     Ok((
         i,
         Tree {
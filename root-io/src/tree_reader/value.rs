@@ -0,0 +1,152 @@
+use std::fmt;
+
+use failure::Error;
+use nom::{multi::count, IResult};
+use serde::Serialize;
+
+use crate::tree_reader::leafs::TLeaf;
+
+/// Parser for a single element of a `TLeaf`
+pub(crate) type ValueParser = fn(&[u8]) -> IResult<&[u8], Value>;
+
+/// A single value of an entry of a `TBranch`. The type is determined
+/// at runtime from the `TLeaf`s of the branch. Values are serialized
+/// as plain numbers, strings and lists.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    I8(i8),
+    U8(u8),
+    I16(i16),
+    U16(u16),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    /// Fixed size arrays, a variable number of elements per entry or
+    /// the values of a branch with several leaves
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// The value as an integer; `None` for floats, strings and arrays
+    pub fn as_i64(&self) -> Option<i64> {
+        use Value::*;
+        Some(match *self {
+            Bool(v) => v as i64,
+            I8(v) => v as i64,
+            U8(v) => v as i64,
+            I16(v) => v as i64,
+            U16(v) => v as i64,
+            I32(v) => v as i64,
+            U32(v) => v as i64,
+            I64(v) => v,
+            U64(v) => v as i64,
+            _ => return None,
+        })
+    }
+
+    /// The value as a float; `None` for strings and arrays
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::F32(v) => Some(v as f64),
+            Value::F64(v) => Some(v),
+            Value::U64(v) => Some(v as f64),
+            _ => self.as_i64().map(|v| v as f64),
+        }
+    }
+}
+
+/// Scalars are written as is and arrays as lists such as `[1, 2, 3]`
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Value::*;
+        match self {
+            Bool(v) => write!(f, "{}", v),
+            I8(v) => write!(f, "{}", v),
+            U8(v) => write!(f, "{}", v),
+            I16(v) => write!(f, "{}", v),
+            U16(v) => write!(f, "{}", v),
+            I32(v) => write!(f, "{}", v),
+            U32(v) => write!(f, "{}", v),
+            I64(v) => write!(f, "{}", v),
+            U64(v) => write!(f, "{}", v),
+            F32(v) => write!(f, "{}", v),
+            F64(v) => write!(f, "{}", v),
+            String(v) => write!(f, "{}", v),
+            Array(vs) => {
+                write!(f, "[")?;
+                for (i, v) in vs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Decodes the entries of a `TBranch` based on the type information
+/// of its leaves
+pub(crate) struct EntryDecoder {
+    /// Parser, fixed number of elements and string-ness of each leaf
+    leaves: Vec<(ValueParser, usize, bool)>,
+    /// Is the number of elements given by a counter branch?
+    jagged: bool,
+}
+
+impl EntryDecoder {
+    pub(crate) fn new(leaves: &[TLeaf], jagged: bool) -> Result<Self, Error> {
+        if jagged && leaves.len() != 1 {
+            return Err(format_err!(
+                "Variable size branches with {} leaves are not supported",
+                leaves.len()
+            ));
+        }
+        let leaves = leaves
+            .iter()
+            .map(|l| Ok((l.element_parser()?, l.len(), l.is_string())))
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { leaves, jagged })
+    }
+
+    /// Decode one entry. `n` is the number of elements in this entry
+    /// as given by the counter branch; it must be `Some` for jagged
+    /// branches.
+    pub(crate) fn decode<'s>(&self, i: &'s [u8], n: Option<usize>) -> IResult<&'s [u8], Value> {
+        let mut i = i;
+        let mut values = Vec::with_capacity(self.leaves.len());
+        for &(parser, len, is_string) in &self.leaves {
+            let (rest, value) = if is_string {
+                parser(i)?
+            } else if self.jagged {
+                let (rest, els) = count(parser, n.unwrap_or(0) * len)(i)?;
+                let value = if len == 1 {
+                    Value::Array(els)
+                } else {
+                    Value::Array(els.chunks(len).map(|c| Value::Array(c.to_vec())).collect())
+                };
+                (rest, value)
+            } else if len > 1 {
+                let (rest, els) = count(parser, len)(i)?;
+                (rest, Value::Array(els))
+            } else {
+                parser(i)?
+            };
+            i = rest;
+            values.push(value);
+        }
+        let value = if values.len() == 1 {
+            values.pop().unwrap()
+        } else {
+            Value::Array(values)
+        };
+        Ok((i, value))
+    }
+}
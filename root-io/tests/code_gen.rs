@@ -0,0 +1,96 @@
+#![cfg(all(test, not(target_arch = "wasm32")))]
+
+use std::path::Path;
+use std::process::Command;
+
+use root_io::RootFile;
+
+/// Code generated from `simple.root`
+#[allow(dead_code)]
+mod simple {
+    include!("code_gen/simple.rs");
+}
+
+/// Generate and format the code for the streamer info of the given file
+async fn generate(f: &RootFile) -> String {
+    let mut s = String::new();
+    f.streamer_info_as_rust(&mut s).await.unwrap();
+    prettyplease::unparse(&syn::parse_file(&s).unwrap())
+}
+
+/// Check that `code` compiles by building it in a scratch crate
+fn assert_compiles(name: &str, code: &str) {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("code-gen-{}", name));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        r#"[package]
+name = "code-gen-{}"
+version = "0.0.0"
+edition = "2021"
+
+[dependencies]
+nom = "7"
+root-io = {{ path = {:?} }}
+
+[workspace]
+"#,
+        name,
+        env!("CARGO_MANIFEST_DIR")
+    );
+    std::fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), code).unwrap();
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let output = Command::new(cargo)
+        .arg("check")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Generated code for {} does not compile:\n{}",
+        name,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[tokio::test]
+async fn generated_code_is_up_to_date() {
+    let f = RootFile::new(Path::new("./src/test_data/simple.root"))
+        .await
+        .unwrap();
+    let code = generate(&f).await;
+    assert_eq!(code, include_str!("code_gen/simple.rs"));
+}
+
+#[tokio::test]
+async fn parse_with_generated_code() {
+    let f = RootFile::new(Path::new("./src/test_data/simple.root"))
+        .await
+        .unwrap();
+    let tree = f
+        .get("tree")
+        .unwrap()
+        .parse_with(simple::ttree)
+        .await
+        .unwrap();
+    assert_eq!(tree.fentries, 4);
+    assert_eq!(tree.tnamed.name, "tree");
+    let branches = tree.fbranches.items().unwrap();
+    assert_eq!(branches.len(), 3);
+}
+
+#[tokio::test]
+async fn generated_esd_code_compiles() {
+    let path = alice_open_data::test_file().unwrap();
+    let f = RootFile::new(path.as_path()).await.unwrap();
+    assert_compiles("esd", &generate(&f).await);
+}
+
+#[tokio::test]
+async fn generated_code_compiles() {
+    // User classes with nested objects and variable sized arrays
+    let f = RootFile::new(Path::new("./src/test_data/mc10events.root"))
+        .await
+        .unwrap();
+    assert_compiles("mc10events", &generate(&f).await);
+}
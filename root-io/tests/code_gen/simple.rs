@@ -0,0 +1,568 @@
+#[allow(unused_imports)]
+use nom::{
+    bytes::complete::*, combinator::*, multi::*, number::complete::*, sequence::*,
+    IResult,
+};
+#[allow(unused_imports)]
+use root_io::core::{class_object, object_pointer, parsers::*, types::*, Object};
+#[derive(Debug, Clone)]
+pub struct TTree {
+    /// Version of the read layout
+    pub ver: u16,
+    /// The basis for a named object (name, title)
+    pub tnamed: TNamed,
+    /// Line attributes
+    pub tattline: TAttLine,
+    /// Fill area attributes
+    pub tattfill: TAttFill,
+    /// Marker attributes
+    pub tattmarker: TAttMarker,
+    /// Number of entries
+    pub fentries: i64,
+    /// Total number of bytes in all branches before compression
+    pub ftotbytes: i64,
+    /// Total number of bytes in all branches after compression
+    pub fzipbytes: i64,
+    /// Number of autosaved bytes
+    pub fsavedbytes: i64,
+    /// Number of autoflushed bytes
+    pub fflushedbytes: i64,
+    /// Tree weight (see TTree::SetWeight)
+    pub fweight: f64,
+    /// Timer interval in milliseconds
+    pub ftimerinterval: i32,
+    /// Number of runs before prompting in Scan
+    pub fscanfield: i32,
+    /// Update frequency for EntryLoop
+    pub fupdate: i32,
+    /// Initial Length of fEntryOffset table in the basket buffers
+    pub fdefaultentryoffsetlen: i32,
+    /// Number of Cluster range in addition to the one defined by 'AutoFlush'
+    pub fnclusterrange: i32,
+    /// Maximum number of entries in case of circular buffers
+    pub fmaxentries: i64,
+    /// Maximum number of entries to process
+    pub fmaxentryloop: i64,
+    /// Maximum total size of buffers kept in memory
+    pub fmaxvirtualsize: i64,
+    /// Autosave tree when fAutoSave entries written or -fAutoSave (compressed) bytes produced
+    pub fautosave: i64,
+    /// Autoflush tree when fAutoFlush entries written or -fAutoFlush (compressed) bytes produced
+    pub fautoflush: i64,
+    /// Number of entries to estimate histogram limits
+    pub festimate: i64,
+    /// [fNClusterRange] Last entry of a cluster range.
+    pub fclusterrangeend: Vec<i64>,
+    /// [fNClusterRange] Number of entries in each cluster for a given range.
+    pub fclustersize: Vec<i64>,
+    /// List of Branches
+    pub fbranches: Object,
+    /// Direct pointers to individual branch leaves
+    pub fleaves: Object,
+    /// List of aliases for expressions based on the tree branches.
+    pub faliases: Object,
+    /// Sorted index values
+    pub findexvalues: Vec<f64>,
+    /// Index of sorted values
+    pub findex: Vec<i32>,
+    /// Pointer to the tree Index (if any)
+    pub ftreeindex: Object,
+    /// pointer to list of friend elements
+    pub ffriends: Object,
+    /// pointer to a list of user objects associated to this Tree
+    pub fuserinfo: Object,
+    /// Branch supporting the TRefTable (if any)
+    pub fbranchref: Object,
+}
+#[derive(Debug, Clone)]
+pub struct TAttLine {
+    /// Version of the read layout
+    pub ver: u16,
+    /// line color
+    pub flinecolor: i16,
+    /// line style
+    pub flinestyle: i16,
+    /// line width
+    pub flinewidth: i16,
+}
+#[derive(Debug, Clone)]
+pub struct TAttFill {
+    /// Version of the read layout
+    pub ver: u16,
+    /// fill area color
+    pub ffillcolor: i16,
+    /// fill area style
+    pub ffillstyle: i16,
+}
+#[derive(Debug, Clone)]
+pub struct TAttMarker {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Marker color index
+    pub fmarkercolor: i16,
+    /// Marker style
+    pub fmarkerstyle: i16,
+    /// Marker size
+    pub fmarkersize: f32,
+}
+#[derive(Debug, Clone)]
+pub struct TBranch {
+    /// Version of the read layout
+    pub ver: u16,
+    /// The basis for a named object (name, title)
+    pub tnamed: TNamed,
+    /// Fill area attributes
+    pub tattfill: TAttFill,
+    /// Compression level and algorithm
+    pub fcompress: i32,
+    /// Initial Size of  Basket Buffer
+    pub fbasketsize: i32,
+    /// Initial Length of fEntryOffset table in the basket buffers
+    pub fentryoffsetlen: i32,
+    /// Last basket number written
+    pub fwritebasket: i32,
+    /// Current entry number (last one filled in this branch)
+    pub fentrynumber: i64,
+    /// Offset of this branch
+    pub foffset: i32,
+    /// Maximum number of Baskets so far
+    pub fmaxbaskets: i32,
+    /// Branch split level
+    pub fsplitlevel: i32,
+    /// Number of entries
+    pub fentries: i64,
+    /// Number of the first entry in this branch
+    pub ffirstentry: i64,
+    /// Total number of bytes in all leaves before compression
+    pub ftotbytes: i64,
+    /// Total number of bytes in all leaves after compression
+    pub fzipbytes: i64,
+    /// -> List of Branches of this branch
+    pub fbranches: Object,
+    /// -> List of leaves of this branch
+    pub fleaves: Object,
+    /// -> List of baskets of this branch
+    pub fbaskets: Object,
+    /// [fMaxBaskets] Length of baskets on file
+    pub fbasketbytes: Vec<i32>,
+    /// [fMaxBaskets] Table of first entry in each basket
+    pub fbasketentry: Vec<i64>,
+    /// [fMaxBaskets] Addresses of baskets on file
+    pub fbasketseek: Vec<i64>,
+    /// Name of file where buffers are stored ("" if in same file as Tree header)
+    pub ffilename: String,
+}
+#[derive(Debug, Clone)]
+pub struct TLeafF {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Leaf: description of a Branch data type
+    pub tleaf: TLeaf,
+    /// Minimum value if leaf range is specified
+    pub fminimum: f32,
+    /// Maximum value if leaf range is specified
+    pub fmaximum: f32,
+}
+#[derive(Debug, Clone)]
+pub struct TLeaf {
+    /// Version of the read layout
+    pub ver: u16,
+    /// The basis for a named object (name, title)
+    pub tnamed: TNamed,
+    /// Number of fixed length elements
+    pub flen: i32,
+    /// Number of bytes for this data type
+    pub flentype: i32,
+    /// Offset in ClonesArray object (if one)
+    pub foffset: i32,
+    /// (=kTRUE if leaf has a range, kFALSE otherwise)
+    pub fisrange: bool,
+    /// (=kTRUE if unsigned, kFALSE otherwise)
+    pub fisunsigned: bool,
+    /// Pointer to Leaf count if variable length (we do not own the counter)
+    pub fleafcount: Object,
+}
+#[derive(Debug, Clone)]
+pub struct TSeqCollection {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Collection abstract base class
+    pub tcollection: TCollection,
+}
+#[derive(Debug, Clone)]
+pub struct TCollection {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Basic ROOT object
+    pub tobject: TObject,
+    /// name of the collection
+    pub fname: String,
+    /// number of elements in collection
+    pub fsize: i32,
+}
+#[derive(Debug, Clone)]
+pub struct TBranchRef {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Branch descriptor
+    pub tbranch: TBranch,
+    /// pointer to the TRefTable
+    pub freftable: Object,
+}
+#[derive(Debug, Clone)]
+pub struct TRefTable {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Basic ROOT object
+    pub tobject: TObject,
+    /// dummy for backward compatibility
+    pub fsize: i32,
+    /// array of Parent objects  (eg TTree branch) holding the referenced objects
+    pub fparents: Object,
+    /// Object owning this TRefTable
+    pub fowner: Object,
+    /// UUIDs of TProcessIDs used in fParentIDs
+    pub fprocessguids: Vec<u8>,
+}
+#[derive(Debug, Clone)]
+pub struct TLeafI {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Leaf: description of a Branch data type
+    pub tleaf: TLeaf,
+    /// Minimum value if leaf range is specified
+    pub fminimum: i32,
+    /// Maximum value if leaf range is specified
+    pub fmaximum: i32,
+}
+#[derive(Debug, Clone)]
+pub struct TLeafC {
+    /// Version of the read layout
+    pub ver: u16,
+    /// Leaf: description of a Branch data type
+    pub tleaf: TLeaf,
+    /// Minimum value if leaf range is specified
+    pub fminimum: i32,
+    /// Maximum value if leaf range is specified
+    pub fmaximum: i32,
+}
+pub fn ttree<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TTree> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    let (i, tattline) = length_value(checked_byte_count, |i| tattline(i, context))(i)?;
+    let (i, tattfill) = length_value(checked_byte_count, |i| tattfill(i, context))(i)?;
+    let (i, tattmarker) = length_value(
+        checked_byte_count,
+        |i| tattmarker(i, context),
+    )(i)?;
+    let (i, fentries) = be_i64(i)?;
+    let (i, ftotbytes) = be_i64(i)?;
+    let (i, fzipbytes) = be_i64(i)?;
+    let (i, fsavedbytes) = be_i64(i)?;
+    let (i, fflushedbytes) = be_i64(i)?;
+    let (i, fweight) = be_f64(i)?;
+    let (i, ftimerinterval) = be_i32(i)?;
+    let (i, fscanfield) = be_i32(i)?;
+    let (i, fupdate) = be_i32(i)?;
+    let (i, fdefaultentryoffsetlen) = be_i32(i)?;
+    let (i, fnclusterrange) = be_i32(i)?;
+    let (i, fmaxentries) = be_i64(i)?;
+    let (i, fmaxentryloop) = be_i64(i)?;
+    let (i, fmaxvirtualsize) = be_i64(i)?;
+    let (i, fautosave) = be_i64(i)?;
+    let (i, fautoflush) = be_i64(i)?;
+    let (i, festimate) = be_i64(i)?;
+    let (i, fclusterrangeend) = basic_pointer(be_i64, fnclusterrange as usize)(i)?;
+    let (i, fclustersize) = basic_pointer(be_i64, fnclusterrange as usize)(i)?;
+    let (i, fbranches) = length_value(
+        checked_byte_count,
+        |i| class_object("TObjArray", i, context),
+    )(i)?;
+    let (i, fleaves) = length_value(
+        checked_byte_count,
+        |i| class_object("TObjArray", i, context),
+    )(i)?;
+    let (i, faliases) = object_pointer(i, context)?;
+    let (i, findexvalues) = tarray(be_f64, i)?;
+    let (i, findex) = tarray(be_i32, i)?;
+    let (i, ftreeindex) = object_pointer(i, context)?;
+    let (i, ffriends) = object_pointer(i, context)?;
+    let (i, fuserinfo) = object_pointer(i, context)?;
+    let (i, fbranchref) = object_pointer(i, context)?;
+    Ok((
+        i,
+        TTree {
+            ver,
+            tnamed,
+            tattline,
+            tattfill,
+            tattmarker,
+            fentries,
+            ftotbytes,
+            fzipbytes,
+            fsavedbytes,
+            fflushedbytes,
+            fweight,
+            ftimerinterval,
+            fscanfield,
+            fupdate,
+            fdefaultentryoffsetlen,
+            fnclusterrange,
+            fmaxentries,
+            fmaxentryloop,
+            fmaxvirtualsize,
+            fautosave,
+            fautoflush,
+            festimate,
+            fclusterrangeend,
+            fclustersize,
+            fbranches,
+            fleaves,
+            faliases,
+            findexvalues,
+            findex,
+            ftreeindex,
+            ffriends,
+            fuserinfo,
+            fbranchref,
+        },
+    ))
+}
+pub fn tattline<'s>(i: &'s [u8], _context: &'s Context) -> IResult<&'s [u8], TAttLine> {
+    let (i, ver) = be_u16(i)?;
+    let (i, flinecolor) = be_i16(i)?;
+    let (i, flinestyle) = be_i16(i)?;
+    let (i, flinewidth) = be_i16(i)?;
+    Ok((
+        i,
+        TAttLine {
+            ver,
+            flinecolor,
+            flinestyle,
+            flinewidth,
+        },
+    ))
+}
+pub fn tattfill<'s>(i: &'s [u8], _context: &'s Context) -> IResult<&'s [u8], TAttFill> {
+    let (i, ver) = be_u16(i)?;
+    let (i, ffillcolor) = be_i16(i)?;
+    let (i, ffillstyle) = be_i16(i)?;
+    Ok((
+        i,
+        TAttFill {
+            ver,
+            ffillcolor,
+            ffillstyle,
+        },
+    ))
+}
+pub fn tattmarker<'s>(
+    i: &'s [u8],
+    _context: &'s Context,
+) -> IResult<&'s [u8], TAttMarker> {
+    let (i, ver) = be_u16(i)?;
+    let (i, fmarkercolor) = be_i16(i)?;
+    let (i, fmarkerstyle) = be_i16(i)?;
+    let (i, fmarkersize) = be_f32(i)?;
+    Ok((
+        i,
+        TAttMarker {
+            ver,
+            fmarkercolor,
+            fmarkerstyle,
+            fmarkersize,
+        },
+    ))
+}
+pub fn tbranch<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TBranch> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    let (i, tattfill) = length_value(checked_byte_count, |i| tattfill(i, context))(i)?;
+    let (i, fcompress) = be_i32(i)?;
+    let (i, fbasketsize) = be_i32(i)?;
+    let (i, fentryoffsetlen) = be_i32(i)?;
+    let (i, fwritebasket) = be_i32(i)?;
+    let (i, fentrynumber) = be_i64(i)?;
+    let (i, foffset) = be_i32(i)?;
+    let (i, fmaxbaskets) = be_i32(i)?;
+    let (i, fsplitlevel) = be_i32(i)?;
+    let (i, fentries) = be_i64(i)?;
+    let (i, ffirstentry) = be_i64(i)?;
+    let (i, ftotbytes) = be_i64(i)?;
+    let (i, fzipbytes) = be_i64(i)?;
+    let (i, fbranches) = length_value(
+        checked_byte_count,
+        |i| class_object("TObjArray", i, context),
+    )(i)?;
+    let (i, fleaves) = length_value(
+        checked_byte_count,
+        |i| class_object("TObjArray", i, context),
+    )(i)?;
+    let (i, fbaskets) = length_value(
+        checked_byte_count,
+        |i| class_object("TObjArray", i, context),
+    )(i)?;
+    let (i, fbasketbytes) = basic_pointer(be_i32, fmaxbaskets as usize)(i)?;
+    let (i, fbasketentry) = basic_pointer(be_i64, fmaxbaskets as usize)(i)?;
+    let (i, fbasketseek) = basic_pointer(be_i64, fmaxbaskets as usize)(i)?;
+    let (i, ffilename) = string(i)?;
+    Ok((
+        i,
+        TBranch {
+            ver,
+            tnamed,
+            tattfill,
+            fcompress,
+            fbasketsize,
+            fentryoffsetlen,
+            fwritebasket,
+            fentrynumber,
+            foffset,
+            fmaxbaskets,
+            fsplitlevel,
+            fentries,
+            ffirstentry,
+            ftotbytes,
+            fzipbytes,
+            fbranches,
+            fleaves,
+            fbaskets,
+            fbasketbytes,
+            fbasketentry,
+            fbasketseek,
+            ffilename,
+        },
+    ))
+}
+pub fn tleaff<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TLeafF> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tleaf) = length_value(checked_byte_count, |i| tleaf(i, context))(i)?;
+    let (i, fminimum) = be_f32(i)?;
+    let (i, fmaximum) = be_f32(i)?;
+    Ok((
+        i,
+        TLeafF {
+            ver,
+            tleaf,
+            fminimum,
+            fmaximum,
+        },
+    ))
+}
+pub fn tleaf<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TLeaf> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tnamed) = length_value(checked_byte_count, tnamed)(i)?;
+    let (i, flen) = be_i32(i)?;
+    let (i, flentype) = be_i32(i)?;
+    let (i, foffset) = be_i32(i)?;
+    let (i, fisrange) = map(be_u8, |v| v != 0)(i)?;
+    let (i, fisunsigned) = map(be_u8, |v| v != 0)(i)?;
+    let (i, fleafcount) = object_pointer(i, context)?;
+    Ok((
+        i,
+        TLeaf {
+            ver,
+            tnamed,
+            flen,
+            flentype,
+            foffset,
+            fisrange,
+            fisunsigned,
+            fleafcount,
+        },
+    ))
+}
+pub fn tseqcollection<'s>(
+    i: &'s [u8],
+    context: &'s Context,
+) -> IResult<&'s [u8], TSeqCollection> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tcollection) = length_value(
+        checked_byte_count,
+        |i| tcollection(i, context),
+    )(i)?;
+    Ok((i, TSeqCollection { ver, tcollection }))
+}
+pub fn tcollection<'s>(
+    i: &'s [u8],
+    _context: &'s Context,
+) -> IResult<&'s [u8], TCollection> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tobject) = tobject(i)?;
+    let (i, fname) = string(i)?;
+    let (i, fsize) = be_i32(i)?;
+    Ok((
+        i,
+        TCollection {
+            ver,
+            tobject,
+            fname,
+            fsize,
+        },
+    ))
+}
+pub fn tbranchref<'s>(
+    i: &'s [u8],
+    context: &'s Context,
+) -> IResult<&'s [u8], TBranchRef> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tbranch) = length_value(checked_byte_count, |i| tbranch(i, context))(i)?;
+    let (i, freftable) = object_pointer(i, context)?;
+    Ok((
+        i,
+        TBranchRef {
+            ver,
+            tbranch,
+            freftable,
+        },
+    ))
+}
+pub fn treftable<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TRefTable> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tobject) = tobject(i)?;
+    let (i, fsize) = be_i32(i)?;
+    let (i, fparents) = object_pointer(i, context)?;
+    let (i, fowner) = object_pointer(i, context)?;
+    let (i, fprocessguids) = map(length_data(checked_byte_count), <[u8]>::to_vec)(i)?;
+    Ok((
+        i,
+        TRefTable {
+            ver,
+            tobject,
+            fsize,
+            fparents,
+            fowner,
+            fprocessguids,
+        },
+    ))
+}
+pub fn tleafi<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TLeafI> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tleaf) = length_value(checked_byte_count, |i| tleaf(i, context))(i)?;
+    let (i, fminimum) = be_i32(i)?;
+    let (i, fmaximum) = be_i32(i)?;
+    Ok((
+        i,
+        TLeafI {
+            ver,
+            tleaf,
+            fminimum,
+            fmaximum,
+        },
+    ))
+}
+pub fn tleafc<'s>(i: &'s [u8], context: &'s Context) -> IResult<&'s [u8], TLeafC> {
+    let (i, ver) = be_u16(i)?;
+    let (i, tleaf) = length_value(checked_byte_count, |i| tleaf(i, context))(i)?;
+    let (i, fminimum) = be_i32(i)?;
+    let (i, fmaximum) = be_i32(i)?;
+    Ok((
+        i,
+        TLeafC {
+            ver,
+            tleaf,
+            fminimum,
+            fmaximum,
+        },
+    ))
+}
@@ -69,8 +69,8 @@ mod local {
             f.streamer_info_as_yaml(&mut s).await.unwrap();
             f.streamer_info_as_rust(&mut s).await.unwrap();
             for item in f.items() {
-                item.name();
-                if item.verbose_info().contains("TTree") {
+                item.object_name();
+                if item.class_name() == "TTree" {
                     item.as_tree().await.unwrap();
                 }
             }
@@ -90,7 +90,7 @@ mod local {
             f.streamer_info_as_yaml(&mut s).await.unwrap();
             f.streamer_info_as_rust(&mut s).await.unwrap();
             for item in f.items() {
-                item.name();
+                item.object_name();
                 item.verbose_info();
             }
         }
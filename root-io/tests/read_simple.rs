@@ -31,7 +31,7 @@ impl Model {
 }
 
 async fn read_simple(f: RootFile) {
-    let t = f.items()[0].as_tree().await.unwrap();
+    let t = f.get("tree").unwrap().as_tree().await.unwrap();
     let s = Model::stream_from_tree(t).unwrap();
     s.for_each(|m| async move {
         println!("{:?}", m);
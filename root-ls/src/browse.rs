@@ -0,0 +1,390 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::{value_t, ArgMatches};
+use failure::Error;
+use futures::TryStreamExt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+use root_io::schema::{BranchSchema, ClassSchema, FileSchema};
+use root_io::tree_reader::Tree;
+use root_io::RootFile;
+
+/// One entry in the browsed hierarchy: a key of the file, a branch
+/// of a tree or a class of the streamer info
+struct Node {
+    label: String,
+    /// Lines shown in the details pane
+    details: Vec<String>,
+    /// `name;cycle` of the tree and name of the branch if the values
+    /// of this node can be previewed
+    preview: Option<(String, String)>,
+    children: Vec<Node>,
+}
+
+/// State of the browser independent of the terminal
+struct Browser {
+    nodes: Vec<Node>,
+    /// Paths (indices of the children on each level) of the expanded nodes
+    expanded: HashSet<Vec<usize>>,
+    /// Index of the selected node among the visible ones
+    selected: usize,
+    /// Number of values shown in previews
+    n_preview: i64,
+    /// Previews of the values of branches which were already loaded
+    previews: HashMap<(String, String), Vec<String>>,
+}
+
+impl Browser {
+    fn new(f: &RootFile, schema: &FileSchema, n_preview: i64) -> Self {
+        let mut nodes: Vec<_> = f
+            .items()
+            .iter()
+            .map(|item| {
+                let key = format!("{};{}", item.object_name(), item.cycle());
+                let mut details = vec![
+                    format!("Name:         {}", item.object_name()),
+                    format!("Title:        {}", item.title()),
+                    format!("Class:        {}", item.class_name()),
+                    format!("Cycle:        {}", item.cycle()),
+                    format!(
+                        "Written:      {}",
+                        item.datetime()
+                            .map_or("unknown".to_string(), |d| d.to_string())
+                    ),
+                    format!("Size on disk: {} bytes", item.compressed_size()),
+                    format!("Object size:  {} bytes", item.uncompressed_size()),
+                ];
+                let tree = schema
+                    .trees
+                    .iter()
+                    .find(|t| t.name == item.object_name() && t.cycle == item.cycle());
+                let children = match tree {
+                    Some(tree) => {
+                        details.push(format!("Entries:      {}", tree.entries));
+                        tree.branches.iter().map(|b| branch_node(&key, b)).collect()
+                    }
+                    None => vec![],
+                };
+                Node {
+                    label: format!("{} ({})", key, item.class_name()),
+                    details,
+                    preview: None,
+                    children,
+                }
+            })
+            .collect();
+        nodes.push(Node {
+            label: "StreamerInfo".to_string(),
+            details: vec![format!("{} classes", schema.classes.len())],
+            preview: None,
+            children: schema.classes.iter().map(class_node).collect(),
+        });
+        Browser {
+            nodes,
+            expanded: HashSet::new(),
+            selected: 0,
+            n_preview,
+            previews: HashMap::new(),
+        }
+    }
+
+    /// The paths and nodes of all visible nodes in display order
+    fn visible(&self) -> Vec<(Vec<usize>, &Node)> {
+        fn add<'a>(
+            nodes: &'a [Node],
+            parent: &[usize],
+            expanded: &HashSet<Vec<usize>>,
+            out: &mut Vec<(Vec<usize>, &'a Node)>,
+        ) {
+            for (i, node) in nodes.iter().enumerate() {
+                let mut path = parent.to_vec();
+                path.push(i);
+                let is_expanded = expanded.contains(&path);
+                out.push((path.clone(), node));
+                if is_expanded {
+                    add(&node.children, &path, expanded, out);
+                }
+            }
+        }
+        let mut out = vec![];
+        add(&self.nodes, &[], &self.expanded, &mut out);
+        out
+    }
+
+    fn selected(&self) -> (Vec<usize>, &Node) {
+        self.visible().swap_remove(self.selected)
+    }
+
+    /// Update the state for the pressed key. Returns `false` if the
+    /// browser should be closed.
+    fn handle(&mut self, key: KeyCode) -> bool {
+        let n_visible = self.visible().len();
+        let (path, node) = self.selected();
+        let has_children = !node.children.is_empty();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(n_visible - 1)
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::PageDown => self.selected = (self.selected + 20).min(n_visible - 1),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(20),
+            KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') if has_children => {
+                self.expanded.insert(path);
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if self.expanded.remove(&path) {
+                    // Collapse nested nodes, too
+                    self.expanded.retain(|p| !p.starts_with(&path));
+                } else if path.len() > 1 {
+                    // Jump to the parent
+                    let parent = &path[..path.len() - 1];
+                    self.selected = self
+                        .visible()
+                        .iter()
+                        .position(|(p, _)| p == parent)
+                        .unwrap_or(self.selected);
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Load the preview of the selected branch if it was not loaded yet
+    async fn load_preview(&mut self, f: &RootFile, trees: &mut HashMap<String, Tree>) {
+        let key = match &self.selected().1.preview {
+            Some(key) if !self.previews.contains_key(key) => key.clone(),
+            _ => return,
+        };
+        let lines = match preview(f, trees, &key.0, &key.1, self.n_preview).await {
+            Ok(lines) => lines,
+            Err(e) => vec![format!("Cannot preview values: {}", e)],
+        };
+        self.previews.insert(key, lines);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [main, help] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+        let [left, right] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .areas(main);
+
+        let visible = self.visible();
+        let items: Vec<_> = visible
+            .iter()
+            .map(|(path, node)| {
+                let marker = match (node.children.is_empty(), self.expanded.contains(path)) {
+                    (true, _) => " ",
+                    (false, true) => "▾",
+                    (false, false) => "▸",
+                };
+                let indent = "  ".repeat(path.len() - 1);
+                ListItem::new(format!("{}{} {}", indent, marker, node.label))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Content"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, left, &mut state);
+
+        let node = visible[self.selected].1;
+        let mut lines = node.details.clone();
+        if let Some(preview) = node.preview.as_ref().and_then(|key| self.previews.get(key)) {
+            lines.push(String::new());
+            lines.push(format!("First {} values:", self.n_preview));
+            lines.extend(preview.iter().cloned());
+        }
+        let details = Paragraph::new(lines.join("\n"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(node.label.as_str()),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, right);
+        frame.render_widget(
+            Paragraph::new("↑/↓ move  →/enter expand  ← collapse  q quit"),
+            help,
+        );
+    }
+}
+
+fn branch_node(tree: &str, b: &BranchSchema) -> Node {
+    let ratio = if b.compressed_bytes > 0 {
+        b.uncompressed_bytes as f64 / b.compressed_bytes as f64
+    } else {
+        1.0
+    };
+    let mut details = vec![
+        format!("Entries:      {}", b.entries),
+        format!("Uncompressed: {} bytes", b.uncompressed_bytes),
+        format!(
+            "Compressed:   {} bytes (ratio {:.2})",
+            b.compressed_bytes, ratio
+        ),
+    ];
+    for l in &b.leaves {
+        details.push(String::new());
+        details.push(format!("Leaf `{}` ({})", l.name, l.class));
+        details.push(format!("  Title:  {}", l.title));
+        details.push(format!(
+            "  Type:   {}",
+            l.type_name.as_deref().unwrap_or("not supported")
+        ));
+        details.push(format!("  Length: {}", l.length));
+        if let Some(counter) = &l.counter {
+            details.push(format!("  Counter: {}", counter));
+        }
+    }
+    Node {
+        label: b.name.to_owned(),
+        details,
+        preview: if b.branches.is_empty() {
+            Some((tree.to_string(), b.name.to_owned()))
+        } else {
+            None
+        },
+        children: b.branches.iter().map(|b| branch_node(tree, b)).collect(),
+    }
+}
+
+fn class_node(c: &ClassSchema) -> Node {
+    let mut details = vec![
+        format!("Version:  {}", c.version),
+        format!("Checksum: {}", c.checksum),
+        String::new(),
+    ];
+    for m in &c.members {
+        details.push(format!("{} {}; // {}", m.type_name, m.name, m.comment));
+    }
+    Node {
+        label: c.name.to_owned(),
+        details,
+        preview: None,
+        children: vec![],
+    }
+}
+
+/// The first `n` values of the given branch as lines of text
+async fn preview(
+    f: &RootFile,
+    trees: &mut HashMap<String, Tree>,
+    tree: &str,
+    branch: &str,
+    n: i64,
+) -> Result<Vec<String>, Error> {
+    if !trees.contains_key(tree) {
+        trees.insert(tree.to_string(), f.get(tree)?.as_lazy_tree().await?);
+    }
+    let rows: Vec<_> = trees[tree]
+        .select_range(&[branch], 0..n)
+        .await?
+        .try_collect()
+        .await?;
+    Ok(rows
+        .iter()
+        .enumerate()
+        .map(|(entry, row)| format!("{}: {}", entry, row[0]))
+        .collect())
+}
+
+/// Interactively browse the content of the file
+pub(crate) async fn browse(f: &RootFile, sub_matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let n_preview = value_t!(sub_matches.value_of("preview"), i64)?;
+    let schema = f.schema().await?;
+    let mut browser = Browser::new(f, &schema, n_preview);
+    let mut trees = HashMap::new();
+    let mut terminal = ratatui::try_init()?;
+    let res = async {
+        loop {
+            browser.load_preview(f, &mut trees).await;
+            terminal.draw(|frame| browser.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !browser.handle(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    .await;
+    ratatui::restore();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+
+    async fn browser() -> (RootFile, Browser) {
+        let path = Path::new("../root-io/src/test_data/simple.root");
+        let f = RootFile::new(path).await.unwrap();
+        let schema = f.schema().await.unwrap();
+        let browser = Browser::new(&f, &schema, 3);
+        (f, browser)
+    }
+
+    fn labels(browser: &Browser) -> Vec<&str> {
+        browser
+            .visible()
+            .iter()
+            .map(|(_, n)| n.label.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn navigate() {
+        let (f, mut b) = browser().await;
+        assert_eq!(labels(&b), ["tree;1 (TTree)", "StreamerInfo"]);
+        b.handle(KeyCode::Right);
+        assert_eq!(
+            labels(&b),
+            ["tree;1 (TTree)", "one", "two", "three", "StreamerInfo"]
+        );
+        b.handle(KeyCode::Down);
+        b.handle(KeyCode::Down);
+        assert_eq!(b.selected().1.label, "two");
+
+        let mut trees = HashMap::new();
+        b.load_preview(&f, &mut trees).await;
+        let key = ("tree;1".to_string(), "two".to_string());
+        assert_eq!(b.previews[&key], ["0: 1.1", "1: 2.2", "2: 3.3"]);
+
+        // Jump to the parent and collapse it
+        b.handle(KeyCode::Left);
+        assert_eq!(b.selected, 0);
+        b.handle(KeyCode::Left);
+        assert_eq!(labels(&b), ["tree;1 (TTree)", "StreamerInfo"]);
+        assert!(!b.handle(KeyCode::Char('q')));
+    }
+
+    #[tokio::test]
+    async fn draw() {
+        let (_f, mut b) = browser().await;
+        b.handle(KeyCode::Right);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| b.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("▾ tree;1 (TTree)"));
+        assert!(screen.contains("Entries:      4"));
+    }
+}
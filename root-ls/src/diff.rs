@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use failure::Error;
+use futures::{StreamExt, TryStreamExt};
+use root_io::schema::{BranchSchema, ClassSchema, FileSchema, TreeSchema};
+use root_io::tree_reader::Value;
+use root_io::RootFile;
+
+use crate::dump::readable_branches;
+
+/// What to compare and how
+pub(crate) struct Options {
+    /// Compare the content of the branches entry by entry
+    pub(crate) content: bool,
+    /// Absolute tolerance when comparing numbers
+    pub(crate) atol: f64,
+    /// Relative tolerance when comparing numbers
+    pub(crate) rtol: f64,
+    /// Maximum number of differing entries reported per branch
+    pub(crate) max_diffs: usize,
+}
+
+/// Compare the files `a` and `b`. Returns the found differences in
+/// human readable form; the files are identical if it is empty.
+pub(crate) async fn diff(a: &RootFile, b: &RootFile, opts: &Options) -> Result<Vec<String>, Error> {
+    let mut diffs = vec![];
+    diff_keys(a, b, &mut diffs);
+
+    let (schema_a, schema_b) = (a.schema().await?, b.schema().await?);
+    diff_classes(&schema_a, &schema_b, &mut diffs);
+    let trees_a = trees(&schema_a);
+    let trees_b = trees(&schema_b);
+    for (key, tree_a) in &trees_a {
+        let tree_b = match trees_b.get(key) {
+            Some(tree_b) => tree_b,
+            // Already reported as missing key
+            None => continue,
+        };
+        let common = diff_tree_layout(tree_a, tree_b, &mut diffs);
+        if opts.content {
+            let readable = readable_branches(&tree_a.branches);
+            let names: Vec<_> = common
+                .iter()
+                .filter(|name| readable.contains(name))
+                .collect();
+            diff_tree_content(a, b, key, &names, opts, &mut diffs).await?;
+        }
+    }
+    Ok(diffs)
+}
+
+/// Compare the names, cycles and classes of the items of both files
+fn diff_keys(a: &RootFile, b: &RootFile, diffs: &mut Vec<String>) {
+    let keys = |f: &RootFile| -> BTreeMap<_, _> {
+        f.items()
+            .iter()
+            .map(|item| {
+                let key = format!("{};{}", item.object_name(), item.cycle());
+                (key, item.class_name().to_string())
+            })
+            .collect()
+    };
+    let (keys_a, keys_b) = (keys(a), keys(b));
+    for (key, class_a) in &keys_a {
+        match keys_b.get(key) {
+            None => diffs.push(format!("key `{}` ({}) only in A", key, class_a)),
+            Some(class_b) if class_a != class_b => diffs.push(format!(
+                "key `{}` is a {} in A but a {} in B",
+                key, class_a, class_b
+            )),
+            _ => {}
+        }
+    }
+    for (key, class_b) in &keys_b {
+        if !keys_a.contains_key(key) {
+            diffs.push(format!("key `{}` ({}) only in B", key, class_b));
+        }
+    }
+}
+
+/// Compare the classes of the streamer infos of both files
+fn diff_classes(a: &FileSchema, b: &FileSchema, diffs: &mut Vec<String>) {
+    let (classes_a, classes_b) = (classes(a), classes(b));
+    for ((name, version), class_a) in &classes_a {
+        match classes_b.get(&(name.to_owned(), *version)) {
+            None => diffs.push(format!("class `{}` version {} only in A", name, version)),
+            Some(class_b) if class_a.checksum != class_b.checksum => diffs.push(format!(
+                "class `{}` version {} has checksum {} in A but {} in B",
+                name, version, class_a.checksum, class_b.checksum
+            )),
+            Some(class_b) if class_a.members != class_b.members => diffs.push(format!(
+                "class `{}` version {} has different members",
+                name, version
+            )),
+            _ => {}
+        }
+    }
+    for (name, version) in classes_b.keys() {
+        if !classes_a.contains_key(&(name.to_owned(), *version)) {
+            diffs.push(format!("class `{}` version {} only in B", name, version));
+        }
+    }
+}
+
+/// The classes of a file by name and version
+fn classes(schema: &FileSchema) -> BTreeMap<(String, u32), &ClassSchema> {
+    schema
+        .classes
+        .iter()
+        .map(|c| ((c.name.to_owned(), c.version), c))
+        .collect()
+}
+
+/// The trees of a file by `name;cycle`
+fn trees(schema: &FileSchema) -> BTreeMap<String, &TreeSchema> {
+    schema
+        .trees
+        .iter()
+        .map(|t| (format!("{};{}", t.name, t.cycle), t))
+        .collect()
+}
+
+/// All branches of a tree including the nested ones
+fn flatten(branches: &[BranchSchema]) -> BTreeMap<&str, &BranchSchema> {
+    let mut out = BTreeMap::new();
+    for b in branches {
+        out.insert(b.name.as_str(), b);
+        out.extend(flatten(&b.branches));
+    }
+    out
+}
+
+/// Compare the number of entries and the branches of two trees.
+/// Sizes are ignored since they depend on the compression. Returns
+/// the branches with the same layout in both trees.
+fn diff_tree_layout(a: &TreeSchema, b: &TreeSchema, diffs: &mut Vec<String>) -> Vec<String> {
+    let key = format!("{};{}", a.name, a.cycle);
+    if a.entries != b.entries {
+        diffs.push(format!(
+            "tree `{}` has {} entries in A but {} in B",
+            key, a.entries, b.entries
+        ));
+    }
+    let (branches_a, branches_b) = (flatten(&a.branches), flatten(&b.branches));
+    let names: BTreeSet<_> = branches_a.keys().chain(branches_b.keys()).collect();
+    let mut common = vec![];
+    for name in names {
+        match (branches_a.get(name), branches_b.get(name)) {
+            (Some(_), None) => diffs.push(format!("tree `{}`: branch `{}` only in A", key, name)),
+            (None, Some(_)) => diffs.push(format!("tree `{}`: branch `{}` only in B", key, name)),
+            (Some(ba), Some(bb)) if ba.entries != bb.entries || ba.leaves != bb.leaves => diffs
+                .push(format!(
+                    "tree `{}`: branch `{}` has a different layout",
+                    key, name
+                )),
+            _ => common.push(name.to_string()),
+        }
+    }
+    common
+}
+
+/// Compare the given branches of the tree `key` entry by entry
+async fn diff_tree_content(
+    a: &RootFile,
+    b: &RootFile,
+    key: &str,
+    names: &[&String],
+    opts: &Options,
+    diffs: &mut Vec<String>,
+) -> Result<(), Error> {
+    let (tree_a, tree_b) = (
+        a.get(key)?.as_lazy_tree().await?,
+        b.get(key)?.as_lazy_tree().await?,
+    );
+    for name in names {
+        let rows_a = tree_a.select(&[name.as_str()]).await?;
+        let rows_b = tree_b.select(&[name.as_str()]).await?;
+        let mut rows = rows_a.zip(rows_b).enumerate().boxed_local();
+        let mut n_diffs = 0;
+        while let Some((entry, (row_a, row_b))) = rows.next().await {
+            let (row_a, row_b) = (row_a?, row_b?);
+            if !values_close(&row_a[0], &row_b[0], opts) {
+                n_diffs += 1;
+                if n_diffs > opts.max_diffs {
+                    break;
+                }
+                diffs.push(format!(
+                    "tree `{}`: branch `{}` differs in entry {}: {} != {}",
+                    key, name, entry, row_a[0], row_b[0]
+                ));
+            }
+        }
+        if n_diffs > opts.max_diffs {
+            // Count the remaining differences without reporting them
+            let rest: usize = rows
+                .map(|(_, (row_a, row_b))| Ok::<_, Error>((row_a?, row_b?)))
+                .try_fold(0, |n, (row_a, row_b)| async move {
+                    Ok(n + !values_close(&row_a[0], &row_b[0], opts) as usize)
+                })
+                .await?;
+            diffs.push(format!(
+                "tree `{}`: branch `{}` differs in {} more entries",
+                key,
+                name,
+                rest + 1
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Are the two values equal within the tolerances? Numbers `x` and
+/// `y` are close if `|x - y| <= atol + rtol * |y|`.
+fn values_close(a: &Value, b: &Value, opts: &Options) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_close(a, b, opts))
+        }
+        (Value::String(a), Value::String(b)) => a == b,
+        _ => match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) => {
+                x == y
+                    || (x.is_nan() && y.is_nan())
+                    || (x - y).abs() <= opts.atol + opts.rtol * y.abs()
+            }
+            _ => a == b,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use root_io::Source;
+
+    use super::*;
+    use crate::test_utils::InMemory;
+
+    fn opts() -> Options {
+        Options {
+            content: true,
+            atol: 0.0,
+            rtol: 0.0,
+            max_diffs: 2,
+        }
+    }
+
+    async fn open(name: &str) -> RootFile {
+        let path = Path::new("../root-io/src/test_data").join(name);
+        RootFile::new(path.as_path()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn same_content_different_compression() {
+        let a = open("HZZ-zlib.root").await;
+        let b = open("HZZ-lz4.root").await;
+        assert_eq!(diff(&a, &b, &opts()).await.unwrap(), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn different_files() {
+        let a = open("simple.root").await;
+        let b = open("HZZ.root").await;
+        let diffs = diff(&a, &b, &opts()).await.unwrap();
+        assert!(diffs.contains(&"key `tree;1` (TTree) only in A".to_string()));
+        assert!(diffs.contains(&"key `events;1` (TTree) only in B".to_string()));
+    }
+
+    /// `HZZ-uncompressed.root` with the first `n` entries of the
+    /// branch `EventWeight` multiplied by `factor`
+    async fn scaled_weights(n: usize, factor: f32) -> RootFile {
+        let weights: Vec<_> = open("HZZ-uncompressed.root")
+            .await
+            .get("events")
+            .unwrap()
+            .as_tree()
+            .await
+            .unwrap()
+            .select(&["EventWeight"])
+            .await
+            .unwrap()
+            .map_ok(|row| row[0].as_f64().unwrap() as f32)
+            .try_collect()
+            .await
+            .unwrap();
+        let bytes = |factor: f32| -> Vec<u8> {
+            weights[..n]
+                .iter()
+                .flat_map(|w| (w * factor).to_be_bytes())
+                .collect()
+        };
+        let mut buf = std::fs::read("../root-io/src/test_data/HZZ-uncompressed.root").unwrap();
+        let old = bytes(1.0);
+        let pos = buf.windows(old.len()).position(|w| w == old).unwrap();
+        buf[pos..pos + old.len()].copy_from_slice(&bytes(factor));
+        RootFile::new(Source::from_fetch(InMemory(buf)))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn different_values() {
+        let a = open("HZZ-uncompressed.root").await;
+        let b = scaled_weights(3, 2.0).await;
+        let diffs = diff(&a, &b, &opts()).await.unwrap();
+        assert_eq!(diffs.len(), 3, "{:?}", diffs);
+        for (entry, diff) in diffs[..2].iter().enumerate() {
+            let prefix = format!(
+                "tree `events;1`: branch `EventWeight` differs in entry {}: ",
+                entry
+            );
+            assert!(diff.starts_with(&prefix), "{}", diff);
+        }
+        assert_eq!(
+            diffs[2],
+            "tree `events;1`: branch `EventWeight` differs in 1 more entries"
+        );
+
+        // Small differences are within the tolerances
+        let b = scaled_weights(3, 1.001).await;
+        assert_eq!(diff(&a, &b, &opts()).await.unwrap().len(), 3);
+        let opts = Options {
+            rtol: 0.01,
+            ..opts()
+        };
+        assert_eq!(diff(&a, &b, &opts).await.unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn tolerances() {
+        let mut opts = opts();
+        let (a, b) = (Value::F32(1.0), Value::F64(1.001));
+        assert!(!values_close(&a, &b, &opts));
+        opts.atol = 0.01;
+        assert!(values_close(&a, &b, &opts));
+        opts.atol = 0.0;
+        opts.rtol = 0.01;
+        assert!(values_close(&a, &b, &opts));
+        let jagged = |vs: Vec<i32>| Value::Array(vs.into_iter().map(Value::I32).collect());
+        assert!(values_close(
+            &jagged(vec![1, 2]),
+            &jagged(vec![1, 2]),
+            &opts
+        ));
+        assert!(!values_close(&jagged(vec![1, 2]), &jagged(vec![1]), &opts));
+        assert!(!values_close(
+            &jagged(vec![1, 2]),
+            &jagged(vec![1, 3]),
+            &opts
+        ));
+    }
+}
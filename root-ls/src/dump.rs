@@ -0,0 +1,132 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use clap::ArgMatches;
+use failure::{format_err, Error};
+use futures::{stream, StreamExt};
+use root_io::schema::BranchSchema;
+use root_io::tree_reader::Value;
+use root_io::RootFile;
+
+use crate::table::write_table;
+
+/// Print the values of the selected branches and entries of a tree
+pub(crate) async fn dump(f: &RootFile, sub_matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let item = f.get(sub_matches.value_of("TREE").unwrap())?;
+    let tree = item.as_lazy_tree().await?;
+    let names = match sub_matches.value_of("branches") {
+        Some(names) => names.split(',').map(|n| n.trim().to_string()).collect(),
+        None => {
+            let branches: Vec<_> = tree
+                .top_level_branches()?
+                .iter()
+                .map(|b| b.schema())
+                .collect();
+            readable_branches(&branches)
+        }
+    };
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    let entries = match sub_matches.value_of("entries") {
+        Some(range) => parse_range(range)?,
+        None => 0..tree.entries(),
+    };
+    let entries = entries.start.max(0)..entries.end.min(tree.entries());
+    let rows = tree.select_range(&names, entries.clone()).await?;
+    let mut rows = stream::iter(entries).zip(rows).boxed_local();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match sub_matches.value_of("format") {
+        Some("table") => {
+            let mut table = vec![];
+            while let Some((entry, row)) = rows.next().await {
+                let mut cells = vec![entry.to_string()];
+                cells.extend(row?.iter().map(Value::to_string));
+                table.push(cells);
+            }
+            let header: Vec<_> = std::iter::once("entry")
+                .chain(names.iter().copied())
+                .collect();
+            write_table(&mut out, &header, &table)?;
+        }
+        Some("csv") => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(std::iter::once(&"entry").chain(&names))?;
+            while let Some((entry, row)) = rows.next().await {
+                let row = row?;
+                writer.write_record(
+                    std::iter::once(entry.to_string()).chain(row.iter().map(Value::to_string)),
+                )?;
+            }
+            writer.flush()?;
+        }
+        Some("jsonl") => {
+            while let Some((entry, row)) = rows.next().await {
+                // Written by hand to keep the order of the branches
+                let mut line = format!("{{\"entry\":{}", entry);
+                for (name, value) in names.iter().zip(row?) {
+                    line += &format!(
+                        ",{}:{}",
+                        serde_json::to_string(name)?,
+                        serde_json::to_string(&value)?
+                    );
+                }
+                writeln!(out, "{}}}", line)?;
+            }
+        }
+        format => unreachable!("Unsupported format {:?}", format),
+    }
+    Ok(())
+}
+
+/// Names of all branches without sub-branches whose values can be decoded
+pub(crate) fn readable_branches(branches: &[BranchSchema]) -> Vec<String> {
+    let mut names = vec![];
+    for b in branches {
+        if !b.branches.is_empty() {
+            names.extend(readable_branches(&b.branches));
+        } else if !b.leaves.is_empty()
+            && b.leaves.iter().all(|l| l.type_name.is_some())
+            && (b.leaves.len() == 1 || b.leaves.iter().all(|l| l.counter.is_none()))
+        {
+            names.push(b.name.to_owned());
+        }
+    }
+    names
+}
+
+/// Parse a range of entries such as `10..20`, `10..`, `..20` or `10`
+fn parse_range(s: &str) -> Result<Range<i64>, Error> {
+    let parse = |s: &str, default| match s.trim() {
+        "" => Ok(default),
+        s => s
+            .parse()
+            .map_err(|_| format_err!("Invalid range of entries `{}`", s)),
+    };
+    match s.split_once("..") {
+        Some((start, end)) => Ok(parse(start, 0)?..parse(end, i64::MAX)?),
+        None => {
+            let entry = parse(s, 0)?;
+            Ok(entry..entry.saturating_add(1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("10..20").unwrap(), 10..20);
+        assert_eq!(parse_range("10..").unwrap(), 10..i64::MAX);
+        assert_eq!(parse_range("..20").unwrap(), 0..20);
+        assert_eq!(parse_range("7").unwrap(), 7..8);
+        // The last representable entry does not overflow
+        assert_eq!(
+            parse_range("9223372036854775807").unwrap(),
+            i64::MAX..i64::MAX
+        );
+        assert!(parse_range("a..b").is_err());
+    }
+}
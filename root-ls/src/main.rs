@@ -1,10 +1,20 @@
-use std::env;
-
 use std::path::Path;
+use std::process;
 
 use clap::{crate_version, value_t, App, AppSettings, Arg, ArgMatches, SubCommand};
-use failure::Error;
-use root_io::RootFile;
+use failure::{format_err, Error};
+use reqwest::Url;
+use root_io::{RootFile, Source};
+
+use crate::summary::Summary;
+
+mod browse;
+mod diff;
+mod dump;
+mod stats;
+mod summary;
+mod table;
+mod test_utils;
 
 #[tokio::main]
 async fn main() {
@@ -12,10 +22,16 @@ async fn main() {
         .version(crate_version!())
         .arg(
             Arg::with_name("INPUT")
-                .help("Input .root file")
+                .help("Input .root files; local paths, glob patterns or http(s) URLs")
                 .required(true)
+                .multiple(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name("recover")
+                .long("recover")
+                .help("Recover files whose list of keys is missing or corrupt, e.g. of crashed jobs"),
+        )
         .setting(AppSettings::VersionlessSubcommands)
         .subcommand(
             SubCommand::with_name("inspect")
@@ -32,33 +48,265 @@ async fn main() {
             SubCommand::with_name("to-rust")
                 .about("Generate Rust structs and parsers form the StreamerInfo"),
         )
+        .subcommand(
+            SubCommand::with_name("schema")
+                .about("Output the StreamerInfo and the layout of all trees in a machine readable format")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json"])
+                        .default_value("json")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about("Print the values of the branches of a tree similar to `TTree::Scan`")
+                .arg(
+                    Arg::with_name("TREE")
+                        .help("Name of the tree (optionally with `;cycle`)")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("branches")
+                        .long("branches")
+                        .takes_value(true)
+                        .help("Comma separated list of branches; defaults to all readable branches"),
+                )
+                .arg(
+                    Arg::with_name("entries")
+                        .long("entries")
+                        .takes_value(true)
+                        .help("Range of entries such as `10..20`, `10..`, `..20` or `10`"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "csv", "jsonl"])
+                        .default_value("table")
+                        .help("Output format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Print the sizes and compression of all branches similar to `TTree::Print`")
+                .arg(
+                    Arg::with_name("tree")
+                        .long("tree")
+                        .takes_value(true)
+                        .help("Only show this tree; defaults to all trees"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .takes_value(true)
+                        .possible_values(&[
+                            "name",
+                            "entries",
+                            "baskets",
+                            "uncompressed",
+                            "compressed",
+                            "ratio",
+                        ])
+                        .help("Sort the branches; numbers are sorted in descending order"),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .takes_value(true)
+                        .help("Only show branches whose name matches this regular expression"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Compare the keys, streamer info and trees of this file with another file; exits with a nonzero code if they differ")
+                .arg(
+                    Arg::with_name("OTHER")
+                        .help("The file to compare with")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("content")
+                        .long("content")
+                        .help("Also compare the content of all common branches entry by entry"),
+                )
+                .arg(
+                    Arg::with_name("atol")
+                        .long("atol")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Absolute tolerance when comparing numbers"),
+                )
+                .arg(
+                    Arg::with_name("rtol")
+                        .long("rtol")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Relative tolerance when comparing numbers"),
+                )
+                .arg(
+                    Arg::with_name("max-diffs")
+                        .long("max-diffs")
+                        .takes_value(true)
+                        .default_value("5")
+                        .help("Maximum number of differing entries reported per branch"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("browse")
+                .about("Interactively browse the keys, trees, branches and streamer info of this file")
+                .arg(
+                    Arg::with_name("preview")
+                        .long("preview")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("Number of values shown in the preview of a branch"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Check the integrity of this file; exits with a nonzero code on problems"),
+        )
         .get_matches();
-    let in_path = Path::new(matches.value_of("INPUT").unwrap());
-    let f = root_io::RootFile::new(in_path)
-        .await
-        .expect("Failed to open file");
-
-    if let Some(matches) = matches.subcommand_matches("inspect") {
-        inspect_file(&f, matches).await;
-    } else if matches.subcommand_matches("to-yaml").is_some() {
-        sinfo_to_yaml(&f).await;
-    } else if matches.subcommand_matches("to-rust").is_some() {
-        to_rust(&f).await.unwrap();
+    let inputs = match inputs(matches.values_of("INPUT").unwrap()) {
+        Ok(inputs) => inputs,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let (name, sub_matches) = matches.subcommand();
+    let sub_matches = match sub_matches {
+        Some(sub_matches) => sub_matches,
+        None => {
+            // Write help if no sub command is given
+            println!("{}", matches.usage());
+            return;
+        }
+    };
+    let mut summary = Summary::default();
+    let mut ok = true;
+    for (input, source) in &inputs {
+        if inputs.len() > 1 {
+            println!("==> {} <==", input);
+        }
+        let f = if matches.is_present("recover") {
+            RootFile::recover(source.clone()).await
+        } else {
+            RootFile::new(source.clone()).await
+        };
+        let f = match f {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Failed to open file {}: {}", input, e);
+                ok = false;
+                continue;
+            }
+        };
+        for warning in f.warnings() {
+            eprintln!("Warning: {}", warning);
+        }
+        let summary = if inputs.len() > 1 {
+            Some(&mut summary)
+        } else {
+            None
+        };
+        match run(name, &f, sub_matches, summary).await {
+            Ok(true) => {}
+            Ok(false) => ok = false,
+            Err(e) => {
+                eprintln!("Failed to process {}: {}", input, e);
+                ok = false;
+            }
+        }
+    }
+    if let Err(e) = summary.print(inputs.len()) {
+        eprintln!("Failed to print summary: {}", e);
+        ok = false;
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Expand the given inputs into the name and source of each file.
+/// URLs are used as is while glob patterns are expanded into all
+/// matching local files.
+fn inputs<'a>(patterns: impl Iterator<Item = &'a str>) -> Result<Vec<(String, Source)>, Error> {
+    let mut inputs = vec![];
+    for pattern in patterns {
+        if pattern.contains(['*', '?', '[']) && !is_url(pattern) {
+            let paths = glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?;
+            if paths.is_empty() {
+                return Err(format_err!("No files match `{}`", pattern));
+            }
+            for path in paths {
+                inputs.push((path.display().to_string(), path.into()));
+            }
+        } else {
+            inputs.push((pattern.to_string(), source(pattern)?));
+        }
+    }
+    Ok(inputs)
+}
+
+fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// A local path or a URL
+fn source(input: &str) -> Result<Source, Error> {
+    Ok(if is_url(input) {
+        Url::parse(input)?.into()
     } else {
-        // Write help if no sub command is given
-        println!("{}", matches.usage());
+        Path::new(input).into()
+    })
+}
+
+/// Run the sub command `name` on the file `f`. Returns `false` if
+/// the sub command found problems (e.g. differences or corruptions).
+async fn run(
+    name: &str,
+    f: &RootFile,
+    sub_matches: &ArgMatches<'_>,
+    summary: Option<&mut Summary>,
+) -> Result<bool, Error> {
+    match name {
+        "inspect" => inspect_file(f, sub_matches, summary).await?,
+        "to-yaml" => sinfo_to_yaml(f).await?,
+        "to-rust" => to_rust(f).await?,
+        "schema" => schema(f, sub_matches).await?,
+        "dump" => dump::dump(f, sub_matches).await?,
+        "stats" => stats::stats(f, sub_matches, summary).await?,
+        "browse" => browse::browse(f, sub_matches).await?,
+        "diff" => return diff(f, sub_matches).await,
+        "check" => return check(f).await,
+        name => unreachable!("Unknown sub command {}", name),
     }
+    Ok(true)
 }
 
-async fn inspect_file(f: &RootFile, sub_matches: &ArgMatches<'_>) {
+async fn inspect_file(
+    f: &RootFile,
+    sub_matches: &ArgMatches<'_>,
+    summary: Option<&mut Summary>,
+) -> Result<(), Error> {
     if sub_matches.is_present("item-pos") {
-        let idx = value_t!(sub_matches.value_of("item-pos"), usize).unwrap();
+        let idx = value_t!(sub_matches.value_of("item-pos"), usize)?;
+        let item = f
+            .items()
+            .get(idx)
+            .ok_or_else(|| format_err!("No item at position {}", idx))?;
         // FIXME: This should not be specific for TTrees!
-        let tree = f.items()[idx].as_tree().await.unwrap();
+        let tree = item.as_tree().await?;
         if sub_matches.is_present("v") {
             println!("{:#?}", tree);
         } else {
-            for (name, types) in &tree.branch_names_and_types() {
+            for (name, types) in &tree.branch_names_and_types()? {
                 println!("{}: {:#?}", name, types);
             }
         }
@@ -68,18 +316,27 @@ async fn inspect_file(f: &RootFile, sub_matches: &ArgMatches<'_>) {
             if sub_matches.is_present("v") {
                 println!("{}: {}", i, item.verbose_info());
             } else {
-                println!("{}: {}", i, item.name());
+                println!(
+                    "{}: `{};{}` of type `{}`",
+                    i,
+                    item.object_name(),
+                    item.cycle(),
+                    item.class_name()
+                );
             }
         }
+        if let Some(summary) = summary {
+            summary.add_file(f).await?;
+        }
     }
+    Ok(())
 }
 
-async fn sinfo_to_yaml(f: &RootFile) {
+async fn sinfo_to_yaml(f: &RootFile) -> Result<(), Error> {
     let mut s = String::new();
-    match f.streamer_info_as_yaml(&mut s).await {
-        Ok(_) => println!("{}", s),
-        Err(e) => println!("Failed to create yaml. Error: {:?}", e),
-    }
+    f.streamer_info_as_yaml(&mut s).await?;
+    println!("{}", s);
+    Ok(())
 }
 
 async fn to_rust(f: &RootFile) -> Result<(), Error> {
@@ -89,3 +346,85 @@ async fn to_rust(f: &RootFile) -> Result<(), Error> {
     println!("{}", prettyplease::unparse(&tree));
     Ok(())
 }
+
+async fn schema(f: &RootFile, sub_matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let schema = f.schema().await?;
+    match sub_matches.value_of("format") {
+        Some("json") => println!("{}", serde_json::to_string_pretty(&schema)?),
+        format => unreachable!("Unsupported format {:?}", format),
+    }
+    Ok(())
+}
+
+/// Compare this file with the one given on the command line and print
+/// the differences. Returns `true` if the files are identical.
+async fn diff(f: &RootFile, sub_matches: &ArgMatches<'_>) -> Result<bool, Error> {
+    let other = RootFile::new(source(sub_matches.value_of("OTHER").unwrap())?).await?;
+    let opts = diff::Options {
+        content: sub_matches.is_present("content"),
+        atol: value_t!(sub_matches.value_of("atol"), f64)?,
+        rtol: value_t!(sub_matches.value_of("rtol"), f64)?,
+        max_diffs: value_t!(sub_matches.value_of("max-diffs"), usize)?,
+    };
+    let diffs = diff::diff(f, &other, &opts).await?;
+    for d in &diffs {
+        println!("{}", d);
+    }
+    if diffs.is_empty() {
+        println!("Files are identical");
+    } else {
+        println!("{} differences found", diffs.len());
+    }
+    Ok(diffs.is_empty())
+}
+
+/// Verify the file and print the found problems. Returns `true` if the file is intact.
+async fn check(f: &RootFile) -> Result<bool, Error> {
+    let report = f.verify().await?;
+    for issue in report.issues() {
+        println!("{}", issue);
+    }
+    println!(
+        "Checked {} keys and {} baskets: {}",
+        report.keys(),
+        report.baskets(),
+        if report.is_ok() {
+            "OK".to_string()
+        } else {
+            format!("{} problems found", report.issues().len())
+        }
+    );
+    Ok(report.is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_inputs() {
+        let inputs = inputs(
+            [
+                "../root-io/src/test_data/HZZ-*.root",
+                "simple.root",
+                "https://example.com/simple.root",
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        let names: Vec<_> = inputs.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "../root-io/src/test_data/HZZ-lz4.root",
+                "../root-io/src/test_data/HZZ-lzma.root",
+                "../root-io/src/test_data/HZZ-uncompressed.root",
+                "../root-io/src/test_data/HZZ-zlib.root",
+                "simple.root",
+                "https://example.com/simple.root",
+            ]
+        );
+        assert!(format!("{:?}", inputs[5].1).contains("Remote"));
+        assert!(super::inputs(["no_such_*.root"].into_iter()).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::io;
+
+use clap::ArgMatches;
+use failure::Error;
+use regex::Regex;
+use root_io::core::Compression;
+use root_io::tree_reader::{TBranch, Tree};
+use root_io::RootFile;
+
+use crate::summary::{is_latest_cycle, Summary};
+use crate::table::write_table;
+
+/// Size information of one branch
+struct BranchStats {
+    name: String,
+    /// Nesting level of the branch in the tree
+    depth: usize,
+    entries: i64,
+    baskets: usize,
+    uncompressed: i64,
+    compressed: i64,
+    compression: Compression,
+}
+
+impl BranchStats {
+    fn ratio(&self) -> f64 {
+        ratio(self.uncompressed, self.compressed)
+    }
+}
+
+/// Print the sizes of all branches of the trees in this file similar to `TTree::Print`
+pub(crate) async fn stats(
+    f: &RootFile,
+    sub_matches: &ArgMatches<'_>,
+    mut summary: Option<&mut Summary>,
+) -> Result<(), Error> {
+    let filter = sub_matches.value_of("filter").map(Regex::new).transpose()?;
+    let sort = sub_matches.value_of("sort");
+    let items = match sub_matches.value_of("tree") {
+        Some(name) => vec![f.get(name)?],
+        None => f
+            .items()
+            .iter()
+            .filter(|item| item.class_name() == "TTree")
+            .collect(),
+    };
+    for item in items {
+        let tree = item.as_lazy_tree().await?;
+        // Lower cycles of a tree are only counted if requested explicitly
+        if let Some(summary) = summary.as_mut() {
+            if sub_matches.is_present("tree") || is_latest_cycle(f, item) {
+                summary.add(&tree);
+            }
+        }
+        let mut branches = vec![];
+        for b in tree.top_level_branches()? {
+            collect_stats(b, 0, f.compression(), &mut branches);
+        }
+        if let Some(filter) = &filter {
+            branches.retain(|b| filter.is_match(&b.name));
+        }
+        match sort {
+            Some("name") => branches.sort_by(|a, b| a.name.cmp(&b.name)),
+            Some("entries") => branches.sort_by_key(|b| -b.entries),
+            Some("baskets") => branches.sort_by_key(|b| std::cmp::Reverse(b.baskets)),
+            Some("uncompressed") => branches.sort_by_key(|b| -b.uncompressed),
+            Some("compressed") => branches.sort_by_key(|b| -b.compressed),
+            Some("ratio") => branches.sort_by(|a, b| b.ratio().total_cmp(&a.ratio())),
+            _ => {}
+        }
+        print_tree(&tree, item.cycle(), &branches, sort.is_none())?;
+    }
+    Ok(())
+}
+
+/// Add the stats of `b` and all its sub-branches to `out`
+fn collect_stats(b: &TBranch, depth: usize, file: Compression, out: &mut Vec<BranchStats>) {
+    let compression = b.compression();
+    out.push(BranchStats {
+        name: b.name(),
+        depth,
+        entries: b.entries(),
+        baskets: b.baskets(),
+        uncompressed: b.uncompressed_bytes(),
+        compressed: b.compressed_bytes(),
+        compression: if compression.is_inherited() {
+            file
+        } else {
+            compression
+        },
+    });
+    for sub in b.sub_branches() {
+        collect_stats(sub, depth + 1, file, out);
+    }
+}
+
+/// Uncompressed size divided by compressed size
+fn ratio(uncompressed: i64, compressed: i64) -> f64 {
+    if compressed > 0 {
+        uncompressed as f64 / compressed as f64
+    } else {
+        1.0
+    }
+}
+
+fn print_tree(tree: &Tree, cycle: i16, branches: &[BranchStats], indent: bool) -> io::Result<()> {
+    println!(
+        "Tree `{};{}`: {} entries, {} bytes uncompressed, {} bytes compressed (ratio {:.2})",
+        tree.name(),
+        cycle,
+        tree.entries(),
+        tree.uncompressed_bytes(),
+        tree.compressed_bytes(),
+        ratio(tree.uncompressed_bytes(), tree.compressed_bytes()),
+    );
+    let rows: Vec<_> = branches
+        .iter()
+        .map(|b| {
+            let indent = if indent { b.depth * 2 } else { 0 };
+            vec![
+                format!("{:indent$}{}", "", b.name, indent = indent),
+                b.entries.to_string(),
+                b.baskets.to_string(),
+                b.uncompressed.to_string(),
+                b.compressed.to_string(),
+                format!("{:.2}", b.ratio()),
+                b.compression.to_string(),
+            ]
+        })
+        .collect();
+    let header = [
+        "branch",
+        "entries",
+        "baskets",
+        "uncompressed",
+        "compressed",
+        "ratio",
+        "compression",
+    ];
+    write_table(&mut io::stdout().lock(), &header, &rows)?;
+    println!();
+    Ok(())
+}
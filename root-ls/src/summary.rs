@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use failure::Error;
+use root_io::tree_reader::Tree;
+use root_io::{FileItem, RootFile};
+
+use crate::table::write_table;
+
+/// Totals of the trees of the same name across several files
+#[derive(Default)]
+struct TreeTotals {
+    files: usize,
+    entries: i64,
+    uncompressed: i64,
+    compressed: i64,
+}
+
+/// Summary of the trees found in all input files
+#[derive(Default)]
+pub(crate) struct Summary {
+    trees: BTreeMap<String, TreeTotals>,
+}
+
+impl Summary {
+    /// Add the trees of one of the input files
+    pub(crate) async fn add_file(&mut self, f: &RootFile) -> Result<(), Error> {
+        for item in f.items() {
+            if item.class_name() == "TTree" && is_latest_cycle(f, item) {
+                self.add(&item.as_lazy_tree().await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a tree of one of the input files. Only one cycle of each
+    /// tree of a file should be added; see `is_latest_cycle`.
+    pub(crate) fn add(&mut self, tree: &Tree) {
+        let totals = self.trees.entry(tree.name().to_string()).or_default();
+        totals.files += 1;
+        totals.entries += tree.entries();
+        totals.uncompressed += tree.uncompressed_bytes();
+        totals.compressed += tree.compressed_bytes();
+    }
+
+    /// Print the totals of all trees; nothing if no trees were added
+    pub(crate) fn print(&self, n_files: usize) -> io::Result<()> {
+        if self.trees.is_empty() {
+            return Ok(());
+        }
+        println!("Total over {} files:", n_files);
+        let rows: Vec<_> = self
+            .trees
+            .iter()
+            .map(|(name, t)| {
+                vec![
+                    name.to_owned(),
+                    t.files.to_string(),
+                    t.entries.to_string(),
+                    t.uncompressed.to_string(),
+                    t.compressed.to_string(),
+                ]
+            })
+            .collect();
+        let header = ["tree", "files", "entries", "uncompressed", "compressed"];
+        write_table(&mut io::stdout().lock(), &header, &rows)
+    }
+}
+
+/// Is `item` the highest cycle of its name in `f`? Lower cycles are
+/// earlier (e.g. autosaved) versions of the same object and must not
+/// be counted again.
+pub(crate) fn is_latest_cycle(f: &RootFile, item: &FileItem) -> bool {
+    f.get(item.object_name())
+        .is_ok_and(|latest| latest.cycle() == item.cycle())
+}
+
+#[cfg(test)]
+mod tests {
+    use root_io::Source;
+
+    use super::*;
+    use crate::test_utils::InMemory;
+
+    fn i32_at(b: &[u8], pos: usize) -> i32 {
+        i32::from_be_bytes(b[pos..pos + 4].try_into().unwrap())
+    }
+
+    fn i16_at(b: &[u8], pos: usize) -> i16 {
+        i16::from_be_bytes(b[pos..pos + 2].try_into().unwrap())
+    }
+
+    /// `simple.root` with a second cycle of its only tree as written
+    /// by an autosave. A new list of keys holding both cycles is
+    /// appended to the file.
+    fn two_cycles() -> Vec<u8> {
+        let mut b = std::fs::read("../root-io/src/test_data/simple.root").unwrap();
+        // Small files use 32 bit pointers
+        assert!(i32_at(&b, 4) < 1_000_000);
+        let seek_dir = (i32_at(&b, 8) + i32_at(&b, 28)) as usize;
+        assert!(i16_at(&b, seek_dir) < 1000);
+        let n_bytes_keys = i32_at(&b, seek_dir + 10) as usize;
+        let seek_keys = i32_at(&b, seek_dir + 26) as usize;
+        let keys = b[seek_keys..seek_keys + n_bytes_keys].to_vec();
+        let key_len = i16_at(&keys, 14) as usize;
+        assert_eq!(i32_at(&keys, key_len), 1);
+        let tree_key_len = i16_at(&keys, key_len + 4 + 14) as usize;
+        let mut tree_key = keys[key_len + 4..key_len + 4 + tree_key_len].to_vec();
+        tree_key[16..18].copy_from_slice(&2i16.to_be_bytes());
+
+        let mut new_keys = keys[..key_len].to_vec();
+        new_keys.extend(2i32.to_be_bytes());
+        new_keys.extend(&keys[key_len + 4..]);
+        new_keys.extend(tree_key);
+        let n = new_keys.len();
+        new_keys[0..4].copy_from_slice(&(n as i32).to_be_bytes());
+        new_keys[6..10].copy_from_slice(&((n - key_len) as i32).to_be_bytes());
+        let new_seek_keys = b.len();
+        b[seek_dir + 10..seek_dir + 14].copy_from_slice(&(n as i32).to_be_bytes());
+        b[seek_dir + 26..seek_dir + 30].copy_from_slice(&(new_seek_keys as i32).to_be_bytes());
+        b.extend(new_keys);
+        b
+    }
+
+    #[tokio::test]
+    async fn only_latest_cycle() {
+        let f = RootFile::new(Source::from_fetch(InMemory(two_cycles())))
+            .await
+            .unwrap();
+        let cycles: Vec<_> = f.items().iter().map(|item| item.cycle()).collect();
+        assert_eq!(cycles, &[1, 2]);
+        assert!(!is_latest_cycle(&f, &f.items()[0]));
+        assert!(is_latest_cycle(&f, &f.items()[1]));
+
+        let mut summary = Summary::default();
+        summary.add_file(&f).await.unwrap();
+        let totals = &summary.trees["tree"];
+        assert_eq!((totals.files, totals.entries), (1, 4));
+    }
+}
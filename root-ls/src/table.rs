@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+
+/// Write the rows as a table with aligned columns
+pub(crate) fn write_table<W: Write>(
+    out: &mut W,
+    header: &[&str],
+    rows: &[Vec<String>],
+) -> io::Result<()> {
+    let widths: Vec<_> = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(name.len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &mut dyn Iterator<Item = &str>| {
+        cells
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    writeln!(out, "{}", line(&mut header.iter().copied()))?;
+    let rule: Vec<_> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    writeln!(out, "{}", rule.join("-+-"))?;
+    for row in rows {
+        writeln!(out, "{}", line(&mut row.iter().map(String::as_str)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let mut out = vec![];
+        let rows = vec![
+            vec!["0".to_string(), "1".to_string(), "[1, 2]".to_string()],
+            vec!["1".to_string(), "22".to_string(), "[]".to_string()],
+        ];
+        write_table(&mut out, &["entry", "a", "jagged"], &rows).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "entry | a  | jagged\n\
+             ------+----+-------\n\
+             0     | 1  | [1, 2]\n\
+             1     | 22 | []\n"
+        );
+    }
+}
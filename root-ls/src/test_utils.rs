@@ -0,0 +1,21 @@
+#![cfg(test)]
+
+use failure::Error;
+use futures::future::{BoxFuture, FutureExt};
+use root_io::Fetch;
+
+/// A file held in memory
+#[derive(Debug)]
+pub(crate) struct InMemory(pub(crate) Vec<u8>);
+
+impl Fetch for InMemory {
+    fn fetch(&self, start: u64, len: u64) -> BoxFuture<'_, Result<Vec<u8>, Error>> {
+        let bytes = self.0[start as usize..(start + len) as usize].to_vec();
+        async move { Ok(bytes) }.boxed()
+    }
+
+    fn size(&self) -> BoxFuture<'_, Result<u64, Error>> {
+        let len = self.0.len() as u64;
+        async move { Ok(len) }.boxed()
+    }
+}